@@ -0,0 +1,74 @@
+//! Runs the same workload (disjunction of the `reach` benchmark operands) on every `BddEngine`.
+
+use binary_decision_diagrams::engine::{
+    BddEngine, CuddEngine, LibBddEngine, MachineEngine, V2Engine, V3OooEngine, V4Engine,
+};
+use binary_decision_diagrams::v4::core::Bdd;
+use criterion::measurement::WallTime;
+use criterion::{criterion_group, criterion_main, BenchmarkGroup, Criterion};
+use std::cmp::max;
+use std::convert::TryFrom;
+
+/// The number of variables necessary to represent the given BDDs.
+fn variable_count(bdds: &[&Bdd]) -> u16 {
+    let mut count = 0;
+    for bdd in bdds {
+        for index in bdd.iter_indices().skip(2) {
+            count = max(count, u32::from(bdd.get_node(index).get_variable()) + 1);
+        }
+    }
+    u16::try_from(count).unwrap()
+}
+
+fn bench_engine<E: BddEngine>(group: &mut BenchmarkGroup<WallTime>, benchmark: &str, left: &Bdd, right: &Bdd) {
+    let mut engine = E::new(variable_count(&[left, right]));
+    let left = engine.import(left);
+    let right = engine.import(right);
+    group.bench_function(format!("{}/{}", E::NAME, benchmark), |b| {
+        b.iter(|| {
+            let result = engine.or(&left, &right);
+            engine.node_count(&result)
+        })
+    });
+}
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let mut benchmarks = Vec::new();
+    for file in std::fs::read_dir("./bench_inputs/reach").unwrap() {
+        let file = file.unwrap();
+        let path = file.path();
+        let file_name = path.file_name().unwrap().to_str().unwrap();
+        if let Some(bench_name) = file_name.strip_suffix(".or.left.bdd") {
+            benchmarks.push(bench_name.to_string());
+        }
+    }
+    // Actually do the benchmarks in some sensible order.
+    benchmarks.sort_by_cached_key(|name| {
+        let mut split = name.split('.');
+        split.next();
+        let size = split.next().unwrap();
+        size.parse::<usize>().unwrap()
+    });
+
+    let mut group = c.benchmark_group("engines");
+    group.sample_size(10);
+    for benchmark in &benchmarks {
+        let left_path = format!("./bench_inputs/reach/{}.or.left.bdd", benchmark);
+        let left = Bdd::try_from(std::fs::read_to_string(&left_path).unwrap().as_str()).unwrap();
+        let left = left.sort_preorder();
+        let right_path = format!("./bench_inputs/reach/{}.or.right.bdd", benchmark);
+        let right = Bdd::try_from(std::fs::read_to_string(&right_path).unwrap().as_str()).unwrap();
+        let right = right.sort_preorder();
+
+        bench_engine::<V4Engine>(&mut group, benchmark, &left, &right);
+        bench_engine::<V3OooEngine>(&mut group, benchmark, &left, &right);
+        bench_engine::<V2Engine>(&mut group, benchmark, &left, &right);
+        bench_engine::<MachineEngine>(&mut group, benchmark, &left, &right);
+        bench_engine::<LibBddEngine>(&mut group, benchmark, &left, &right);
+        bench_engine::<CuddEngine>(&mut group, benchmark, &left, &right);
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,40 @@
+//! Runs the `v4` apply on synthetic BDDs (see `verify::random_bdd`) stored in different node
+//! layouts, to measure how much the algorithms depend on the DFS pre-order of their inputs.
+
+use binary_decision_diagrams::v4::apply::{apply, And};
+use binary_decision_diagrams::verify::{change_layout, random_bdd, BddShape, Lcg, NodeLayout};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let layouts = [
+        NodeLayout::PreOrder,
+        NodeLayout::PostOrder,
+        NodeLayout::ReversedPreOrder,
+        NodeLayout::Shuffled,
+    ];
+
+    let mut group = c.benchmark_group("layouts");
+    group.sample_size(10);
+    for nodes in &[10_000, 100_000] {
+        let mut rng = Lcg::new(1234);
+        let shape = BddShape {
+            variables: 64,
+            nodes: *nodes,
+            ..BddShape::default()
+        };
+        let left = random_bdd(&shape, &mut rng);
+        // Two large random BDDs would have a huge product, so the right operand is small.
+        let right = random_bdd(&BddShape { nodes: 100, ..shape }, &mut rng);
+        for layout in &layouts {
+            let left = change_layout(&left, *layout, &mut rng);
+            let right = change_layout(&right, *layout, &mut rng);
+            group.bench_function(format!("{:?}/{}", layout, nodes), |b| {
+                b.iter(|| apply(&left, &right, And).0.get_size())
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,48 @@
+//! Compares `parallel_apply` with 1, 2, 4 and 8 threads on the `reach` benchmark operands.
+
+use binary_decision_diagrams::v4::apply::{parallel_apply, Or};
+use binary_decision_diagrams::v4::core::Bdd;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::convert::TryFrom;
+
+const THREADS: [usize; 4] = [1, 2, 4, 8];
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let mut benchmarks = Vec::new();
+    for file in std::fs::read_dir("./bench_inputs/reach").unwrap() {
+        let file = file.unwrap();
+        let path = file.path();
+        let file_name = path.file_name().unwrap().to_str().unwrap();
+        if let Some(bench_name) = file_name.strip_suffix(".or.left.bdd") {
+            benchmarks.push(bench_name.to_string());
+        }
+    }
+    // Actually do the benchmarks in some sensible order.
+    benchmarks.sort_by_cached_key(|name| {
+        let mut split = name.split('.');
+        split.next();
+        let size = split.next().unwrap();
+        size.parse::<usize>().unwrap()
+    });
+
+    let mut group = c.benchmark_group("parallel");
+    group.sample_size(10);
+    for benchmark in &benchmarks {
+        let left_path = format!("./bench_inputs/reach/{}.or.left.bdd", benchmark);
+        let left = Bdd::try_from(std::fs::read_to_string(&left_path).unwrap().as_str()).unwrap();
+        let left = left.sort_preorder();
+        let right_path = format!("./bench_inputs/reach/{}.or.right.bdd", benchmark);
+        let right = Bdd::try_from(std::fs::read_to_string(&right_path).unwrap().as_str()).unwrap();
+        let right = right.sort_preorder();
+
+        for threads in THREADS {
+            group.bench_with_input(BenchmarkId::new(benchmark, threads), &threads, |b, &threads| {
+                b.iter(|| parallel_apply(&left, &right, Or, threads).0.get_size())
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
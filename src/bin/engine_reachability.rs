@@ -0,0 +1,141 @@
+//! The reachability workload of `cudd_reachability`, executed by any `BddEngine`.
+//!
+//! Usage: `engine_reachability <engine> < model.aeon`, where `<engine>` is one of `v4`, `v3-ooo`,
+//! `v2`, `machine`, `lib-bdd` or `cudd` (default is `v4`).
+
+use binary_decision_diagrams::engine::{
+    BddEngine, CuddEngine, LibBddEngine, MachineEngine, V2Engine, V3OooEngine, V4Engine,
+};
+use biodivine_lib_param_bn::{BinaryOp, BooleanNetwork, FnUpdate};
+use std::convert::TryFrom;
+use std::io::Read;
+use std::time::SystemTime;
+
+fn main() {
+    let engine = std::env::args().nth(1).unwrap_or_else(|| "v4".to_string());
+    let mut buffer = String::new();
+    std::io::stdin().read_to_string(&mut buffer).unwrap();
+    let model = BooleanNetwork::try_from(buffer.as_str()).unwrap();
+
+    match engine.as_str() {
+        V4Engine::NAME => reachability::<V4Engine>(&model),
+        V3OooEngine::NAME => reachability::<V3OooEngine>(&model),
+        V2Engine::NAME => reachability::<V2Engine>(&model),
+        MachineEngine::NAME => reachability::<MachineEngine>(&model),
+        LibBddEngine::NAME => reachability::<LibBddEngine>(&model),
+        CuddEngine::NAME => reachability::<CuddEngine>(&model),
+        _ => panic!("Unknown engine: {}.", engine),
+    }
+}
+
+/// Repeatedly pick a vertex of the remaining state space and compute its forward
+/// reachable set, until the whole state space is covered.
+fn reachability<E: BddEngine>(model: &BooleanNetwork) {
+    let variable_count = u16::try_from(model.num_vars()).unwrap();
+    let mut engine = E::new(variable_count);
+    let update_functions: Vec<E::Bdd> = model
+        .variables()
+        .map(|v| {
+            let update = model.get_update_function(v).as_ref().unwrap();
+            fn_update_to_bdd(&mut engine, update)
+        })
+        .collect();
+
+    println!("Engine: {}", E::NAME);
+    let start = SystemTime::now();
+    let mut universe = engine.mk_true();
+    while !engine.is_false(&universe) {
+        println!("Universe size: {}", engine.node_count(&universe));
+
+        let mut i = 0;
+        let mut reachable = pick_a_vertex(&mut engine, &universe);
+        loop {
+            i += 1;
+            let mut done = true;
+
+            for variable in 0..variable_count {
+                let update = &update_functions[usize::from(variable)];
+                let successors = successors(&mut engine, &reachable, variable, update);
+                let successors = engine.and_not(&successors, &reachable);
+                let successors = engine.and(&successors, &universe);
+
+                if !engine.is_false(&successors) {
+                    done = false;
+                    reachable = engine.or(&successors, &reachable);
+                    let elapsed = start.elapsed().unwrap().as_millis();
+                    println!("({}) Iteration ({}), reach size: {}", elapsed, i, engine.node_count(&reachable));
+                }
+            }
+
+            println!("Iteration ({}), reach size: {}", i, engine.node_count(&reachable));
+
+            if done {
+                break;
+            }
+        }
+
+        universe = engine.and_not(&universe, &reachable);
+    }
+    println!("Elapsed: {}", start.elapsed().unwrap().as_millis());
+}
+
+/// The states reachable from `set` by asynchronously updating the given `variable`.
+fn successors<E: BddEngine>(engine: &mut E, set: &E::Bdd, variable: u16, update: &E::Bdd) -> E::Bdd {
+    let variable_bdd = engine.mk_var(variable);
+    let states_with_v = engine.and(set, &variable_bdd);
+    let states_with_not_v = engine.and_not(set, &variable_bdd);
+
+    let go_up = engine.and(&states_with_not_v, update);
+    let go_down = engine.and_not(&states_with_v, update);
+    let go_up = engine.exists(&go_up, variable);
+    let go_down = engine.exists(&go_down, variable);
+
+    let went_up = engine.and(&go_up, &variable_bdd);
+    let went_down = engine.and_not(&go_down, &variable_bdd);
+    engine.or(&went_up, &went_down)
+}
+
+/// Restrict the `set` to a single state by fixing one variable at a time.
+fn pick_a_vertex<E: BddEngine>(engine: &mut E, set: &E::Bdd) -> E::Bdd {
+    let mut candidates = set.clone();
+    for variable in 0..engine.variable_count() {
+        let variable_bdd = engine.mk_var(variable);
+        let mut next = engine.and_not(&candidates, &variable_bdd);
+        if engine.is_false(&next) {
+            next = engine.and(&candidates, &variable_bdd);
+        }
+        candidates = next;
+    }
+    candidates
+}
+
+fn fn_update_to_bdd<E: BddEngine>(engine: &mut E, update: &FnUpdate) -> E::Bdd {
+    match update {
+        FnUpdate::Const(value) => {
+            if *value {
+                engine.mk_true()
+            } else {
+                engine.mk_false()
+            }
+        }
+        FnUpdate::Param(_, _) => {
+            panic!("Parametrised functions not supported.")
+        }
+        FnUpdate::Var(id) => engine.mk_var(u16::try_from(usize::from(*id)).unwrap()),
+        FnUpdate::Not(update) => {
+            let result = fn_update_to_bdd(engine, update);
+            engine.not(&result)
+        }
+        FnUpdate::Binary(op, left, right) => {
+            let left = fn_update_to_bdd(engine, left);
+            let right = fn_update_to_bdd(engine, right);
+            match op {
+                BinaryOp::And => engine.and(&left, &right),
+                BinaryOp::Or => engine.or(&left, &right),
+                BinaryOp::Iff => engine.iff(&left, &right),
+                BinaryOp::Xor => engine.xor(&left, &right),
+                BinaryOp::Imp => engine.imp(&left, &right),
+            }
+        }
+    }
+}
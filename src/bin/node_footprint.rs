@@ -0,0 +1,64 @@
+use binary_decision_diagrams::v4::apply::{apply_with_config, ApplyConfig, Or};
+use binary_decision_diagrams::v4::core::{Bdd, Node, NodeIndex, Variable};
+use std::convert::TryFrom;
+use std::mem::size_of;
+use std::time::SystemTime;
+
+/// The size of a node before it was packed into 128 bits.
+const UNPACKED_NODE_SIZE: usize = size_of::<(Variable, NodeIndex, NodeIndex)>();
+
+/// Compare the memory footprint of the packed `Node` layout to the unpacked one on the `reach`
+/// benchmarks. The node memory of the operands and the result scales linearly with the node
+/// size. The peak memory of `apply` also includes the task cache, which does not store nodes.
+fn main() {
+    let mut benchmarks = Vec::new();
+    for file in std::fs::read_dir("./bench_inputs/reach").unwrap() {
+        let file = file.unwrap();
+        let path = file.path();
+        let file_name = path.file_name().unwrap().to_str().unwrap();
+        if let Some(bench_name) = file_name.strip_suffix(".or.left.bdd") {
+            benchmarks.push(bench_name.to_string());
+        }
+    }
+
+    // Actually do the benchmarks in some sensible order.
+    benchmarks.sort_by_cached_key(|name| {
+        let mut split = name.split('.');
+        split.next();
+        let size = split.next().unwrap();
+        size.parse::<usize>().unwrap()
+    });
+
+    println!("Node size: {} bytes (unpacked: {} bytes)", size_of::<Node>(), UNPACKED_NODE_SIZE);
+    println!("| Benchmark | Time (ms) | Operand nodes | Result nodes | Node memory (packed) | Node memory (unpacked) | Apply peak memory |");
+    println!("|---|---|---|---|---|---|---|");
+    for benchmark in benchmarks {
+        let left = load(&format!("./bench_inputs/reach/{}.or.left.bdd", benchmark));
+        let right = load(&format!("./bench_inputs/reach/{}.or.right.bdd", benchmark));
+
+        let start = SystemTime::now();
+        let (result, stats) = apply_with_config(&left, &right, Or, &ApplyConfig::default()).unwrap();
+        let elapsed = start.elapsed().unwrap().as_millis();
+
+        let operand_nodes = (left.get_size() + right.get_size()) as usize;
+        let nodes = operand_nodes + result.get_size() as usize;
+        println!(
+            "| {} | {} | {} | {} | {} | {} | {} |",
+            benchmark,
+            elapsed,
+            operand_nodes,
+            result.get_size(),
+            nodes * size_of::<Node>(),
+            nodes * UNPACKED_NODE_SIZE,
+            stats.peak_memory,
+        );
+    }
+}
+
+fn load(path: &str) -> Bdd {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|it| Bdd::try_from(it.as_str()).ok())
+        .unwrap()
+        .sort_preorder()
+}
@@ -1,7 +1,7 @@
 #![allow(unused_imports)]
 
 use binary_decision_diagrams::v4::core::Bdd;
-use binary_decision_diagrams::v4::apply::apply;
+use binary_decision_diagrams::v4::apply::{apply_with_config, ApplyConfig, Or};
 use std::convert::TryFrom;
 use perfcnt::linux::{PerfCounterBuilderLinux, HardwareEventType};
 use criterion::measurement::Measurement;
@@ -33,6 +33,13 @@ fn new_branches_counter() -> Perf {
 }
 
 fn main() {
+    // Run with `--prefetch` to measure the prefetching variant of apply.
+    let config = ApplyConfig {
+        prefetch: std::env::args().any(|arg| arg == "--prefetch"),
+        ..ApplyConfig::default()
+    };
+    println!("Prefetching: {}", config.prefetch);
+
     let mut benchmarks = Vec::new();
     for file in std::fs::read_dir("./bench_inputs/reach").unwrap() {
         let file = file.unwrap();
@@ -74,7 +81,7 @@ fn main() {
 
         println!("warmup run...");
         //for _ in 0..10 {
-            benchmark_code(&left, &right);
+            benchmark_code(&left, &right, &config);
         //}
 
         let cycles = new_cpu_cycles_counter();
@@ -92,7 +99,7 @@ fn main() {
         let i_branch_misses = branch_misses.start();
 
         let start = SystemTime::now();
-        let (product_nodes, product_tasks) = benchmark_code(&left, &right);
+        let (product_nodes, product_tasks) = benchmark_code(&left, &right, &config);
         let elapsed = start.elapsed().unwrap().as_millis();
 
         let cycles = cycles.end(i_cycles);
@@ -125,8 +132,10 @@ fn main() {
     }
 }
 
-fn benchmark_code(left: &Bdd, right: &Bdd) -> (usize, usize) {
-    let (created, counted) = apply(left, right);
+fn benchmark_code(left: &Bdd, right: &Bdd, config: &ApplyConfig) -> (usize, usize) {
+    let (result, stats) = apply_with_config(left, right, Or, config).unwrap();
+    let counted = stats.tasks_expanded;
+    let created = result.get_size() as usize;
     println!("Counted {} nodes, created {} nodes.", counted, created);
     (created, counted)
 }
\ No newline at end of file
@@ -0,0 +1,220 @@
+//! Conversions between the `Bdd` representations of this crate (`v2::Bdd`,
+//! `v3::core::bdd::Bdd`, `v4::core::Bdd` and `machine::Bdd`).
+//!
+//! Every representation can be converted into every other one using `TryFrom<&Bdd>`. The
+//! conversion preserves the order of the nodes, so a `Bdd` sorted in DFS pre-order stays sorted.
+//! It fails with a `ConversionError` if a variable or a node index does not fit into the target
+//! representation (e.g. a 32-bit variable of a `v4::core::Bdd` into the 16-bit `machine::Bdd`).
+//!
+//! Note that `v3` and `v4` do not store the number of variables. When converting into `v2`
+//! or `machine`, the number of variables is the largest variable of the `Bdd` plus one.
+
+use std::cmp::max;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+/// The reason why a `Bdd` cannot be converted into another representation.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ConversionError {
+    /// The `variable` is larger than the largest variable (`limit`) of the target representation.
+    VariableOutOfRange { variable: u32, limit: u32 },
+    /// The node `index` is larger than the largest node index (`limit`) of the target
+    /// representation.
+    IndexOutOfRange { index: u64, limit: u64 },
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConversionError::VariableOutOfRange { variable, limit } => {
+                write!(f, "Variable {} is out of range (largest allowed is {}).", variable, limit)
+            }
+            ConversionError::IndexOutOfRange { index, limit } => {
+                write!(f, "Node index {} is out of range (largest allowed is {}).", index, limit)
+            }
+        }
+    }
+}
+
+/// **(internal)** A representation-independent copy of a `Bdd`.
+///
+/// The first node (and the second node, unless the `Bdd` is `false`) are the terminals, which
+/// have no variable. The root is the last node. Every representation implements
+/// `to_raw_bdd` and `from_raw_bdd`, and the conversions are then composed from these two.
+pub(crate) struct RawBdd {
+    pub variable_count: u32,
+    pub nodes: Vec<(Option<u32>, u64, u64)>,
+}
+
+impl RawBdd {
+    /// Create a `RawBdd` from the decision nodes (the terminal nodes are skipped) given by
+    /// `node_count` and `node`. The number of variables is at least `variable_count`.
+    pub fn new<F>(variable_count: u32, node_count: usize, node: F) -> RawBdd
+    where
+        F: Fn(usize) -> (u32, u64, u64),
+    {
+        let mut variable_count = variable_count;
+        let mut nodes = vec![(None, 0, 0)];
+        if node_count > 1 {
+            nodes.push((None, 1, 1));
+        }
+        for index in 2..node_count {
+            let (variable, low, high) = node(index);
+            variable_count = max(variable_count, variable + 1);
+            nodes.push((Some(variable), low, high));
+        }
+        RawBdd { variable_count, nodes }
+    }
+
+    /// Translate the nodes into a target representation, using the given `zero` and `one`
+    /// terminals and a function which packs the decision nodes.
+    pub fn map_nodes<N, F>(&self, zero: N, one: N, pack: F) -> Vec<N>
+    where
+        N: Clone,
+        F: Fn(u32, u64, u64) -> N,
+    {
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(index, (variable, low, high))| match variable {
+                Some(variable) => pack(*variable, *low, *high),
+                None if index == 0 => zero.clone(),
+                None => one.clone(),
+            })
+            .collect()
+    }
+
+    /// Check that all variables are at most `variable_limit` and all node indices
+    /// are at most `index_limit`.
+    pub fn check_limits(&self, variable_limit: u32, index_limit: u64) -> Result<(), ConversionError> {
+        let root = (self.nodes.len() - 1) as u64;
+        if root > index_limit {
+            return Err(ConversionError::IndexOutOfRange { index: root, limit: index_limit });
+        }
+        for (variable, low, high) in &self.nodes {
+            if let Some(variable) = variable {
+                if *variable > variable_limit {
+                    return Err(ConversionError::VariableOutOfRange { variable: *variable, limit: variable_limit });
+                }
+            }
+            let index = max(*low, *high);
+            if index > index_limit {
+                return Err(ConversionError::IndexOutOfRange { index, limit: index_limit });
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of nodes on the longest path from the root to a terminal (terminal included),
+    /// or zero for constant BDDs.
+    pub fn height(&self) -> usize {
+        if self.nodes.len() <= 2 {
+            return 0;
+        }
+        // Run a post-order DFS, the same as `v4::core::Bdd::recompute_height`.
+        let mut height = vec![0usize; self.nodes.len()];
+        let mut stack = vec![(self.nodes.len() - 1, false)];
+        while let Some((index, expanded)) = stack.pop() {
+            let (variable, low, high) = self.nodes[index];
+            if variable.is_none() {
+                height[index] = 1;
+                continue;
+            }
+            let (low, high) = (low as usize, high as usize);
+            if expanded {
+                height[index] = max(height[low], height[high]) + 1;
+            } else if height[index] == 0 {
+                stack.push((index, true));
+                if height[high] == 0 {
+                    stack.push((high, false));
+                }
+                if height[low] == 0 {
+                    stack.push((low, false));
+                }
+            }
+        }
+        height[self.nodes.len() - 1]
+    }
+}
+
+/// Implement `TryFrom<&$from> for $to` using the raw representation.
+macro_rules! raw_conversion {
+    ($from:ty => $($to:ty),+) => {
+        $(
+            impl TryFrom<&$from> for $to {
+                type Error = ConversionError;
+
+                fn try_from(bdd: &$from) -> Result<$to, ConversionError> {
+                    <$to>::from_raw_bdd(&bdd.to_raw_bdd())
+                }
+            }
+        )+
+    };
+}
+
+raw_conversion!(crate::v2::Bdd => crate::v3::core::bdd::Bdd, crate::v4::core::Bdd, crate::machine::Bdd);
+raw_conversion!(crate::v3::core::bdd::Bdd => crate::v2::Bdd, crate::v4::core::Bdd, crate::machine::Bdd);
+raw_conversion!(crate::v4::core::Bdd => crate::v2::Bdd, crate::v3::core::bdd::Bdd, crate::machine::Bdd);
+raw_conversion!(crate::machine::Bdd => crate::v2::Bdd, crate::v3::core::bdd::Bdd, crate::v4::core::Bdd);
+
+#[cfg(test)]
+mod tests {
+    use super::ConversionError;
+    use crate::v3::core::packed_bdd_node::PackedBddNode;
+    use crate::v4::core::{Bdd, Variable};
+    use crate::v4::test_util::nodes;
+    use crate::verify::{FunctionTable, Lcg};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn conversion_round_trip() {
+        let mut rng = Lcg::new(47);
+        for vars in &[0u32, 1, 5, 12] {
+            for density in &[0u64, 50, 100] {
+                let bdd = FunctionTable::random(*vars, *density, &mut rng).to_bdd();
+                let v2 = crate::v2::Bdd::try_from(&bdd).unwrap();
+                let v3 = crate::v3::core::bdd::Bdd::try_from(&v2).unwrap();
+                let machine = crate::machine::Bdd::try_from(&v3).unwrap();
+                let back = Bdd::try_from(&machine).unwrap();
+                assert_eq!(nodes(&bdd), nodes(&back));
+                assert_eq!(bdd.get_height(), back.get_height());
+                assert_eq!(bdd.get_size() as usize, v2.node_count());
+                assert_eq!(bdd.get_size() as usize, v3.node_count());
+                assert_eq!(bdd.get_size() as usize, machine.node_count());
+            }
+        }
+
+        // The variable count is preserved between `v2` and `machine`.
+        let mut machine = crate::machine::Bdd::new_true();
+        machine.ensure_variable_count(10);
+        let v2 = crate::v2::Bdd::try_from(&machine).unwrap();
+        assert_eq!(10, v2.variable_count());
+        assert_eq!(10, crate::machine::Bdd::try_from(&v2).unwrap().variable_count());
+    }
+
+    #[test]
+    fn conversion_errors() {
+        let literal = Bdd::new_literal(Variable::from(70_000u32), true);
+        let expected = ConversionError::VariableOutOfRange {
+            variable: 70_000,
+            limit: u32::from(u16::MAX) - 1,
+        };
+        assert_eq!(Err(expected), crate::machine::Bdd::try_from(&literal).map(|_| ()));
+        assert_eq!(Err(expected), crate::v2::Bdd::try_from(&literal).map(|_| ()));
+        assert!(crate::v3::core::bdd::Bdd::try_from(&literal).is_ok());
+
+        // A (malformed) `v3` BDD with a link that does not fit into 48 bits.
+        let nodes = vec![
+            PackedBddNode::ZERO,
+            PackedBddNode::ONE,
+            PackedBddNode::pack(0u32.into(), (1u64 << 50).into(), 1u64.into()),
+        ];
+        let large = unsafe { crate::v3::core::bdd::Bdd::from_raw_nodes(nodes) };
+        match crate::v4::core::Bdd::try_from(&large) {
+            Err(ConversionError::IndexOutOfRange { index, .. }) => assert_eq!(1 << 50, index),
+            _ => panic!("Index out of range not detected."),
+        }
+        assert!(crate::machine::Bdd::try_from(&large).is_err());
+        assert!(!format!("{}", expected).is_empty());
+    }
+}
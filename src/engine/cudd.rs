@@ -0,0 +1,129 @@
+use super::BddEngine;
+use cudd_sys::cudd::{
+    Cudd_DagSize, Cudd_DisableGarbageCollection, Cudd_Init, Cudd_Not, Cudd_Quit, Cudd_ReadLogicZero, Cudd_ReadOne,
+    Cudd_Ref, Cudd_bddAnd, Cudd_bddExistAbstract, Cudd_bddIte, Cudd_bddIthVar, Cudd_bddOr, Cudd_bddXnor,
+    Cudd_bddXor, CUDD_CACHE_SLOTS, CUDD_UNIQUE_SLOTS,
+};
+use cudd_sys::{DdManager, DdNode};
+use std::os::raw::{c_int, c_uint};
+
+/// A BDD node of a `CuddEngine`. Only valid while the engine exists.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CuddBdd(*mut DdNode);
+
+/// An adapter for the CUDD library (using `cudd_sys`).
+///
+/// Same as in the `cudd_reachability` binary, garbage collection is disabled, so all nodes
+/// stay valid until the engine is dropped. This avoids reference counting in the workloads,
+/// but the memory is only released together with the whole manager.
+pub struct CuddEngine {
+    manager: *mut DdManager,
+    variable_count: u16,
+}
+
+impl CuddEngine {
+    /// **(internal)** Reference a freshly computed node and wrap it.
+    fn wrap(&self, node: *mut DdNode) -> CuddBdd {
+        assert!(!node.is_null(), "CUDD operation failed.");
+        unsafe { Cudd_Ref(node) };
+        CuddBdd(node)
+    }
+}
+
+impl Drop for CuddEngine {
+    fn drop(&mut self) {
+        unsafe { Cudd_Quit(self.manager) };
+    }
+}
+
+impl BddEngine for CuddEngine {
+    type Bdd = CuddBdd;
+    const NAME: &'static str = "cudd";
+
+    fn new(variable_count: u16) -> CuddEngine {
+        let manager = unsafe {
+            Cudd_Init(c_uint::from(variable_count), 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0)
+        };
+        unsafe { Cudd_DisableGarbageCollection(manager) };
+        CuddEngine { manager, variable_count }
+    }
+
+    fn variable_count(&self) -> u16 {
+        self.variable_count
+    }
+
+    fn mk_false(&mut self) -> CuddBdd {
+        self.wrap(unsafe { Cudd_ReadLogicZero(self.manager) })
+    }
+
+    fn mk_true(&mut self) -> CuddBdd {
+        self.wrap(unsafe { Cudd_ReadOne(self.manager) })
+    }
+
+    fn mk_literal(&mut self, variable: u16, value: bool) -> CuddBdd {
+        let node = unsafe { Cudd_bddIthVar(self.manager, c_int::from(variable)) };
+        if value {
+            self.wrap(node)
+        } else {
+            self.wrap(unsafe { Cudd_Not(node) })
+        }
+    }
+
+    fn not(&mut self, bdd: &CuddBdd) -> CuddBdd {
+        self.wrap(unsafe { Cudd_Not(bdd.0) })
+    }
+
+    fn and(&mut self, left: &CuddBdd, right: &CuddBdd) -> CuddBdd {
+        self.wrap(unsafe { Cudd_bddAnd(self.manager, left.0, right.0) })
+    }
+
+    fn or(&mut self, left: &CuddBdd, right: &CuddBdd) -> CuddBdd {
+        self.wrap(unsafe { Cudd_bddOr(self.manager, left.0, right.0) })
+    }
+
+    fn xor(&mut self, left: &CuddBdd, right: &CuddBdd) -> CuddBdd {
+        self.wrap(unsafe { Cudd_bddXor(self.manager, left.0, right.0) })
+    }
+
+    fn iff(&mut self, left: &CuddBdd, right: &CuddBdd) -> CuddBdd {
+        self.wrap(unsafe { Cudd_bddXnor(self.manager, left.0, right.0) })
+    }
+
+    fn imp(&mut self, left: &CuddBdd, right: &CuddBdd) -> CuddBdd {
+        self.wrap(unsafe { Cudd_bddIte(self.manager, left.0, right.0, Cudd_ReadOne(self.manager)) })
+    }
+
+    fn and_not(&mut self, left: &CuddBdd, right: &CuddBdd) -> CuddBdd {
+        self.wrap(unsafe { Cudd_bddAnd(self.manager, left.0, Cudd_Not(right.0)) })
+    }
+
+    fn exists(&mut self, bdd: &CuddBdd, variable: u16) -> CuddBdd {
+        // A single positive variable is also a cube of that variable.
+        let cube = unsafe { Cudd_bddIthVar(self.manager, c_int::from(variable)) };
+        self.wrap(unsafe { Cudd_bddExistAbstract(self.manager, bdd.0, cube) })
+    }
+
+    fn node_count(&self, bdd: &CuddBdd) -> usize {
+        unsafe { Cudd_DagSize(bdd.0) as usize }
+    }
+
+    fn is_false(&self, bdd: &CuddBdd) -> bool {
+        bdd.0 == unsafe { Cudd_ReadLogicZero(self.manager) }
+    }
+
+    fn is_equal(&mut self, left: &CuddBdd, right: &CuddBdd) -> bool {
+        // CUDD diagrams are canonical.
+        left == right
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::check_engine;
+    use super::CuddEngine;
+
+    #[test]
+    fn cudd_engine_matches_truth_tables() {
+        check_engine::<CuddEngine>();
+    }
+}
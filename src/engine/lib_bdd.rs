@@ -0,0 +1,75 @@
+use super::BddEngine;
+use biodivine_lib_bdd::{Bdd, BddVariable, BddVariableSet};
+
+/// An adapter for `biodivine_lib_bdd`.
+pub struct LibBddEngine {
+    variable_set: BddVariableSet,
+    variables: Vec<BddVariable>,
+}
+
+impl BddEngine for LibBddEngine {
+    type Bdd = Bdd;
+    const NAME: &'static str = "lib-bdd";
+
+    fn new(variable_count: u16) -> LibBddEngine {
+        let variable_set = BddVariableSet::new_anonymous(variable_count);
+        let variables = variable_set.variables();
+        LibBddEngine { variable_set, variables }
+    }
+
+    fn variable_count(&self) -> u16 {
+        self.variable_set.num_vars()
+    }
+
+    fn mk_false(&mut self) -> Bdd {
+        self.variable_set.mk_false()
+    }
+
+    fn mk_true(&mut self) -> Bdd {
+        self.variable_set.mk_true()
+    }
+
+    fn mk_literal(&mut self, variable: u16, value: bool) -> Bdd {
+        self.variable_set.mk_literal(self.variables[usize::from(variable)], value)
+    }
+
+    fn not(&mut self, bdd: &Bdd) -> Bdd {
+        bdd.not()
+    }
+
+    fn and(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        left.and(right)
+    }
+
+    fn or(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        left.or(right)
+    }
+
+    fn xor(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        left.xor(right)
+    }
+
+    fn iff(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        left.iff(right)
+    }
+
+    fn imp(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        left.imp(right)
+    }
+
+    fn and_not(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        left.and_not(right)
+    }
+
+    fn exists(&mut self, bdd: &Bdd, variable: u16) -> Bdd {
+        bdd.var_project(self.variables[usize::from(variable)])
+    }
+
+    fn node_count(&self, bdd: &Bdd) -> usize {
+        bdd.size()
+    }
+
+    fn is_false(&self, bdd: &Bdd) -> bool {
+        bdd.is_false()
+    }
+}
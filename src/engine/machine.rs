@@ -0,0 +1,89 @@
+use super::BddEngine;
+use crate::convert::RawBdd;
+use crate::machine::Bdd;
+use std::cmp::max;
+
+/// The `machine` implementation. Negation and restriction are computed on a `RawBdd`.
+pub struct MachineEngine {
+    variable_count: u16,
+}
+
+impl MachineEngine {
+    /// **(internal)** Create a `Bdd` from a `RawBdd` which admits all variables of this engine.
+    fn build(&self, mut raw: RawBdd) -> Bdd {
+        raw.variable_count = max(raw.variable_count, u32::from(self.variable_count));
+        Bdd::from_raw_bdd(&raw).unwrap()
+    }
+}
+
+impl BddEngine for MachineEngine {
+    type Bdd = Bdd;
+    const NAME: &'static str = "machine";
+
+    fn new(variable_count: u16) -> MachineEngine {
+        MachineEngine { variable_count }
+    }
+
+    fn variable_count(&self) -> u16 {
+        self.variable_count
+    }
+
+    fn mk_false(&mut self) -> Bdd {
+        self.build(RawBdd::constant(0, false))
+    }
+
+    fn mk_true(&mut self) -> Bdd {
+        self.build(RawBdd::constant(0, true))
+    }
+
+    fn mk_literal(&mut self, variable: u16, value: bool) -> Bdd {
+        self.build(RawBdd::literal(0, u32::from(variable), value))
+    }
+
+    fn not(&mut self, bdd: &Bdd) -> Bdd {
+        self.build(bdd.to_raw_bdd().not())
+    }
+
+    fn and(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        left.and(right)
+    }
+
+    fn or(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        left.or(right)
+    }
+
+    fn xor(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        left.xor(right)
+    }
+
+    fn iff(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        left.iff(right)
+    }
+
+    fn imp(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        left.imp(right)
+    }
+
+    fn and_not(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        left.and_not(right)
+    }
+
+    fn exists(&mut self, bdd: &Bdd, variable: u16) -> Bdd {
+        let raw = bdd.to_raw_bdd();
+        let low = self.build(raw.restrict(u32::from(variable), false));
+        let high = self.build(raw.restrict(u32::from(variable), true));
+        low.or(&high)
+    }
+
+    fn node_count(&self, bdd: &Bdd) -> usize {
+        bdd.node_count()
+    }
+
+    fn is_false(&self, bdd: &Bdd) -> bool {
+        bdd.node_count() == 1
+    }
+
+    fn import(&mut self, bdd: &crate::v4::core::Bdd) -> Bdd {
+        self.build(bdd.to_raw_bdd())
+    }
+}
@@ -0,0 +1,240 @@
+//! A common interface for all BDD implementations of this crate and the external libraries
+//! we compare against (`biodivine_lib_bdd` and CUDD).
+//!
+//! A workload written against `BddEngine` can be executed by every implementation without
+//! changes. All engines use the same variable ordering: the (numerically) smallest variable
+//! is at the top of the diagram.
+//!
+//! Some of the crate representations only implement a subset of the operations natively.
+//! The rest is computed using the `RawBdd` representation from `crate::convert` (negation,
+//! restriction), so these operations are not indicative of the engine performance:
+//!
+//!  - `V4Engine`: native binary operations (through an `ApplyContext`) and negation.
+//!  - `V3OooEngine`: only the disjunction is native (`v3::core::ooo::apply`). The remaining
+//!    binary operations are derived from disjunction and negation.
+//!  - `V2Engine` and `MachineEngine`: native binary operations.
+//!
+//! Existential quantification is always computed as `f[x = 0] | f[x = 1]`, where the
+//! disjunction is native.
+
+use crate::v4::core::{Bdd, NodeIndex};
+use crate::IntoIndex;
+use std::convert::TryFrom;
+
+mod cudd;
+mod lib_bdd;
+mod machine;
+mod raw;
+mod v2;
+mod v3;
+mod v4;
+
+pub use self::cudd::{CuddBdd, CuddEngine};
+pub use self::lib_bdd::LibBddEngine;
+pub use self::machine::MachineEngine;
+pub use self::v2::V2Engine;
+pub use self::v3::V3OooEngine;
+pub use self::v4::V4Engine;
+
+/// A BDD implementation which can create and manipulate `Self::Bdd` objects.
+///
+/// All `Self::Bdd` objects of one engine must be created by that engine (e.g. CUDD nodes belong
+/// to a single manager). Variables are numbered from `0` to `variable_count() - 1`.
+pub trait BddEngine {
+    /// The type of BDD handled by this engine.
+    type Bdd: Clone;
+
+    /// A short human-readable name of the engine.
+    const NAME: &'static str;
+
+    /// Create a new engine which supports the given number of variables.
+    fn new(variable_count: u16) -> Self
+    where
+        Self: Sized;
+
+    /// The number of variables supported by this engine.
+    fn variable_count(&self) -> u16;
+
+    /// A BDD representing the `false` function.
+    fn mk_false(&mut self) -> Self::Bdd;
+
+    /// A BDD representing the `true` function.
+    fn mk_true(&mut self) -> Self::Bdd;
+
+    /// A BDD representing the literal `variable = value`.
+    fn mk_literal(&mut self, variable: u16, value: bool) -> Self::Bdd;
+
+    /// A BDD representing the positive literal of `variable`.
+    fn mk_var(&mut self, variable: u16) -> Self::Bdd {
+        self.mk_literal(variable, true)
+    }
+
+    /// Logical negation.
+    fn not(&mut self, bdd: &Self::Bdd) -> Self::Bdd;
+
+    /// Logical conjunction.
+    fn and(&mut self, left: &Self::Bdd, right: &Self::Bdd) -> Self::Bdd;
+
+    /// Logical disjunction.
+    fn or(&mut self, left: &Self::Bdd, right: &Self::Bdd) -> Self::Bdd;
+
+    /// Exclusive disjunction.
+    fn xor(&mut self, left: &Self::Bdd, right: &Self::Bdd) -> Self::Bdd;
+
+    /// Logical equivalence.
+    fn iff(&mut self, left: &Self::Bdd, right: &Self::Bdd) -> Self::Bdd;
+
+    /// Logical implication (`left => right`).
+    fn imp(&mut self, left: &Self::Bdd, right: &Self::Bdd) -> Self::Bdd;
+
+    /// Conjunction with a negated `right` operand (i.e. set difference).
+    fn and_not(&mut self, left: &Self::Bdd, right: &Self::Bdd) -> Self::Bdd;
+
+    /// Existential quantification of a single `variable`.
+    fn exists(&mut self, bdd: &Self::Bdd, variable: u16) -> Self::Bdd;
+
+    /// The number of nodes of the BDD, as reported by the implementation. Note that CUDD uses
+    /// complement edges and a single terminal, and `v2` does not always produce minimal BDDs,
+    /// so their node counts are not directly comparable.
+    fn node_count(&self, bdd: &Self::Bdd) -> usize;
+
+    /// True if the BDD represents the `false` function.
+    fn is_false(&self, bdd: &Self::Bdd) -> bool;
+
+    /// True if the two BDDs represent the same function.
+    ///
+    /// By default, this computes the `xor` of the two BDDs, because not all implementations
+    /// produce structurally canonical diagrams.
+    fn is_equal(&mut self, left: &Self::Bdd, right: &Self::Bdd) -> bool {
+        let difference = self.xor(left, right);
+        self.is_false(&difference)
+    }
+
+    /// Import a `v4::core::Bdd` (e.g. loaded from a file) into this engine. All its variables
+    /// must be smaller than `variable_count()`.
+    ///
+    /// By default, the BDD is rebuilt bottom-up using `(x & high) | (!x & low)` for every node.
+    fn import(&mut self, bdd: &Bdd) -> Self::Bdd {
+        if bdd.is_zero() {
+            return self.mk_false();
+        }
+        if bdd.is_one() {
+            return self.mk_true();
+        }
+        // Results of already imported nodes (`None` if not imported yet).
+        let mut imported: Vec<Option<Self::Bdd>> = vec![None; bdd.get_size() as usize];
+        imported[0] = Some(self.mk_false());
+        imported[1] = Some(self.mk_true());
+        let mut stack = vec![bdd.get_root_index()];
+        while let Some(index) = stack.pop() {
+            let i = index.into_index();
+            if imported[i].is_some() {
+                continue;
+            }
+            let (variable, low, high) = bdd.get_node(index).unpack();
+            let (low_i, high_i) = (low.into_index(), high.into_index());
+            match (&imported[low_i], &imported[high_i]) {
+                (Some(low), Some(high)) => {
+                    let (low, high) = (low.clone(), high.clone());
+                    let variable = u16::try_from(u32::from(variable)).expect("Variable out of range.");
+                    let positive = self.mk_literal(variable, true);
+                    let negative = self.mk_literal(variable, false);
+                    let high = self.and(&positive, &high);
+                    let low = self.and(&negative, &low);
+                    imported[i] = Some(self.or(&low, &high));
+                }
+                _ => {
+                    stack.push(index);
+                    push_if_missing(&mut stack, &imported, high);
+                    push_if_missing(&mut stack, &imported, low);
+                }
+            }
+        }
+        imported[bdd.get_root_index().into_index()].take().unwrap()
+    }
+}
+
+/// **(internal)** Push the `index` to the `stack` if it has no `imported` result yet.
+fn push_if_missing<T>(stack: &mut Vec<NodeIndex>, imported: &[Option<T>], index: NodeIndex) {
+    let i = index.into_index();
+    if imported[i].is_none() {
+        stack.push(index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BddEngine, LibBddEngine, MachineEngine, V2Engine, V3OooEngine, V4Engine};
+    use crate::verify::{FunctionTable, Lcg};
+
+    /// Compare all operations of the engine `E` with the results computed on truth tables.
+    pub(super) fn check_engine<E: BddEngine>() {
+        let variables = 6u32;
+        let mut engine = E::new(variables as u16);
+        assert_eq!(variables as u16, engine.variable_count());
+        let mut rng = Lcg::new(17);
+        let import = |engine: &mut E, table: &FunctionTable| engine.import(&table.to_bdd());
+        let zero = FunctionTable::constant(variables, false);
+        let one = FunctionTable::constant(variables, true);
+
+        let false_bdd = engine.mk_false();
+        let true_bdd = engine.mk_true();
+        assert!(engine.is_false(&false_bdd));
+        assert!(!engine.is_false(&true_bdd));
+        let expected = import(&mut engine, &zero);
+        assert!(engine.is_equal(&false_bdd, &expected));
+        let expected = import(&mut engine, &one);
+        assert!(engine.is_equal(&true_bdd, &expected));
+
+        for variable in 0..variables {
+            let table = FunctionTable::literal(variables, variable, true);
+            let literal = engine.mk_var(variable as u16);
+            let expected = import(&mut engine, &table);
+            assert!(engine.is_equal(&literal, &expected));
+            let literal = engine.mk_literal(variable as u16, false);
+            assert!(!engine.is_equal(&literal, &expected));
+        }
+
+        for (l_density, r_density) in &[(50, 50), (10, 90), (0, 30), (100, 50), (70, 70)] {
+            let l_table = FunctionTable::random(variables, *l_density, &mut rng);
+            let r_table = FunctionTable::random(variables, *r_density, &mut rng);
+            let left = import(&mut engine, &l_table);
+            let right = import(&mut engine, &r_table);
+            assert!(engine.node_count(&left) > 0);
+
+            let expect = |function: fn(bool, bool) -> bool| l_table.apply(&r_table, function);
+            let checks: Vec<(FunctionTable, E::Bdd)> = vec![
+                (expect(|l, r| l && r), engine.and(&left, &right)),
+                (expect(|l, r| l || r), engine.or(&left, &right)),
+                (expect(|l, r| l ^ r), engine.xor(&left, &right)),
+                (expect(|l, r| l == r), engine.iff(&left, &right)),
+                (expect(|l, r| !l || r), engine.imp(&left, &right)),
+                (expect(|l, r| l && !r), engine.and_not(&left, &right)),
+                (expect(|l, _| !l), engine.not(&left)),
+            ];
+            for (table, result) in checks {
+                let expected = import(&mut engine, &table);
+                assert!(engine.is_equal(&result, &expected));
+            }
+
+            for variable in 0..variables {
+                let bit = 1 << (variables - 1 - variable);
+                let table = FunctionTable::from_fn(variables, |i| {
+                    l_table.get(i & !bit) || l_table.get(i | bit)
+                });
+                let result = engine.exists(&left, variable as u16);
+                let expected = import(&mut engine, &table);
+                assert!(engine.is_equal(&result, &expected));
+            }
+        }
+    }
+
+    #[test]
+    fn engines_match_truth_tables() {
+        check_engine::<V4Engine>();
+        check_engine::<V3OooEngine>();
+        check_engine::<V2Engine>();
+        check_engine::<MachineEngine>();
+        check_engine::<LibBddEngine>();
+    }
+}
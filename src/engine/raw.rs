@@ -0,0 +1,147 @@
+//! **(internal)** Operations on `RawBdd` which are missing in some of the representations
+//! of this crate. They are used to implement the `BddEngine` trait uniformly.
+
+use crate::convert::RawBdd;
+use fxhash::FxHashMap;
+use std::cmp::max;
+
+impl RawBdd {
+    /// A constant `RawBdd` (with the given number of variables).
+    pub fn constant(variable_count: u32, value: bool) -> RawBdd {
+        let mut nodes = vec![(None, 0, 0)];
+        if value {
+            nodes.push((None, 1, 1));
+        }
+        RawBdd { variable_count, nodes }
+    }
+
+    /// A `RawBdd` of the literal `variable = value`.
+    pub fn literal(variable_count: u32, variable: u32, value: bool) -> RawBdd {
+        let (low, high) = if value { (0, 1) } else { (1, 0) };
+        RawBdd {
+            variable_count: max(variable_count, variable + 1),
+            nodes: vec![(None, 0, 0), (None, 1, 1), (Some(variable), low, high)],
+        }
+    }
+
+    /// True if this `RawBdd` represents the `false` function.
+    pub fn is_false(&self) -> bool {
+        self.nodes.len() == 1
+    }
+
+    /// The negation of this `RawBdd`, obtained by swapping the two terminals.
+    pub fn not(&self) -> RawBdd {
+        if self.nodes.len() <= 2 {
+            return RawBdd::constant(self.variable_count, self.is_false());
+        }
+        let swap = |link: u64| if link < 2 { 1 - link } else { link };
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|(variable, low, high)| match variable {
+                Some(_) => (*variable, swap(*low), swap(*high)),
+                None => (*variable, *low, *high),
+            })
+            .collect();
+        RawBdd {
+            variable_count: self.variable_count,
+            nodes,
+        }
+    }
+
+    /// The restriction of this `RawBdd` to `variable = value`.
+    ///
+    /// The result is reduced (assuming this `RawBdd` is reduced) and its nodes are
+    /// sorted in DFS post-order.
+    pub fn restrict(&self, variable: u32, value: bool) -> RawBdd {
+        // Maps indices of this `RawBdd` to indices of the result (`u64::MAX` if not computed).
+        let mut result = vec![u64::MAX; self.nodes.len()];
+        let mut unique: FxHashMap<(u32, u64, u64), u64> = FxHashMap::default();
+        let mut nodes = vec![(None, 0, 0), (None, 1, 1)];
+        result[0] = 0;
+        if self.nodes.len() > 1 {
+            result[1] = 1;
+        }
+
+        let mut stack = vec![self.nodes.len() - 1];
+        while let Some(index) = stack.pop() {
+            if result[index] != u64::MAX {
+                continue;
+            }
+            let (node_variable, low, high) = self.nodes[index];
+            let node_variable = node_variable.unwrap();
+            let (low, high) = (low as usize, high as usize);
+            if node_variable == variable {
+                // Skip the node entirely, only the selected child is needed.
+                let child = if value { high } else { low };
+                if result[child] == u64::MAX {
+                    stack.push(index);
+                    stack.push(child);
+                } else {
+                    result[index] = result[child];
+                }
+            } else if result[low] == u64::MAX || result[high] == u64::MAX {
+                stack.push(index);
+                if result[high] == u64::MAX {
+                    stack.push(high);
+                }
+                if result[low] == u64::MAX {
+                    stack.push(low);
+                }
+            } else if result[low] == result[high] {
+                result[index] = result[low];
+            } else {
+                let key = (node_variable, result[low], result[high]);
+                let new_index = *unique.entry(key).or_insert_with(|| {
+                    nodes.push((Some(key.0), key.1, key.2));
+                    (nodes.len() - 1) as u64
+                });
+                result[index] = new_index;
+            }
+        }
+
+        let root = result[self.nodes.len() - 1];
+        if root < 2 {
+            return RawBdd::constant(self.variable_count, root == 1);
+        }
+        // The root is the last created node unless it was reused from an earlier position.
+        // In that case, the nodes created after it are unreachable and must be removed.
+        let raw = RawBdd {
+            variable_count: self.variable_count,
+            nodes,
+        };
+        if root as usize == raw.nodes.len() - 1 {
+            raw
+        } else {
+            raw.reachable_from(root as usize)
+        }
+    }
+
+    /// **(internal)** A copy of the nodes reachable from `root` (a decision node) in DFS
+    /// post-order. Requires that children appear before their parents.
+    fn reachable_from(&self, root: usize) -> RawBdd {
+        let mut reachable = vec![false; self.nodes.len()];
+        reachable[root] = true;
+        for index in (2..=root).rev() {
+            if reachable[index] {
+                let (_, low, high) = self.nodes[index];
+                reachable[low as usize] = true;
+                reachable[high as usize] = true;
+            }
+        }
+        let mut new_index = vec![0u64; self.nodes.len()];
+        let mut nodes = vec![(None, 0, 0), (None, 1, 1)];
+        new_index[1] = 1;
+        for index in 2..=root {
+            if reachable[index] {
+                let (variable, low, high) = self.nodes[index];
+                nodes.push((variable, new_index[low as usize], new_index[high as usize]));
+                new_index[index] = (nodes.len() - 1) as u64;
+            }
+        }
+        RawBdd {
+            variable_count: self.variable_count,
+            nodes,
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use super::BddEngine;
+use crate::convert::RawBdd;
+use crate::v2::Bdd;
+use std::cmp::max;
+
+/// The `v2` implementation. Negation and restriction are computed on a `RawBdd`.
+///
+/// The `v2` binary operations cannot resolve the whole task in the root (their stack expects
+/// at least one expansion), so operations with a constant operand are resolved here.
+pub struct V2Engine {
+    variable_count: u16,
+}
+
+impl V2Engine {
+    /// **(internal)** Create a `Bdd` from a `RawBdd` which admits all variables of this engine.
+    fn build(&self, mut raw: RawBdd) -> Bdd {
+        raw.variable_count = max(raw.variable_count, u32::from(self.variable_count));
+        Bdd::from_raw_bdd(&raw).unwrap()
+    }
+
+    /// **(internal)** Apply the operation `native`, or compute the result directly using its
+    /// truth `table` if one of the operands is constant.
+    fn apply<F>(&self, left: &Bdd, right: &Bdd, table: fn(bool, bool) -> bool, native: F) -> Bdd
+    where
+        F: Fn(&Bdd, &Bdd) -> Bdd,
+    {
+        let constant = |bdd: &Bdd| match bdd.node_count() {
+            1 => Some(false),
+            2 => Some(true),
+            _ => None,
+        };
+        let (bdd, when_false, when_true) = match (constant(left), constant(right)) {
+            (None, None) => return native(left, right),
+            (Some(l), Some(r)) => return self.build(RawBdd::constant(0, table(l, r))),
+            (Some(l), None) => (right, table(l, false), table(l, true)),
+            (None, Some(r)) => (left, table(false, r), table(true, r)),
+        };
+        match (when_false, when_true) {
+            (false, true) => bdd.clone(),
+            (true, false) => self.build(bdd.to_raw_bdd().not()),
+            (value, _) => self.build(RawBdd::constant(0, value)),
+        }
+    }
+}
+
+impl BddEngine for V2Engine {
+    type Bdd = Bdd;
+    const NAME: &'static str = "v2";
+
+    fn new(variable_count: u16) -> V2Engine {
+        V2Engine { variable_count }
+    }
+
+    fn variable_count(&self) -> u16 {
+        self.variable_count
+    }
+
+    fn mk_false(&mut self) -> Bdd {
+        self.build(RawBdd::constant(0, false))
+    }
+
+    fn mk_true(&mut self) -> Bdd {
+        self.build(RawBdd::constant(0, true))
+    }
+
+    fn mk_literal(&mut self, variable: u16, value: bool) -> Bdd {
+        self.build(RawBdd::literal(0, u32::from(variable), value))
+    }
+
+    fn not(&mut self, bdd: &Bdd) -> Bdd {
+        self.build(bdd.to_raw_bdd().not())
+    }
+
+    fn and(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        self.apply(left, right, |l, r| l && r, Bdd::and)
+    }
+
+    fn or(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        self.apply(left, right, |l, r| l || r, Bdd::or)
+    }
+
+    fn xor(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        self.apply(left, right, |l, r| l ^ r, Bdd::xor)
+    }
+
+    fn iff(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        self.apply(left, right, |l, r| l == r, Bdd::iff)
+    }
+
+    fn imp(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        self.apply(left, right, |l, r| !l || r, Bdd::imp)
+    }
+
+    fn and_not(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        self.apply(left, right, |l, r| l && !r, Bdd::and_not)
+    }
+
+    fn exists(&mut self, bdd: &Bdd, variable: u16) -> Bdd {
+        let raw = bdd.to_raw_bdd();
+        let low = self.build(raw.restrict(u32::from(variable), false));
+        let high = self.build(raw.restrict(u32::from(variable), true));
+        self.or(&low, &high)
+    }
+
+    fn node_count(&self, bdd: &Bdd) -> usize {
+        bdd.node_count()
+    }
+
+    fn is_false(&self, bdd: &Bdd) -> bool {
+        bdd.node_count() == 1
+    }
+
+    fn import(&mut self, bdd: &crate::v4::core::Bdd) -> Bdd {
+        self.build(bdd.to_raw_bdd())
+    }
+}
@@ -0,0 +1,99 @@
+use super::BddEngine;
+use crate::convert::RawBdd;
+use crate::v3::core::bdd::Bdd;
+use crate::v3::core::ooo::apply;
+
+/// The out-of-order `v3` implementation.
+///
+/// Only the disjunction is implemented by `v3::core::ooo::apply`. The other binary operations
+/// are derived from it using negation (computed on a `RawBdd`), e.g. `a & b = !(!a | !b)`.
+pub struct V3OooEngine {
+    variable_count: u16,
+}
+
+impl V3OooEngine {
+    /// **(internal)** Create a `Bdd` from a `RawBdd`.
+    fn build(&self, raw: RawBdd) -> Bdd {
+        Bdd::from_raw_bdd(&raw).unwrap()
+    }
+}
+
+impl BddEngine for V3OooEngine {
+    type Bdd = Bdd;
+    const NAME: &'static str = "v3-ooo";
+
+    fn new(variable_count: u16) -> V3OooEngine {
+        V3OooEngine { variable_count }
+    }
+
+    fn variable_count(&self) -> u16 {
+        self.variable_count
+    }
+
+    fn mk_false(&mut self) -> Bdd {
+        self.build(RawBdd::constant(0, false))
+    }
+
+    fn mk_true(&mut self) -> Bdd {
+        self.build(RawBdd::constant(0, true))
+    }
+
+    fn mk_literal(&mut self, variable: u16, value: bool) -> Bdd {
+        self.build(RawBdd::literal(0, u32::from(variable), value))
+    }
+
+    fn not(&mut self, bdd: &Bdd) -> Bdd {
+        self.build(bdd.to_raw_bdd().not())
+    }
+
+    fn and(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        let (left, right) = (self.not(left), self.not(right));
+        let result = apply(&left, &right);
+        self.not(&result)
+    }
+
+    fn or(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        apply(left, right)
+    }
+
+    fn xor(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        let left_only = self.and_not(left, right);
+        let right_only = self.and_not(right, left);
+        apply(&left_only, &right_only)
+    }
+
+    fn iff(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        let result = self.xor(left, right);
+        self.not(&result)
+    }
+
+    fn imp(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        let left = self.not(left);
+        apply(&left, right)
+    }
+
+    fn and_not(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        let left = self.not(left);
+        let result = apply(&left, right);
+        self.not(&result)
+    }
+
+    fn exists(&mut self, bdd: &Bdd, variable: u16) -> Bdd {
+        let raw = bdd.to_raw_bdd();
+        let low = self.build(raw.restrict(u32::from(variable), false));
+        let high = self.build(raw.restrict(u32::from(variable), true));
+        apply(&low, &high)
+    }
+
+    fn node_count(&self, bdd: &Bdd) -> usize {
+        bdd.node_count()
+    }
+
+    fn is_false(&self, bdd: &Bdd) -> bool {
+        bdd.is_false()
+    }
+
+    fn import(&mut self, bdd: &crate::v4::core::Bdd) -> Bdd {
+        self.build(bdd.to_raw_bdd())
+    }
+}
@@ -0,0 +1,88 @@
+use super::BddEngine;
+use crate::v4::apply::{And, AndNot, ApplyContext, Iff, Imp, Or, Xor};
+use crate::v4::core::{Bdd, Variable};
+
+/// The `v4` implementation. All binary operations share one `ApplyContext`.
+pub struct V4Engine {
+    variable_count: u16,
+    context: ApplyContext,
+}
+
+impl V4Engine {
+    /// Create an engine which uses a custom `ApplyContext` (e.g. with the out-of-order engine).
+    pub fn with_context(variable_count: u16, context: ApplyContext) -> V4Engine {
+        V4Engine { variable_count, context }
+    }
+}
+
+impl BddEngine for V4Engine {
+    type Bdd = Bdd;
+    const NAME: &'static str = "v4";
+
+    fn new(variable_count: u16) -> V4Engine {
+        V4Engine::with_context(variable_count, ApplyContext::new())
+    }
+
+    fn variable_count(&self) -> u16 {
+        self.variable_count
+    }
+
+    fn mk_false(&mut self) -> Bdd {
+        Bdd::new_zero()
+    }
+
+    fn mk_true(&mut self) -> Bdd {
+        Bdd::new_one()
+    }
+
+    fn mk_literal(&mut self, variable: u16, value: bool) -> Bdd {
+        Bdd::new_literal(Variable::from(u32::from(variable)), value)
+    }
+
+    fn not(&mut self, bdd: &Bdd) -> Bdd {
+        bdd.not()
+    }
+
+    fn and(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        self.context.apply(left, right, And).0
+    }
+
+    fn or(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        self.context.apply(left, right, Or).0
+    }
+
+    fn xor(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        self.context.apply(left, right, Xor).0
+    }
+
+    fn iff(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        self.context.apply(left, right, Iff).0
+    }
+
+    fn imp(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        self.context.apply(left, right, Imp).0
+    }
+
+    fn and_not(&mut self, left: &Bdd, right: &Bdd) -> Bdd {
+        self.context.apply(left, right, AndNot).0
+    }
+
+    fn exists(&mut self, bdd: &Bdd, variable: u16) -> Bdd {
+        let raw = bdd.to_raw_bdd();
+        let low = Bdd::from_raw_bdd(&raw.restrict(u32::from(variable), false)).unwrap();
+        let high = Bdd::from_raw_bdd(&raw.restrict(u32::from(variable), true)).unwrap();
+        self.or(&low, &high)
+    }
+
+    fn node_count(&self, bdd: &Bdd) -> usize {
+        bdd.get_size() as usize
+    }
+
+    fn is_false(&self, bdd: &Bdd) -> bool {
+        bdd.is_zero()
+    }
+
+    fn import(&mut self, bdd: &Bdd) -> Bdd {
+        bdd.clone()
+    }
+}
@@ -50,4 +50,10 @@ pub mod v4;
 
 pub mod machine;
 
+pub mod convert;
+
+pub mod engine;
+
+pub mod verify;
+
 pub mod perf_testing;
\ No newline at end of file
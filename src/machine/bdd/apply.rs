@@ -0,0 +1,300 @@
+use super::Bdd;
+use crate::machine::{BddNode, NodeId};
+use fxhash::FxHashMap;
+use std::cmp::{max, min};
+
+/// Binary logical operations on `Bdd` objects.
+///
+/// The result admits the maximum of the variable counts of the two operands, and its nodes are
+/// sorted in DFS pre-order. Since the result is also reduced, two results which represent the
+/// same function are equal in terms of `Bdd::eq_bytes`.
+impl Bdd {
+    /// Create a `Bdd` representing the formula `self & other`.
+    pub fn and(&self, other: &Bdd) -> Bdd {
+        self.binary_op(other, and)
+    }
+
+    /// Create a `Bdd` representing the formula `self | other`.
+    pub fn or(&self, other: &Bdd) -> Bdd {
+        self.binary_op(other, or)
+    }
+
+    /// Create a `Bdd` representing the formula `self ^ other`.
+    pub fn xor(&self, other: &Bdd) -> Bdd {
+        self.binary_op(other, xor)
+    }
+
+    /// Create a `Bdd` representing the formula `self <=> other`.
+    pub fn iff(&self, other: &Bdd) -> Bdd {
+        self.binary_op(other, iff)
+    }
+
+    /// Create a `Bdd` representing the formula `self => other`.
+    pub fn imp(&self, other: &Bdd) -> Bdd {
+        self.binary_op(other, imp)
+    }
+
+    /// Create a `Bdd` representing the formula `self & !other`.
+    pub fn and_not(&self, other: &Bdd) -> Bdd {
+        self.binary_op(other, and_not)
+    }
+
+    /// Apply a general binary operation to the two `Bdd` objects.
+    ///
+    /// The operation is given the values of the two operands, where `None` means the value
+    /// is not known yet (the operand is not a terminal node). It should return the result
+    /// as soon as it can be determined (e.g. `and` is `false` once one of the operands is
+    /// `false`), or `None` if both operand values are needed. It must return a value when both
+    /// operands are known. Any truth table can be expressed this way, but returning early
+    /// makes the operation faster.
+    ///
+    /// *Panics:* If `op_function` returns `None` for two known values.
+    pub fn binary_op<F>(&self, other: &Bdd, op_function: F) -> Bdd
+    where
+        F: Fn(Option<bool>, Option<bool>) -> Option<bool>,
+    {
+        let variable_count = max(self.variable_count, other.variable_count);
+        let mut nodes = vec![BddNode::ZERO, BddNode::ONE];
+        let mut node_cache: FxHashMap<BddNode, NodeId> = FxHashMap::default();
+        let mut task_cache: FxHashMap<(NodeId, NodeId), NodeId> = FxHashMap::default();
+
+        let root_task = (self.root_id(), other.root_id());
+        let mut stack = vec![root_task];
+        while let Some(&(left, right)) = stack.last() {
+            if task_cache.contains_key(&(left, right)) {
+                stack.pop();
+                continue;
+            }
+            if let Some(value) = op_function(terminal_value(left), terminal_value(right)) {
+                task_cache.insert((left, right), if value { NodeId::ONE } else { NodeId::ZERO });
+                stack.pop();
+                continue;
+            }
+            if left.is_terminal() && right.is_terminal() {
+                panic!("Operation undefined for terminal values.");
+            }
+
+            // Terminal nodes have an undefined variable, which is larger than any other variable.
+            let (l_var, l_low, l_high) = self[left].unpack();
+            let (r_var, r_low, r_high) = other[right].unpack();
+            let variable = min(l_var, r_var);
+            let (l_low, l_high) = if l_var == variable { (l_low, l_high) } else { (left, left) };
+            let (r_low, r_high) = if r_var == variable { (r_low, r_high) } else { (right, right) };
+
+            let low = task_cache.get(&(l_low, r_low)).cloned();
+            let high = task_cache.get(&(l_high, r_high)).cloned();
+            match (low, high) {
+                (Some(low), Some(high)) => {
+                    stack.pop();
+                    let result = if low == high {
+                        low
+                    } else {
+                        let node = unsafe { BddNode::pack_unchecked(variable, low, high) };
+                        *node_cache.entry(node).or_insert_with(|| {
+                            nodes.push(node);
+                            // The number of nodes is bounded by the product of the operand sizes,
+                            // which fits into the 48-bit address space.
+                            unsafe { NodeId::from_u64((nodes.len() - 1) as u64) }
+                        })
+                    };
+                    task_cache.insert((left, right), result);
+                }
+                (low, high) => {
+                    if high.is_none() {
+                        stack.push((l_high, r_high));
+                    }
+                    if low.is_none() {
+                        stack.push((l_low, r_low));
+                    }
+                }
+            }
+        }
+
+        Bdd::from_reachable_nodes(variable_count, &nodes, task_cache[&root_task])
+    }
+}
+
+/// The value of a terminal node, or `None` for a decision node.
+fn terminal_value(id: NodeId) -> Option<bool> {
+    if id.is_zero() {
+        Some(false)
+    } else if id.is_one() {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+fn and(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+    match (left, right) {
+        (Some(true), Some(true)) => Some(true),
+        (Some(false), _) | (_, Some(false)) => Some(false),
+        _ => None,
+    }
+}
+
+fn or(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+    match (left, right) {
+        (Some(false), Some(false)) => Some(false),
+        (Some(true), _) | (_, Some(true)) => Some(true),
+        _ => None,
+    }
+}
+
+fn xor(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+    match (left, right) {
+        (Some(l), Some(r)) => Some(l ^ r),
+        _ => None,
+    }
+}
+
+fn iff(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+    match (left, right) {
+        (Some(l), Some(r)) => Some(l == r),
+        _ => None,
+    }
+}
+
+fn imp(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+    match (left, right) {
+        (Some(true), Some(false)) => Some(false),
+        (Some(false), _) | (_, Some(true)) => Some(true),
+        _ => None,
+    }
+}
+
+fn and_not(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+    match (left, right) {
+        (Some(true), Some(false)) => Some(true),
+        (Some(false), _) | (_, Some(true)) => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::{BddNode, NodeId, VariableId};
+    use super::Bdd;
+    use crate::verify::{FunctionTable, Lcg};
+    use std::collections::HashMap;
+
+    /// A result of a binary operation together with the Boolean function it should compute.
+    type OperationCase = (Bdd, fn(bool, bool) -> bool);
+
+    /// Build a `Bdd` of the given truth table. The nodes are intentionally in DFS post-order.
+    fn from_table(table: &FunctionTable) -> Bdd {
+        fn build(
+            table: &FunctionTable,
+            variable: u32,
+            offset: u64,
+            bdd: &mut Bdd,
+            unique: &mut HashMap<BddNode, NodeId>,
+        ) -> NodeId {
+            if variable == table.variables() {
+                return if table.get(offset) { NodeId::ONE } else { NodeId::ZERO };
+            }
+            let half = 1u64 << (table.variables() - 1 - variable);
+            let low = build(table, variable + 1, offset, bdd, unique);
+            let high = build(table, variable + 1, offset + half, bdd, unique);
+            match BddNode::try_pack(VariableId::from(variable as u16), low, high) {
+                None => low,
+                Some(node) => *unique.entry(node).or_insert_with(|| unsafe { bdd.push_node(node) }),
+            }
+        }
+        let variables = table.variables() as u16;
+        let mut bdd = Bdd::new_true();
+        bdd.ensure_variable_count(variables);
+        let root = build(table, 0, 0, &mut bdd, &mut HashMap::new());
+        if root.is_terminal() {
+            let mut result = if root.is_zero() { Bdd::new_false() } else { Bdd::new_true() };
+            result.ensure_variable_count(variables);
+            result
+        } else {
+            bdd
+        }
+    }
+
+    fn eval(bdd: &Bdd, row: u64, variables: u16) -> bool {
+        let mut id = bdd.root_id();
+        while !id.is_terminal() {
+            let node = bdd[id];
+            let bit = variables - 1 - u16::from(node.variable());
+            id = if (row >> bit) & 1 == 1 { node.high_link() } else { node.low_link() };
+        }
+        id.is_one()
+    }
+
+    /// Check that the nodes are reduced and sorted in DFS pre-order with the root last.
+    fn assert_preorder(bdd: &Bdd) {
+        let mut expected = bdd.node_count();
+        let mut visited = vec![false; bdd.node_count()];
+        let mut stack = vec![bdd.root_id()];
+        while let Some(id) = stack.pop() {
+            let index = id.into_u64() as usize;
+            if id.is_terminal() || visited[index] {
+                continue;
+            }
+            visited[index] = true;
+            expected -= 1;
+            assert_eq!(expected, index);
+            let (variable, low, high) = bdd[id].unpack();
+            assert_ne!(low, high);
+            assert!(variable < bdd[low].variable() && variable < bdd[high].variable());
+            stack.push(high);
+            stack.push(low);
+        }
+        // All decision nodes are reachable.
+        assert!(bdd.root_id().is_terminal() || expected == 2);
+    }
+
+    #[test]
+    fn machine_bdd_operations() {
+        let variables = 6;
+        let mut rng = Lcg::new(7);
+        let nor = |l: Option<bool>, r: Option<bool>| match (l, r) {
+            (Some(true), _) | (_, Some(true)) => Some(false),
+            (Some(false), Some(false)) => Some(true),
+            _ => None,
+        };
+        for (l_density, r_density) in &[(50, 50), (10, 80), (0, 50), (100, 30)] {
+            let left_table = FunctionTable::random(u32::from(variables), *l_density, &mut rng);
+            let right_table = FunctionTable::random(u32::from(variables - 2), *r_density, &mut rng);
+            let left = from_table(&left_table);
+            let mut right = from_table(&right_table);
+            // The right operand uses fewer variables, the result must still admit all of them.
+            assert_eq!(variables - 2, right.variable_count());
+            right.ensure_variable_count(variables - 1);
+            // The right operand does not depend on the last two variables.
+            let right_table =
+                FunctionTable::from_fn(u32::from(variables), |row| right_table.get(row >> 2));
+
+            let results: Vec<OperationCase> = vec![
+                (left.and(&right), |l, r| l & r),
+                (left.or(&right), |l, r| l | r),
+                (left.xor(&right), |l, r| l ^ r),
+                (left.iff(&right), |l, r| l == r),
+                (left.imp(&right), |l, r| !l | r),
+                (left.and_not(&right), |l, r| l & !r),
+                (left.binary_op(&right, nor), |l, r| !(l | r)),
+            ];
+            for (result, function) in results {
+                assert_eq!(variables, result.variable_count());
+                assert_preorder(&result);
+                let actual = FunctionTable::from_fn(u32::from(variables), |row| {
+                    eval(&result, row, variables)
+                });
+                assert_eq!(left_table.apply(&right_table, function), actual);
+            }
+
+            // Equivalent functions have the same canonical representation.
+            assert!(left.and(&right).eq_bytes(&right.and(&left)));
+            assert!(left.xor(&right).xor(&right).eq_bytes(&left.or(&left)));
+            assert!(left.and_not(&left).eq_bytes(&left.xor(&left)));
+        }
+        let mut other = Bdd::new_true();
+        other.ensure_variable_count(3);
+        assert!(Bdd::new_false().or(&Bdd::new_true()).eq_bytes(&Bdd::new_true()));
+        assert!(Bdd::new_false().imp(&other).eq_bytes(&other));
+        assert_eq!(1, Bdd::new_true().and_not(&other).node_count());
+    }
+}
@@ -1,9 +1,12 @@
 use super::BddNode;
-use crate::machine::NodeId;
+use crate::convert::{ConversionError, RawBdd};
+use crate::machine::{NodeId, VariableId};
 use std::cmp::max;
 use std::convert::TryFrom;
 use std::ops::Index;
 
+mod apply;
+
 /// A directed acyclic graph representing a Boolean function.
 ///
 /// The first two nodes must be `ZERO` and `ONE`. The root node must be last.
@@ -88,6 +91,57 @@ impl Bdd {
         self.root_id()
     }
 
+    /// **(internal)** Create a `Bdd` from the nodes that are reachable from `root` in the given
+    /// `nodes` vector. The first two `nodes` must be `ZERO` and `ONE`.
+    ///
+    /// The nodes of the result are sorted in DFS pre-order: the root is last and every other
+    /// node is placed right before the previously visited node.
+    pub(crate) fn from_reachable_nodes(variable_count: u16, nodes: &[BddNode], root: NodeId) -> Bdd {
+        if root.is_terminal() {
+            let mut result = if root.is_zero() { Bdd::new_false() } else { Bdd::new_true() };
+            result.variable_count = variable_count;
+            return result;
+        }
+
+        // Collect the reachable decision nodes in DFS pre-order.
+        let mut order = vec![usize::MAX; nodes.len()];
+        let mut preorder: Vec<NodeId> = Vec::new();
+        let mut stack: Vec<NodeId> = vec![root];
+        while let Some(id) = stack.pop() {
+            let index = unsafe { id.into_usize() };
+            if id.is_terminal() || order[index] != usize::MAX {
+                continue;
+            }
+            order[index] = preorder.len();
+            preorder.push(id);
+            let (low, high) = nodes[index].links();
+            stack.push(high);
+            stack.push(low);
+        }
+
+        // The root gets the last position, the rest follows in descending order.
+        let remap = |id: NodeId| -> NodeId {
+            if id.is_terminal() {
+                id
+            } else {
+                let position = preorder.len() + 1 - order[unsafe { id.into_usize() }];
+                // The result is never larger than the input, which is a valid `Bdd`.
+                unsafe { NodeId::from_u64(position as u64) }
+            }
+        };
+        let mut new_nodes = vec![BddNode::ZERO; preorder.len() + 2];
+        new_nodes[1] = BddNode::ONE;
+        for id in &preorder {
+            let (variable, low, high) = nodes[unsafe { id.into_usize() }].unpack();
+            let position = unsafe { remap(*id).into_usize() };
+            new_nodes[position] = unsafe { BddNode::pack_unchecked(variable, remap(low), remap(high)) };
+        }
+        Bdd {
+            variable_count,
+            nodes: new_nodes,
+        }
+    }
+
     /// Checks for "syntactic" equality between two `Bdd` objects.
     ///
     /// This is more strict than logical equivalence because two `Bdd` objects can represent the
@@ -97,6 +151,30 @@ impl Bdd {
     }
 }
 
+impl Bdd {
+    /// **(internal)** A representation-independent copy of this `Bdd` (see `crate::convert`).
+    pub(crate) fn to_raw_bdd(&self) -> RawBdd {
+        RawBdd::new(u32::from(self.variable_count), self.nodes.len(), |index| {
+            let (variable, low, high) = self.nodes[index].unpack();
+            (u32::from(u16::from(variable)), low.into_u64(), high.into_u64())
+        })
+    }
+
+    /// **(internal)** Create a `Bdd` from its representation-independent copy.
+    pub(crate) fn from_raw_bdd(raw: &RawBdd) -> Result<Bdd, ConversionError> {
+        // The largest 48-bit value is the undefined `NodeId`.
+        raw.check_limits(u32::from(u16::MAX) - 1, NodeId::UNDEFINED.into_u64() - 1)?;
+        let nodes = raw.map_nodes(BddNode::ZERO, BddNode::ONE, |variable, low, high| unsafe {
+            // The values were checked above.
+            BddNode::pack_unchecked(VariableId::from(variable as u16), NodeId::from_u64(low), NodeId::from_u64(high))
+        });
+        Ok(Bdd {
+            variable_count: raw.variable_count as u16,
+            nodes,
+        })
+    }
+}
+
 impl Index<NodeId> for Bdd {
     type Output = BddNode;
 
@@ -158,3 +158,59 @@ impl Bdd {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::v2::Bdd;
+    use crate::verify::{FunctionTable, Lcg};
+
+    const VARIABLES: u32 = 5;
+
+    fn from_table(table: &FunctionTable) -> Bdd {
+        let mut raw = table.to_bdd().to_raw_bdd();
+        raw.variable_count = VARIABLES;
+        Bdd::from_raw_bdd(&raw).unwrap()
+    }
+
+    fn to_table(bdd: &Bdd) -> FunctionTable {
+        let raw = bdd.to_raw_bdd();
+        FunctionTable::from_fn(VARIABLES, |index| {
+            let mut node = raw.nodes.len() - 1;
+            while let (Some(variable), low, high) = raw.nodes[node] {
+                let bit = 1 << (VARIABLES - 1 - variable);
+                node = (if index & bit != 0 { high } else { low }) as usize;
+            }
+            node == 1
+        })
+    }
+
+    #[test]
+    pub fn asymmetric_operations() {
+        let mut rng = Lcg::new(3);
+        for _ in 0..20 {
+            let l_table = FunctionTable::random(VARIABLES, 50, &mut rng);
+            let r_table = FunctionTable::random(VARIABLES, 50, &mut rng);
+            let (left, right) = (from_table(&l_table), from_table(&r_table));
+            if left.node_count() <= 2 || right.node_count() <= 2 {
+                continue; // The apply algorithms expect at least one expanded task.
+            }
+            let expect = |function: fn(bool, bool) -> bool| l_table.apply(&r_table, function);
+            let imp = expect(|l, r| !l || r);
+            let and_not = expect(|l, r| l && !r);
+            // Depending on the operand sizes, these use the mirrored operations.
+            assert_eq!(imp, to_table(&left.imp(&right)));
+            assert_eq!(and_not, to_table(&left.and_not(&right)));
+
+            // The mirrored operations compute `op(other, self)`.
+            let (big, small, imp, and_not) = if left.node_count() >= right.node_count() {
+                (&left, &right, expect(|l, r| l || !r), expect(|l, r| !l && r))
+            } else {
+                (&right, &left, imp, and_not)
+            };
+            assert_eq!(imp, to_table(&big._u32_inv_imp(small)));
+            assert_eq!(imp, to_table(&big._u48_inv_imp(small)));
+            assert_eq!(and_not, to_table(&big._u32_not_and(small)));
+            assert_eq!(and_not, to_table(&big._u48_not_and(small)));
+        }
+    }
+}
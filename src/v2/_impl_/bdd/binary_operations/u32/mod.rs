@@ -282,8 +282,8 @@ impl Bdd {
         apply_u32!(
             self,
             other,
-            |l: NodeId, r: NodeId| l.is_zero() || r.is_one(),
-            |l: NodeId, r: NodeId| l.is_one() && r.is_zero()
+            |l: NodeId, r: NodeId| l.is_zero() && r.is_one(),
+            |l: NodeId, r: NodeId| l.is_one() || r.is_zero()
         )
     }
 
@@ -322,8 +322,8 @@ impl Bdd {
         apply_u32!(
             self,
             other,
-            |l: NodeId, r: NodeId| l.is_one() && r.is_zero(),
-            |l: NodeId, r: NodeId| l.is_zero() || r.is_one()
+            |l: NodeId, r: NodeId| l.is_one() || r.is_zero(),
+            |l: NodeId, r: NodeId| l.is_zero() && r.is_one()
         )
     }
 }
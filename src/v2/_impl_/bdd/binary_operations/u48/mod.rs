@@ -266,8 +266,8 @@ impl Bdd {
         apply_u48!(
             self,
             other,
-            |l: NodeId, r: NodeId| l.is_zero() || r.is_one(),
-            |l: NodeId, r: NodeId| l.is_one() && r.is_zero()
+            |l: NodeId, r: NodeId| l.is_zero() && r.is_one(),
+            |l: NodeId, r: NodeId| l.is_one() || r.is_zero()
         )
     }
 
@@ -306,8 +306,8 @@ impl Bdd {
         apply_u48!(
             self,
             other,
-            |l: NodeId, r: NodeId| l.is_one() && r.is_zero(),
-            |l: NodeId, r: NodeId| l.is_zero() || r.is_one()
+            |l: NodeId, r: NodeId| l.is_one() || r.is_zero(),
+            |l: NodeId, r: NodeId| l.is_zero() && r.is_one()
         )
     }
 }
@@ -1,3 +1,4 @@
+use crate::convert::{ConversionError, RawBdd};
 use std::cmp::max;
 use std::convert::TryFrom;
 use std::ops::{Shl, Shr};
@@ -69,6 +70,7 @@ impl From<u16> for VariableId {
 ///
 /// A `Bdd` object is not guaranteed to be minimal or canonical. In general we try to create `Bdds`
 /// which are as small as possible, but we prefer speed to minimality.
+#[derive(Clone)]
 pub struct Bdd {
     variable_count: u16,
     nodes: Vec<BddNode>,
@@ -363,6 +365,29 @@ impl Bdd {
     }
 }
 
+impl Bdd {
+    /// **(internal)** A representation-independent copy of this `Bdd` (see `crate::convert`).
+    pub(crate) fn to_raw_bdd(&self) -> RawBdd {
+        RawBdd::new(u32::from(self.variable_count), self.nodes.len(), |index| {
+            let (variable, low, high) = self.nodes[index].unpack();
+            (u32::from(variable.0), low.0, high.0)
+        })
+    }
+
+    /// **(internal)** Create a `Bdd` from its representation-independent copy.
+    pub(crate) fn from_raw_bdd(raw: &RawBdd) -> Result<Bdd, ConversionError> {
+        // The low link shares its `u64` with the variable, so it only has 48 bits.
+        raw.check_limits(u32::from(u16::MAX) - 1, BddNode::ID_MASK)?;
+        let nodes = raw.map_nodes(BddNode::ZERO, BddNode::ONE, |variable, low, high| {
+            BddNode::pack(VariableId(variable as u16), NodeId(low), NodeId(high))
+        });
+        Ok(Bdd {
+            variable_count: raw.variable_count as u16,
+            nodes,
+        })
+    }
+}
+
 impl TryFrom<&str> for Bdd {
     type Error = String;
 
@@ -1,3 +1,4 @@
+use crate::convert::{ConversionError, RawBdd};
 use crate::v3::core::node_id::NodeId;
 use crate::v3::core::packed_bdd_node::PackedBddNode;
 use std::convert::TryFrom;
@@ -161,6 +162,27 @@ impl Bdd {
 
 }
 
+impl Bdd {
+    /// **(internal)** A representation-independent copy of this `Bdd` (see `crate::convert`).
+    pub(crate) fn to_raw_bdd(&self) -> RawBdd {
+        RawBdd::new(0, self.nodes.len(), |index| {
+            let (variable, low, high) = self.nodes[index].unpack();
+            (u32::from(variable), u64::from(low), u64::from(high))
+        })
+    }
+
+    /// **(internal)** Create a `Bdd` from its representation-independent copy.
+    pub(crate) fn from_raw_bdd(raw: &RawBdd) -> Result<Bdd, ConversionError> {
+        raw.check_limits(u32::MAX - 1, u64::MAX - 1)?;
+        let nodes = raw.map_nodes(PackedBddNode::ZERO, PackedBddNode::ONE, |variable, low, high| {
+            PackedBddNode::pack(VariableId::from(variable), NodeId::from(low), NodeId::from(high))
+        });
+        Ok(Bdd {
+            height: raw.height(),
+            nodes,
+        })
+    }
+}
 
 impl TryFrom<&str> for Bdd {
     type Error = String;
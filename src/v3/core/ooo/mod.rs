@@ -14,16 +14,26 @@ pub mod reorder_buffer;
 pub mod execution_queue;
 
 pub fn apply(left_bdd: &Bdd, right_bdd: &Bdd) -> Bdd {
+    // Constant operands have no height, so the structures below would be empty.
+    if left_bdd.is_true() || right_bdd.is_false() {
+        return left_bdd.clone();
+    }
+    if right_bdd.is_true() || left_bdd.is_false() {
+        return right_bdd.clone();
+    }
+    // Every expanded task stays on the stack together with its two sub-tasks, so the stack
+    // (and hence the ROB) needs up to two slots for every level of the recursion.
     let mut queue = ExecutionRetireQueue::<32>::new();
-    let mut rob = ReorderBuffer::new(left_bdd.get_height() + right_bdd.get_height());
+    let mut rob = ReorderBuffer::new(2 * (left_bdd.get_height() + right_bdd.get_height()));
     let mut task_cache = TaskCache::new(left_bdd.node_count(), right_bdd.node_count());
     let mut node_cache = NodeCache::new(left_bdd.node_count(), 2 * left_bdd.node_count());
-    let mut stack = TaskStack::new(left_bdd.get_height(), right_bdd.get_height());
+    let mut stack = TaskStack::new(2 * left_bdd.get_height(), 2 * right_bdd.get_height());
     let mut stall = 0;
     unsafe {
         stack.push_new(0, (left_bdd.get_root_id(), right_bdd.get_root_id()));
 
         while !stack.is_empty() || !queue.is_empty() {
+            node_cache.check_capacity();
             if queue.can_retire() {
                 let task = queue.retire_task_reference();
                 if task.is_retired() { // The task was retired during the execute step.
@@ -166,4 +176,71 @@ pub fn apply(left_bdd: &Bdd, right_bdd: &Bdd) -> Bdd {
     unsafe {
         Bdd::from_raw_nodes(node_cache.export_nodes())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use crate::v3::core::bdd::Bdd;
+    use crate::v3::core::ooo::apply;
+    use crate::verify::{FunctionTable, Lcg};
+
+    fn from_table(table: &FunctionTable) -> Bdd {
+        Bdd::from_raw_bdd(&table.to_bdd().to_raw_bdd()).unwrap()
+    }
+
+    fn to_table(variables: u32, bdd: &Bdd) -> FunctionTable {
+        let raw = bdd.to_raw_bdd();
+        FunctionTable::from_fn(variables, |index| {
+            let mut node = raw.nodes.len() - 1;
+            while let (Some(variable), low, high) = raw.nodes[node] {
+                let bit = 1 << (variables - 1 - variable);
+                node = (if index & bit != 0 { high } else { low }) as usize;
+            }
+            node == 1
+        })
+    }
+
+    fn check_or(l_table: &FunctionTable, r_table: &FunctionTable) {
+        let left = from_table(l_table);
+        let right = from_table(r_table);
+        let expected = l_table.apply(r_table, |l, r| l || r);
+        assert_eq!(expected, to_table(l_table.variables(), &apply(&left, &right)));
+    }
+
+    #[test]
+    pub fn ooo_apply_constants() {
+        let mut rng = Lcg::new(1);
+        let table = FunctionTable::random(4, 50, &mut rng);
+        let zero = FunctionTable::constant(4, false);
+        let one = FunctionTable::constant(4, true);
+        for (left, right) in &[(&table, &zero), (&zero, &table), (&table, &one), (&one, &table), (&zero, &one)] {
+            check_or(left, right);
+        }
+    }
+
+    #[test]
+    pub fn ooo_apply_result_larger_than_left() {
+        // The node cache initially holds twice the nodes of the left operand, but the result
+        // of a minterm and a random function is much larger.
+        let mut rng = Lcg::new(7);
+        let variables = 8;
+        let minterm = FunctionTable::from_fn(variables, |i| i == 77);
+        for _ in 0..5 {
+            let table = FunctionTable::random(variables, 50, &mut rng);
+            check_or(&minterm, &table);
+        }
+    }
+
+    #[test]
+    pub fn ooo_apply_deep_recursion() {
+        // A literal of the last variable forces the recursion through every level of the
+        // right operand, keeping two pending sub-tasks on the stack for each of them.
+        let mut rng = Lcg::new(7);
+        let variables = 8;
+        let literal = FunctionTable::literal(variables, variables - 1, true);
+        for _ in 0..5 {
+            let table = FunctionTable::random(variables, 50, &mut rng);
+            check_or(&literal, &table);
+        }
+    }
+}
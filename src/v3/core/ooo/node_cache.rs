@@ -121,9 +121,12 @@ impl NodeCache {
         }
     }
 
-    pub fn check_capacity(&mut self)  {
-        if self.index_after_last == self.nodes.len() {
-            unimplemented!("Re-allocate nodes.")
+    /// Make sure there is room for at least two more nodes (one iteration of `apply` creates
+    /// at most two), doubling the node storage if necessary. Slots remain valid.
+    pub fn check_capacity(&mut self) {
+        if self.index_after_last + 2 > self.nodes.len() {
+            let new_len = 2 * self.nodes.len();
+            self.nodes.resize(new_len, (PackedBddNode::ZERO, NodeCacheSlot::UNDEFINED));
         }
     }
 
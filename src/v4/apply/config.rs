@@ -0,0 +1,204 @@
+use super::super::core::CancellationToken;
+use std::fmt::{Display, Formatter};
+
+/// Tuning parameters of the `apply` algorithm.
+///
+/// The default values correspond to the constants used by `apply` and were used to obtain
+/// the measurements in the `PERF_*.md` documents.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ApplyConfig {
+    /// The initial capacity of the task cache. By default, this is the size of the left BDD,
+    /// which is also the minimal allowed capacity.
+    pub task_cache_capacity: Option<u64>,
+    /// The initial capacity of the node cache. By default, this is half of the size
+    /// of the left BDD.
+    pub node_cache_capacity: Option<u64>,
+    /// The task cache doubles once it has seen `task_cache_load_factor * capacity` insertions
+    /// since the last growth. Must be at least `1`.
+    pub task_cache_load_factor: u64,
+    /// When the node cache is full, its capacity is multiplied by this number.
+    /// Must be at least `2`.
+    pub node_cache_growth_factor: u64,
+    /// The caches are checked for growth after `left_bdd.get_size() >> check_interval_shift`
+    /// iterations...
+    pub check_interval_shift: u32,
+    /// ...but at least after this many iterations.
+    pub min_check_interval: u64,
+    /// The maximal amount of memory (in bytes) that the task cache, node cache and task stack
+    /// can use. The limit is checked before the caches grow, so it is never exceeded by more
+    /// than the initial allocation.
+    pub memory_limit: Option<usize>,
+    /// A token which is polled every time the caches are checked for growth. Once it is
+    /// cancelled, the computation stops with `ApplyError::Cancelled`.
+    pub cancellation: Option<CancellationToken>,
+    /// The implementation of the algorithm. Both engines produce the same results.
+    pub engine: ApplyEngine,
+    /// If set, the in-order engine issues software prefetches for the operand nodes and task
+    /// cache slots of every newly created sub-task. This can hide some memory latency for large
+    /// BDDs, but costs extra instructions. Ignored by the out-of-order engine.
+    pub prefetch: bool,
+}
+
+/// The implementation used by `apply_with_config` and `ApplyContext`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ApplyEngine {
+    /// The classic depth-first algorithm which finishes every task as soon as both of its
+    /// sub-tasks are done.
+    #[default]
+    InOrder,
+    /// A pipelined algorithm which can work on several tasks at once to hide the latency
+    /// of the node cache lookups.
+    OutOfOrder,
+}
+
+impl Default for ApplyConfig {
+    fn default() -> Self {
+        ApplyConfig {
+            task_cache_capacity: None,
+            node_cache_capacity: None,
+            task_cache_load_factor: 2,
+            node_cache_growth_factor: 2,
+            check_interval_shift: 3,
+            min_check_interval: 1024,
+            memory_limit: None,
+            cancellation: None,
+            engine: ApplyEngine::default(),
+            prefetch: false,
+        }
+    }
+}
+
+/// Statistics collected during a single `apply` operation.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ApplyStats {
+    /// The number of tasks that had to be expanded (i.e. were not resolved as terminals
+    /// or found in the task cache).
+    pub tasks_expanded: usize,
+    /// The number of tasks that were found in the task cache.
+    pub task_cache_hits: usize,
+    /// The number of task cache writes which overwrote the result of a different task.
+    pub task_cache_collisions: usize,
+    /// The total number of additional links followed in the node cache collision chains.
+    pub node_cache_chain_steps: usize,
+    /// The longest collision chain traversed in the node cache.
+    pub node_cache_max_chain: usize,
+    /// The number of times the task cache was grown.
+    pub task_cache_grow_events: usize,
+    /// The number of times the node cache was grown.
+    pub node_cache_grow_events: usize,
+    /// The largest amount of memory (in bytes) used by the task cache, node cache and task stack,
+    /// measured every time the caches are checked for growth (including the temporary
+    /// allocations made while growing).
+    pub peak_memory: usize,
+}
+
+/// The reason why an `apply` operation did not finish.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ApplyError {
+    /// The caches would have to grow past the `memory_limit` of the config. Contains
+    /// the statistics of the unfinished computation and an estimate of how much memory
+    /// (in bytes, beyond the limit) would be necessary for the next growth step.
+    /// Note that the computation itself may need even more memory to finish.
+    MemoryLimit {
+        stats: ApplyStats,
+        additional_memory: usize,
+    },
+    /// The `cancellation` token of the config was cancelled. Contains the statistics
+    /// of the unfinished computation.
+    Cancelled { stats: ApplyStats },
+}
+
+impl Display for ApplyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApplyError::MemoryLimit { additional_memory, .. } => write!(
+                f,
+                "Memory limit exceeded. At least {} more bytes are needed.",
+                additional_memory
+            ),
+            ApplyError::Cancelled { .. } => write!(f, "Operation cancelled."),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ApplyConfig, ApplyEngine, ApplyError};
+    use crate::v4::core::CancellationToken;
+    use crate::v4::apply::{apply, apply_with_config, And};
+    use crate::v4::test_util::nodes;
+    use crate::verify::{FunctionTable, Lcg};
+
+    #[test]
+    pub fn apply_config_and_stats() {
+        let mut rng = Lcg::new(11);
+        let left = FunctionTable::random(16, 50, &mut rng).to_bdd();
+        let right = FunctionTable::random(16, 50, &mut rng).to_bdd();
+        let (expected, expected_count) = apply(&left, &right, And);
+
+        let (result, stats) = apply_with_config(&left, &right, And, &ApplyConfig::default()).unwrap();
+        assert_eq!(nodes(&expected), nodes(&result));
+        assert_eq!(expected_count, stats.tasks_expanded);
+        assert!(stats.peak_memory > 0);
+
+        // Prefetching does not change the computation at all.
+        let prefetch = ApplyConfig {
+            prefetch: true,
+            ..ApplyConfig::default()
+        };
+        let (result, prefetch_stats) = apply_with_config(&left, &right, And, &prefetch).unwrap();
+        assert_eq!(nodes(&expected), nodes(&result));
+        assert_eq!(stats, prefetch_stats);
+
+        // Tiny caches that are checked often have to grow many times, but the result is the same.
+        let config = ApplyConfig {
+            task_cache_capacity: Some(1),
+            node_cache_capacity: Some(2),
+            task_cache_load_factor: 1,
+            node_cache_growth_factor: 4,
+            check_interval_shift: 10,
+            min_check_interval: 1,
+            memory_limit: None,
+            cancellation: None,
+            engine: ApplyEngine::InOrder,
+            prefetch: false,
+        };
+        let (result, small) = apply_with_config(&left, &right, And, &config).unwrap();
+        assert_eq!(nodes(&expected), nodes(&result));
+        assert!(small.node_cache_grow_events > 0);
+        assert!(small.task_cache_grow_events > 0);
+        assert!(small.node_cache_max_chain <= small.node_cache_chain_steps);
+        assert!(small.tasks_expanded + small.task_cache_hits > 0);
+
+        // With a memory limit below the peak memory, the computation has to stop.
+        let limited = ApplyConfig {
+            memory_limit: Some(small.peak_memory / 2),
+            ..config.clone()
+        };
+        match apply_with_config(&left, &right, And, &limited) {
+            Err(ApplyError::MemoryLimit { stats, additional_memory }) => {
+                assert!(additional_memory > 0);
+                assert!(stats.peak_memory > small.peak_memory / 2);
+                assert!(stats.tasks_expanded < small.tasks_expanded);
+            }
+            _ => panic!("Memory limit not enforced."),
+        }
+        let unlimited = ApplyConfig {
+            memory_limit: Some(small.peak_memory),
+            ..limited
+        };
+        assert!(apply_with_config(&left, &right, And, &unlimited).is_ok());
+
+        // A cancelled token stops the computation at the first check.
+        let token = CancellationToken::new();
+        token.cancel();
+        let cancelled = ApplyConfig {
+            cancellation: Some(token),
+            ..config
+        };
+        match apply_with_config(&left, &right, And, &cancelled) {
+            Err(ApplyError::Cancelled { stats }) => assert_eq!(0, stats.tasks_expanded),
+            _ => panic!("Cancellation not enforced."),
+        }
+    }
+}
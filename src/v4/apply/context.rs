@@ -0,0 +1,118 @@
+use super::super::core::Bdd;
+use super::node_cache::NodeCache;
+use super::task_cache::TaskCache;
+use super::unsafe_stack::UnsafeStack;
+use super::ooo::ooo_apply_in;
+use super::{apply_in, node_cache_capacity, stack_capacity, task_cache_capacity, ApplyConfig, ApplyEngine, ApplyError, ApplyStats, ApplyTask, BooleanOperator};
+
+/// A persistent context for running many `apply` operations in a row.
+///
+/// Every call to `apply` allocates (and erases) a fresh task cache, node cache and task stack.
+/// For mid-size BDDs, this can easily dominate the cost of the whole operation. The context
+/// owns these structures instead and only clears them between operations. They are reallocated
+/// only when the operands of an operation do not fit into the current capacity.
+///
+/// The results are the same as when using `apply` directly.
+pub struct ApplyContext {
+    config: ApplyConfig,
+    task_cache: TaskCache,
+    node_cache: NodeCache,
+    stack: UnsafeStack<ApplyTask>,
+}
+
+impl Default for ApplyContext {
+    fn default() -> Self {
+        ApplyContext::new()
+    }
+}
+
+impl ApplyContext {
+    /// Create a new context with minimal capacity. The structures are grown on demand.
+    pub fn new() -> ApplyContext {
+        ApplyContext::with_config(ApplyConfig::default())
+    }
+
+    /// Create a new context which uses the given `config` for all operations.
+    pub fn with_config(config: ApplyConfig) -> ApplyContext {
+        ApplyContext {
+            config,
+            task_cache: TaskCache::new(0),
+            node_cache: NodeCache::new(2),
+            stack: UnsafeStack::new(0),
+        }
+    }
+
+    /// Compute the result of the given Boolean `operator` applied to the two BDDs
+    /// (see `v4::apply::apply`).
+    ///
+    /// *Panics:* If the computation exceeds the limits of the context config. Use
+    /// `ApplyContext::apply_with_stats` to handle such errors.
+    pub fn apply<Op: BooleanOperator>(&mut self, left_bdd: &Bdd, right_bdd: &Bdd, operator: Op) -> (Bdd, usize) {
+        match self.apply_with_stats(left_bdd, right_bdd, operator) {
+            Ok((result, stats)) => (result, stats.tasks_expanded),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// The same as `ApplyContext::apply`, but returns detailed statistics about the computation
+    /// or an error if the limits of the context config are exceeded.
+    pub fn apply_with_stats<Op: BooleanOperator>(
+        &mut self,
+        left_bdd: &Bdd,
+        right_bdd: &Bdd,
+        operator: Op,
+    ) -> Result<(Bdd, ApplyStats), ApplyError> {
+        self.task_cache.clear(task_cache_capacity(left_bdd, &self.config));
+        self.node_cache.clear();
+        self.node_cache.ensure_capacity(node_cache_capacity(left_bdd, &self.config));
+        match self.config.engine {
+            ApplyEngine::InOrder => {
+                self.stack.reset(stack_capacity(left_bdd, right_bdd));
+                apply_in(
+                    left_bdd,
+                    right_bdd,
+                    operator,
+                    &self.config,
+                    &mut self.task_cache,
+                    &mut self.node_cache,
+                    &mut self.stack,
+                )
+            }
+            ApplyEngine::OutOfOrder => ooo_apply_in(
+                left_bdd,
+                right_bdd,
+                operator,
+                &self.config,
+                &mut self.task_cache,
+                &mut self.node_cache,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ApplyContext;
+    use crate::v4::apply::{apply, And, Or, Xor};
+    use crate::v4::core::Bdd;
+    use crate::v4::test_util::nodes;
+    use crate::verify::{FunctionTable, Lcg};
+
+    #[test]
+    pub fn apply_context_matches_apply() {
+        let mut rng = Lcg::new(3);
+        let mut context = ApplyContext::new();
+        // Alternate between small and large operands to exercise both reuse and regrowth.
+        for variables in &[4, 10, 6, 12, 3, 12] {
+            let left = FunctionTable::random(*variables, 50, &mut rng).to_bdd();
+            let right = FunctionTable::random(*variables, 30, &mut rng).to_bdd();
+            let (expected, expected_count) = apply(&left, &right, And);
+            let (result, count) = context.apply(&left, &right, And);
+            assert_eq!(nodes(&expected), nodes(&result));
+            assert_eq!(expected_count, count);
+            assert_eq!(nodes(&apply(&right, &left, Xor).0), nodes(&context.apply(&right, &left, Xor).0));
+        }
+        let literal = Bdd::new_literal(0.into(), true);
+        assert!(context.apply(&literal, &literal.not(), Or).0.is_one());
+    }
+}
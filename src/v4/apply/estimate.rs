@@ -0,0 +1,164 @@
+use super::super::core::{Bdd, NodeIndex, Variable};
+use super::BooleanOperator;
+use fxhash::{FxHashMap, FxHashSet};
+use std::cmp::min;
+
+/// An upper bound on the number of nodes in the result of `apply(left_bdd, right_bdd, _)`,
+/// computed only from the number of nodes of each variable in the two BDDs.
+///
+/// Every decision node of the result is created by a task `(l, r)` whose variable is the smaller
+/// of the two node variables. Hence, for each variable `v`, there are at most
+/// `left(v) * right(>= v) + right(v) * left(> v)` such tasks. The bound is valid for every
+/// operator and is never larger than the product of the two BDD sizes.
+pub fn apply_size_bound(left_bdd: &Bdd, right_bdd: &Bdd) -> u64 {
+    let left_counts = variable_counts(left_bdd);
+    let right_counts = variable_counts(right_bdd);
+    let mut variables: Vec<Variable> = left_counts.keys().chain(right_counts.keys()).cloned().collect();
+    variables.sort();
+    variables.dedup();
+
+    // Process variables bottom-up, such that the number of nodes below each variable
+    // (including terminals) is known.
+    let mut left_below: u64 = 2;
+    let mut right_below: u64 = 2;
+    let mut bound: u64 = 2;
+    for variable in variables.iter().rev() {
+        let left_count = left_counts.get(variable).cloned().unwrap_or(0);
+        let right_count = right_counts.get(variable).cloned().unwrap_or(0);
+        let tasks = left_count * (right_count + right_below) + right_count * left_below;
+        bound = bound.saturating_add(tasks);
+        left_below += left_count;
+        right_below += right_count;
+    }
+    min(bound, left_bdd.get_size().saturating_mul(right_bdd.get_size()))
+}
+
+/// Count the tasks that `apply` would expand for the given BDDs and `operator`, using the coupled
+/// DFS without creating any output nodes. The number of result nodes is at most the number
+/// of tasks plus the two terminals.
+///
+/// The count is exact (there is no leaky cache), but it also needs memory proportional to the
+/// number of tasks. If `limit` is given, the search stops and returns `None` once more than
+/// `limit` tasks are found.
+pub fn count_apply_tasks<Op: BooleanOperator>(
+    left_bdd: &Bdd,
+    right_bdd: &Bdd,
+    operator: Op,
+    limit: Option<u64>,
+) -> Option<u64> {
+    let limit = limit.unwrap_or(u64::MAX);
+    let mut visited: FxHashSet<Task> = FxHashSet::default();
+    let mut stack = vec![(left_bdd.get_root_index(), right_bdd.get_root_index())];
+    while let Some(task) = stack.pop() {
+        if !operator.resolve(task.0, task.1).is_undefined() || !visited.insert(task) {
+            continue;
+        }
+        if visited.len() as u64 > limit {
+            return None;
+        }
+        let (low, high) = expand(left_bdd, right_bdd, task);
+        stack.push(high);
+        stack.push(low);
+    }
+    Some(visited.len() as u64)
+}
+
+/// Estimate the number of nodes in the result of `apply(left_bdd, right_bdd, operator)` using
+/// `samples` random walks through the coupled DFS.
+///
+/// Each walk starts in the root task and follows a random child until the task is resolved
+/// by the `operator`. The number of distinct tasks is then estimated from the visited tasks using
+/// the (bias corrected) Chao1 estimator. Tasks which are only reachable by few valuations are
+/// easy to miss, so the estimate tends to be too low for small sample counts, but it is never
+/// larger than `apply_size_bound`. The walks are deterministic for a fixed `seed`.
+pub fn estimate_apply_size<Op: BooleanOperator>(
+    left_bdd: &Bdd,
+    right_bdd: &Bdd,
+    operator: Op,
+    samples: usize,
+    seed: u64,
+) -> u64 {
+    let mut seed = seed;
+    let mut frequency: FxHashMap<Task, u64> = FxHashMap::default();
+    for _ in 0..samples {
+        let mut task = (left_bdd.get_root_index(), right_bdd.get_root_index());
+        while operator.resolve(task.0, task.1).is_undefined() {
+            *frequency.entry(task).or_insert(0) += 1;
+            let (low, high) = expand(left_bdd, right_bdd, task);
+            // Same LCG as used elsewhere for test data; the high bits are the most random ones.
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            task = if seed >> 63 == 1 { high } else { low };
+        }
+    }
+
+    let observed = frequency.len() as u64;
+    let singletons = frequency.values().filter(|it| **it == 1).count() as u64;
+    let doubletons = frequency.values().filter(|it| **it == 2).count() as u64;
+    let unseen = singletons * singletons.saturating_sub(1) / (2 * (doubletons + 1));
+    min(observed + unseen + 2, apply_size_bound(left_bdd, right_bdd))
+}
+
+type Task = (NodeIndex, NodeIndex);
+
+/// The `(low, high)` sub-tasks of the given `task`.
+#[inline]
+fn expand(left_bdd: &Bdd, right_bdd: &Bdd, task: Task) -> (Task, Task) {
+    let (left, right) = task;
+    let (l_var, l_low, l_high) = left_bdd.get_node(left).unpack();
+    let (r_var, r_low, r_high) = right_bdd.get_node(right).unpack();
+    let variable = min(l_var, r_var);
+    let (l_low, l_high) = if l_var == variable { (l_low, l_high) } else { (left, left) };
+    let (r_low, r_high) = if r_var == variable { (r_low, r_high) } else { (right, right) };
+    ((l_low, r_low), (l_high, r_high))
+}
+
+/// The number of decision nodes of each variable.
+fn variable_counts(bdd: &Bdd) -> FxHashMap<Variable, u64> {
+    let mut counts = FxHashMap::default();
+    for index in bdd.iter_indices() {
+        let node = bdd.get_node(index);
+        if !node.is_terminal() {
+            *counts.entry(node.get_variable()).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod test {
+    use super::{apply_size_bound, count_apply_tasks, estimate_apply_size};
+    use crate::v4::apply::{apply, And, Or, Xor};
+    use crate::verify::{FunctionTable, Lcg};
+
+    #[test]
+    pub fn apply_size_estimates() {
+        let mut rng = Lcg::new(23);
+        for (l_density, r_density) in &[(50, 50), (20, 70), (90, 10), (0, 50)] {
+            let left = FunctionTable::random(10, *l_density, &mut rng).to_bdd();
+            let right = FunctionTable::random(10, *r_density, &mut rng).to_bdd();
+            let bound = apply_size_bound(&left, &right);
+            assert!(bound <= left.get_size() * right.get_size());
+
+            let (result, expanded) = apply(&left, &right, And);
+            let tasks = count_apply_tasks(&left, &right, And, None).unwrap();
+            // The task cache of `apply` is leaky, so it can expand some tasks repeatedly.
+            assert!(expanded as u64 >= tasks);
+            assert!(result.get_size() <= tasks + 2);
+            assert!(tasks + 2 <= bound);
+            if tasks > 10 {
+                assert_eq!(None, count_apply_tasks(&left, &right, And, Some(10)));
+            }
+
+            // With enough samples, the estimate should be in the right ballpark.
+            let estimate = estimate_apply_size(&left, &right, And, 5000, 7);
+            assert!(estimate <= bound);
+            assert!(2 * estimate >= tasks + 2);
+
+            let (result, _) = apply(&left, &right, Xor);
+            assert!(result.get_size() <= count_apply_tasks(&left, &right, Xor, None).unwrap() + 2);
+            assert!(estimate_apply_size(&left, &right, Or, 100, 7) <= bound);
+        }
+    }
+}
@@ -1,6 +1,24 @@
-mod task_cache;
-mod unsafe_stack;
-mod node_cache;
+pub(crate) mod task_cache;
+pub(crate) mod unsafe_stack;
+pub(crate) mod node_cache;
+mod shared_task_cache;
+mod sharded_node_cache;
+mod parallel;
+mod operator;
+mod context;
+mod config;
+mod predicates;
+mod estimate;
+mod nary;
+mod ooo;
+
+pub use operator::{And, AndNot, BooleanOperator, Iff, Imp, Or, TruthTable, Xor};
+pub use parallel::{parallel_apply, parallel_apply_cancellable};
+pub use context::ApplyContext;
+pub use config::{ApplyConfig, ApplyEngine, ApplyError, ApplyStats};
+pub use predicates::find_witness;
+pub use estimate::{apply_size_bound, count_apply_tasks, estimate_apply_size};
+pub use nary::{and_all, or_all};
 
 use super::core::{Bdd, Variable, NodeIndex, Node};
 use task_cache::{TaskCache, TaskCacheSlot};
@@ -10,12 +28,12 @@ use crate::IntoIndex;
 use std::cmp::{max, min};
 
 #[derive(Copy, Clone, Eq, PartialEq)]
-struct ApplyTask {
+pub(crate) struct ApplyTask {
     offset: u8,
-    variable: Variable,
-    task: (NodeIndex, NodeIndex),
-    results: [NodeIndex; 2],
-    task_cache_slot: TaskCacheSlot,
+    pub(crate) variable: Variable,
+    pub(crate) task: (NodeIndex, NodeIndex),
+    pub(crate) results: [NodeIndex; 2],
+    pub(crate) task_cache_slot: TaskCacheSlot,
 }
 
 impl ApplyTask {
@@ -46,22 +64,115 @@ impl ApplyTask {
     }
 }
 
-pub fn apply(left_bdd: &Bdd, right_bdd: &Bdd) -> (usize, usize) {
+/// Compute the result of the given Boolean `operator` applied to the two BDDs.
+///
+/// Returns the resulting `Bdd` together with the number of tasks that had to be expanded
+/// to compute it.
+pub fn apply<Op: BooleanOperator>(left_bdd: &Bdd, right_bdd: &Bdd, operator: Op) -> (Bdd, usize) {
+    match apply_with_config(left_bdd, right_bdd, operator, &ApplyConfig::default()) {
+        Ok((result, stats)) => (result, stats.tasks_expanded),
+        Err(error) => unreachable!("Unlimited apply failed: {:?}", error),
+    }
+}
+
+/// The same as `apply`, but uses the given `config` and returns detailed statistics
+/// about the computation.
+///
+/// Returns an error if the computation cannot finish within the limits given by the `config`.
+pub fn apply_with_config<Op: BooleanOperator>(
+    left_bdd: &Bdd,
+    right_bdd: &Bdd,
+    operator: Op,
+    config: &ApplyConfig,
+) -> Result<(Bdd, ApplyStats), ApplyError> {
+    let mut task_cache = TaskCache::new(task_cache_capacity(left_bdd, config));
+    let mut node_cache = NodeCache::new(node_cache_capacity(left_bdd, config));
+    match config.engine {
+        ApplyEngine::InOrder => {
+            let mut stack = UnsafeStack::new(stack_capacity(left_bdd, right_bdd));
+            apply_in(left_bdd, right_bdd, operator, config, &mut task_cache, &mut node_cache, &mut stack)
+        }
+        ApplyEngine::OutOfOrder => {
+            ooo::ooo_apply_in(left_bdd, right_bdd, operator, config, &mut task_cache, &mut node_cache)
+        }
+    }
+}
+
+/// The initial task cache capacity for the given left BDD. The task cache uses the left node
+/// index as the hash base, so the capacity can never be smaller than the left BDD.
+fn task_cache_capacity(left_bdd: &Bdd, config: &ApplyConfig) -> u64 {
+    let capacity = config.task_cache_capacity.unwrap_or_else(|| left_bdd.get_size());
+    max(capacity, left_bdd.get_size())
+}
+
+/// The initial node cache capacity for the given left BDD.
+fn node_cache_capacity(left_bdd: &Bdd, config: &ApplyConfig) -> u64 {
+    let capacity = config.node_cache_capacity.unwrap_or_else(|| left_bdd.get_size() / 2);
+    max(capacity, 2)
+}
+
+/// The stack capacity needed to apply an operator to the two BDDs.
+///
+/// There are up to `height_limit` expanded tasks and every task has up to one extra non-expanded
+/// child. Plus one for the root task.
+fn stack_capacity(left_bdd: &Bdd, right_bdd: &Bdd) -> usize {
     let height_limit = left_bdd.get_height() + right_bdd.get_height();
-    let mut task_cache = TaskCache::new(left_bdd.get_size());
-    let mut node_cache = NodeCache::new(left_bdd.get_size() / 2);
-    let mut task_count = 0;
+    2 * height_limit.into_index() + 1
+}
+
+/// The actual implementation of `apply` using the given (empty) caches and stack. The stack
+/// must have at least `stack_capacity` slots.
+fn apply_in<Op: BooleanOperator>(
+    left_bdd: &Bdd,
+    right_bdd: &Bdd,
+    operator: Op,
+    config: &ApplyConfig,
+    task_cache: &mut TaskCache,
+    node_cache: &mut NodeCache,
+    stack: &mut UnsafeStack<ApplyTask>,
+) -> Result<(Bdd, ApplyStats), ApplyError> {
+    // Prefetching is a compile-time parameter, such that the default version has exactly
+    // the same instructions as before.
+    if config.prefetch {
+        apply_in_impl::<Op, true>(left_bdd, right_bdd, operator, config, task_cache, node_cache, stack)
+    } else {
+        apply_in_impl::<Op, false>(left_bdd, right_bdd, operator, config, task_cache, node_cache, stack)
+    }
+}
+
+fn apply_in_impl<Op: BooleanOperator, const PREFETCH: bool>(
+    left_bdd: &Bdd,
+    right_bdd: &Bdd,
+    operator: Op,
+    config: &ApplyConfig,
+    task_cache: &mut TaskCache,
+    node_cache: &mut NodeCache,
+    stack: &mut UnsafeStack<ApplyTask>,
+) -> Result<(Bdd, ApplyStats), ApplyError> {
+    task_cache.set_load_factor(config.task_cache_load_factor);
+    node_cache.set_growth_factor(config.node_cache_growth_factor);
+    // The caches can be reused, so only the changes of their counters are reported.
+    let initial_counters = CacheCounters::read(task_cache, node_cache);
+    let mut stats = ApplyStats::default();
 
-    // There are up to height_limit expanded tasks and every task has up to one extra non-expanded
-    // child.
-    let mut stack = UnsafeStack::new(2 * height_limit.into_index());
     stack.push(ApplyTask::new(0, (left_bdd.get_root_index(), right_bdd.get_root_index())));
+    let root_result;
 
     'main: loop {
         // Aim to perform at least left_bdd.size / 8 iterations before checking again if stuff
         // needs to grow. Also, cap this at at least 1024 iterations (but assumption is that this
-        // algorithm should not be used for BDDs that small).
-        let base_iterations = max(left_bdd.get_size() >> 3, 1024);
+        // algorithm should not be used for BDDs that small). Both values are configurable.
+        let base_iterations = max(left_bdd.get_size() >> config.check_interval_shift, config.min_check_interval);
+        // Check that the caches can grow (if needed) before actually growing them.
+        check_limits(
+            config,
+            task_cache,
+            node_cache,
+            base_iterations,
+            stack.memory(),
+            &initial_counters,
+            &mut stats,
+        )?;
         // This will give us actual max number of iterations, but will try to be at least as
         // large as base_iterations.
         let node_iterations = node_cache.ensure_capacity(base_iterations);
@@ -74,24 +185,22 @@ pub fn apply(left_bdd: &Bdd, right_bdd: &Bdd) -> (usize, usize) {
             let top = stack.peek();
             let top_offset: usize = top.get_offset().into(); // Save for later...
 
-            let mut result = NodeIndex::UNDEFINED;
+            let mut result;
             // We could also try using top.variable, but this version seems to be faster
             // due to easier branch prediction.
             if top.is_not_decoded() {
                 top.mark_as_decoded();
 
                 let (left, right) = top.task;
-                if left.is_one() || right.is_one() {
-                    result = NodeIndex::ONE;
-                } else if left.is_zero() && right.is_zero() {
-                    result = NodeIndex::ZERO;
-                } else {
+                result = operator.resolve(left, right);
+                if result.is_undefined() {
                     let (cached, slot) = task_cache.read(top.task);
                     if !cached.is_undefined() {
                         result = cached;
+                        stats.task_cache_hits += 1;
                     } else {
                         top.task_cache_slot = slot;
-                        task_count += 1;
+                        stats.tasks_expanded += 1;
                         // Actually expand this task into sub-tasks.
 
                         let left_node = unsafe { left_bdd.get_node_unchecked(left) };
@@ -115,6 +224,20 @@ pub fn apply(left_bdd: &Bdd, right_bdd: &Bdd) -> (usize, usize) {
                             stack.push(ApplyTask::new(1, (left, r_high)));
                             stack.push(ApplyTask::new(2, (left, r_low)));
                         }
+
+                        if PREFETCH {
+                            // The new sub-tasks are decoded in the following iterations, so we
+                            // can start loading their operands and task cache slots right away.
+                            // The high task waits longer, hence it benefits the most.
+                            let high_task = stack.peek_at(2).task;
+                            let low_task = stack.peek().task;
+                            left_bdd.prefetch(high_task.0);
+                            right_bdd.prefetch(high_task.1);
+                            task_cache.prefetch(high_task);
+                            left_bdd.prefetch(low_task.0);
+                            right_bdd.prefetch(low_task.1);
+                            task_cache.prefetch(low_task);
+                        }
                     }
                 }
             } else {
@@ -126,9 +249,13 @@ pub fn apply(left_bdd: &Bdd, right_bdd: &Bdd) -> (usize, usize) {
                     let node = Node::pack(top.variable, result_low, result_high);
 
                     let mut cached = node_cache.ensure(&node);
+                    let mut chain = 0;
                     while let Err(slot) = cached {
                         cached = node_cache.ensure_at(&node, slot);
+                        chain += 1;
                     }
+                    stats.node_cache_chain_steps += chain;
+                    stats.node_cache_max_chain = max(stats.node_cache_max_chain, chain);
                     result = cached.unwrap();
                 }
                 task_cache.write(top.task_cache_slot, top.task, result);
@@ -136,6 +263,11 @@ pub fn apply(left_bdd: &Bdd, right_bdd: &Bdd) -> (usize, usize) {
 
             if !result.is_undefined() {
                 stack.pop();
+                if stack.is_empty() {
+                    // Only the root task has no parent to which the result can be saved.
+                    root_result = result;
+                    break 'main;
+                }
                 // Offset one is the top task, offset two is the one beneath that.
                 let parent = stack.peek_at(top_offset);
                 // high = 1, low = 2, so they will be saved in reverse order.
@@ -144,12 +276,70 @@ pub fn apply(left_bdd: &Bdd, right_bdd: &Bdd) -> (usize, usize) {
                 };
                 *slot = result;
             }
+        }
+    }
 
-            if stack.is_empty() {
-                break 'main;
-            }
+    CacheCounters::read(task_cache, node_cache).update_stats(&initial_counters, &mut stats);
+    Ok((node_cache.export(root_result), stats))
+}
+
+/// Poll the cancellation token of the `config` and check that the caches can grow such that
+/// the node cache has room for `node_capacity` new nodes without exceeding the memory limit.
+/// The `extra_memory` is used by other structures of the algorithm (e.g. the task stack).
+///
+/// Also updates the peak memory in `stats`. In case of an error, the returned statistics
+/// include the changes of the cache counters since `initial_counters`.
+fn check_limits(
+    config: &ApplyConfig,
+    task_cache: &TaskCache,
+    node_cache: &NodeCache,
+    node_capacity: u64,
+    extra_memory: usize,
+    initial_counters: &CacheCounters,
+    stats: &mut ApplyStats,
+) -> Result<(), ApplyError> {
+    if let Some(cancellation) = &config.cancellation {
+        if cancellation.is_cancelled() {
+            CacheCounters::read(task_cache, node_cache).update_stats(initial_counters, stats);
+            return Err(ApplyError::Cancelled { stats: stats.clone() });
         }
     }
+    let required = task_cache.memory_after_growth()
+        + node_cache.memory_after_growth(node_capacity)
+        + extra_memory;
+    stats.peak_memory = max(stats.peak_memory, required);
+    if let Some(limit) = config.memory_limit {
+        if required > limit {
+            CacheCounters::read(task_cache, node_cache).update_stats(initial_counters, stats);
+            return Err(ApplyError::MemoryLimit {
+                stats: stats.clone(),
+                additional_memory: required - limit,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// A snapshot of the counters maintained by the caches.
+struct CacheCounters {
+    task_grow_count: usize,
+    node_grow_count: usize,
+    collisions: usize,
+}
 
-    (node_cache.len(), task_count)
+impl CacheCounters {
+    fn read(task_cache: &TaskCache, node_cache: &NodeCache) -> CacheCounters {
+        CacheCounters {
+            task_grow_count: task_cache.grow_count(),
+            node_grow_count: node_cache.grow_count(),
+            collisions: task_cache.collisions(),
+        }
+    }
+
+    /// Write the difference between these counters and the `initial` counters into `stats`.
+    fn update_stats(&self, initial: &CacheCounters, stats: &mut ApplyStats) {
+        stats.task_cache_grow_events = self.task_grow_count - initial.task_grow_count;
+        stats.node_cache_grow_events = self.node_grow_count - initial.node_grow_count;
+        stats.task_cache_collisions = self.collisions - initial.collisions;
+    }
 }
\ No newline at end of file
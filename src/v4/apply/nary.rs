@@ -0,0 +1,131 @@
+use super::super::core::Bdd;
+use super::{And, ApplyContext, BooleanOperator, Or};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Compute the conjunction of all the given BDDs (see `ApplyContext::and_all`).
+pub fn and_all<I: IntoIterator<Item = Bdd>>(bdds: I) -> Bdd {
+    ApplyContext::new().and_all(bdds)
+}
+
+/// Compute the disjunction of all the given BDDs (see `ApplyContext::or_all`).
+pub fn or_all<I: IntoIterator<Item = Bdd>>(bdds: I) -> Bdd {
+    ApplyContext::new().or_all(bdds)
+}
+
+/// Operations on many BDDs at once.
+impl ApplyContext {
+    /// Compute the conjunction of all the given BDDs. For an empty iterator, the result is `true`.
+    ///
+    /// Instead of conjoining the BDDs in the input order, the two smallest BDDs are always
+    /// conjoined first, such that the intermediate results stay as small as possible. The caches
+    /// of this context are reused for all the pairwise operations. The computation stops early
+    /// once an intermediate result is `false`.
+    pub fn and_all<I: IntoIterator<Item = Bdd>>(&mut self, bdds: I) -> Bdd {
+        self.apply_all(bdds, And, Bdd::new_one(), Bdd::is_zero)
+    }
+
+    /// Compute the disjunction of all the given BDDs. For an empty iterator, the result is `false`.
+    ///
+    /// The BDDs are combined in the same order as in `ApplyContext::and_all`, and the computation
+    /// stops early once an intermediate result is `true`.
+    pub fn or_all<I: IntoIterator<Item = Bdd>>(&mut self, bdds: I) -> Bdd {
+        self.apply_all(bdds, Or, Bdd::new_zero(), Bdd::is_one)
+    }
+
+    /// Combine all BDDs using an associative and commutative `operator` with the given `neutral`
+    /// element. Once an `absorbing` result is found, it is returned immediately.
+    fn apply_all<I, Op, F>(&mut self, bdds: I, operator: Op, neutral: Bdd, absorbing: F) -> Bdd
+    where
+        I: IntoIterator<Item = Bdd>,
+        Op: BooleanOperator,
+        F: Fn(&Bdd) -> bool,
+    {
+        let mut queue = SizeQueue::default();
+        for bdd in bdds {
+            if absorbing(&bdd) {
+                return bdd;
+            }
+            queue.push(bdd);
+        }
+
+        loop {
+            let smaller = match queue.pop() {
+                Some(bdd) => bdd,
+                None => return neutral,
+            };
+            let larger = match queue.pop() {
+                Some(bdd) => bdd,
+                None => return smaller,
+            };
+            // The task cache works best when the larger BDD is on the left.
+            let (result, _) = self.apply(&larger, &smaller, operator);
+            if absorbing(&result) {
+                return result;
+            }
+            queue.push(result);
+        }
+    }
+}
+
+/// A priority queue of BDDs where the smallest BDD is removed first.
+///
+/// The heap stores indices into `operands`. The index also serves as a tie-breaker, which keeps
+/// the order of operations deterministic.
+#[derive(Default)]
+struct SizeQueue {
+    heap: BinaryHeap<Reverse<(u64, usize)>>,
+    operands: Vec<Option<Bdd>>,
+}
+
+impl SizeQueue {
+    fn push(&mut self, bdd: Bdd) {
+        self.heap.push(Reverse((bdd.get_size(), self.operands.len())));
+        self.operands.push(Some(bdd));
+    }
+
+    fn pop(&mut self) -> Option<Bdd> {
+        let Reverse((_, index)) = self.heap.pop()?;
+        self.operands[index].take()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{and_all, or_all};
+    use crate::v4::apply::{apply, And, ApplyContext, Or};
+    use crate::v4::core::{Bdd, Variable};
+    use crate::v4::test_util::nodes;
+    use crate::verify::{FunctionTable, Lcg};
+
+    #[test]
+    pub fn nary_operations() {
+        let mut rng = Lcg::new(41);
+        let bdds: Vec<Bdd> = (0..12)
+            .map(|i| FunctionTable::random(8, 60 + i, &mut rng).to_bdd())
+            .collect();
+        let conjunction = bdds.iter().fold(Bdd::new_one(), |result, bdd| apply(&result, bdd, And).0);
+        let disjunction = bdds.iter().fold(Bdd::new_zero(), |result, bdd| apply(&result, bdd, Or).0);
+        assert_eq!(nodes(&conjunction), nodes(&and_all(bdds.clone())));
+        assert_eq!(nodes(&disjunction), nodes(&or_all(bdds.clone())));
+
+        // Clauses `x_i | !x_{i+1}` conjoined with `x_7` imply all variables.
+        let mut context = ApplyContext::new();
+        let clauses: Vec<Bdd> = (0..7u32)
+            .map(|i| {
+                let x = Bdd::new_literal(Variable::from(i), true);
+                let y = Bdd::new_literal(Variable::from(i + 1), false);
+                apply(&x, &y, Or).0
+            })
+            .chain(std::iter::once(Bdd::new_literal(Variable::from(7), true)))
+            .collect();
+        let all = context.and_all(clauses);
+        assert_eq!(10, all.get_size());
+
+        assert!(and_all(Vec::new()).is_one());
+        assert!(or_all(Vec::new()).is_zero());
+        let literal = Bdd::new_literal(Variable::from(3), true);
+        assert!(context.and_all(vec![literal.clone(), literal.not()]).is_zero());
+        assert!(context.or_all(vec![literal.clone(), Bdd::new_one(), literal]).is_one());
+    }
+}
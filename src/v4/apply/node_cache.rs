@@ -1,6 +1,7 @@
 use std::cmp::max;
+use std::mem::size_of;
 use crate::{FromIndex, IntoIndex};
-use super::super::core::{Node, NodeIndex};
+use super::super::core::{Bdd, Node, NodeIndex};
 
 /// Node cache serves as a temporary storage for BDD nodes which is responsible for ensuring that
 /// each node is given a unique index, and that there are no duplicate nodes.
@@ -31,6 +32,10 @@ pub struct NodeCache {
     index_after_last: u64,
     nodes: Vec<(Node, NodeCacheSlot)>,
     table: Vec<NodeCacheSlot>,
+    /// When the cache is full, its capacity is multiplied by this factor.
+    growth_factor: u64,
+    /// The number of times the cache was grown.
+    grow_count: usize,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
@@ -74,6 +79,10 @@ impl IntoIndex for NodeCacheSlot {
 }
 
 impl NodeCache {
+    /// The highest bit of a 48-bit link. It is ignored when hashing the low link of a node,
+    /// such that users of the cache can store a flag in it (see `complement::apply`). Node
+    /// indices never reach this bit in practice (it would need `2^47` nodes).
+    pub const LINK_FLAG: u64 = (Node::MAX_LINK + 1) >> 1;
 
     /// Create a new node cache with the given initial capacity. To make the resulting node indices
     /// compatible with our BDD conventions, the cache will be also pre-populated with two terminal
@@ -93,10 +102,27 @@ impl NodeCache {
                 result[0] = (Node::ZERO, NodeCacheSlot::UNDEFINED);
                 result[1] = (Node::ONE, NodeCacheSlot::UNDEFINED);
                 result
-            }
+            },
+            growth_factor: 2,
+            grow_count: 0,
         }
     }
 
+    /// Set the factor by which the capacity is multiplied when the cache grows.
+    pub fn set_growth_factor(&mut self, growth_factor: u64) {
+        self.growth_factor = max(growth_factor, 2);
+    }
+
+    /// The number of times the cache was grown.
+    pub fn grow_count(&self) -> usize {
+        self.grow_count
+    }
+
+    /// The amount of memory used by the cache (in bytes).
+    pub fn memory(&self) -> usize {
+        self.nodes.len() * size_of::<(Node, NodeCacheSlot)>() + self.table.len() * size_of::<NodeCacheSlot>()
+    }
+
     pub fn len(&self) -> usize {
         self.index_after_last.into_index()
     }
@@ -157,7 +183,7 @@ impl NodeCache {
     }
 
     fn hash_position(&self, key: &Node) -> usize {
-        let low_link = key.get_low_link().into_index();
+        let low_link = (u64::from(key.get_low_link()) & !Self::LINK_FLAG).into_index();
         let high_link = key.get_high_link().into_index();
         max(low_link, high_link)
     }
@@ -170,9 +196,11 @@ impl NodeCache {
             return free_slots;
         }
 
+        self.grow_count += 1;
         let first_new_slot = self.table.len();
-        self.nodes.reserve_exact(self.nodes.len());
-        self.table.reserve_exact(self.table.len());
+        let extra_slots = (self.growth_factor - 1).into_index() * self.nodes.len();
+        self.nodes.reserve_exact(extra_slots);
+        self.table.reserve_exact(extra_slots);
         // Reserve memory without initializing it.
         unsafe {
             self.nodes.set_len(self.nodes.capacity());
@@ -188,4 +216,39 @@ impl NodeCache {
         return u64::from_index(self.nodes.len()) - self.index_after_last;
     }
 
+    /// Remove all nodes (except for the two terminals) from the cache while keeping the allocated
+    /// memory. Since the hash of a node is always smaller than the index of the last inserted
+    /// node, only this prefix of the hash table needs to be erased.
+    pub fn clear(&mut self) {
+        let len = self.len();
+        for slot in self.table[..len].iter_mut() {
+            *slot = NodeCacheSlot::UNDEFINED;
+        }
+        self.index_after_last = 2;
+    }
+
+    /// The amount of memory (in bytes) the cache will use after a call
+    /// to `ensure_capacity(minimal_capacity)`.
+    pub fn memory_after_growth(&self, minimal_capacity: u64) -> usize {
+        let free_slots = u64::from_index(self.nodes.len()) - self.index_after_last;
+        if free_slots >= minimal_capacity {
+            self.memory()
+        } else {
+            self.memory() * self.growth_factor.into_index()
+        }
+    }
+
+    /// Create a `Bdd` from the nodes of this cache that are reachable from the given `root`.
+    pub fn export(&self, root: NodeIndex) -> Bdd {
+        Bdd::from_reachable(self.len(), root, |index| self.nodes[index.into_index()].0.clone())
+    }
+
+    /// Copy all nodes stored in this cache (including the two terminals) into a vector.
+    pub fn export_nodes(&self) -> Vec<Node> {
+        self.nodes[..self.len()]
+            .iter()
+            .map(|(node, _)| node.clone())
+            .collect()
+    }
+
 }
\ No newline at end of file
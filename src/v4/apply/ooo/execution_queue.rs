@@ -0,0 +1,210 @@
+use super::super::super::core::{Node, NodeIndex, Variable};
+use super::super::node_cache::NodeCacheSlot;
+use super::super::task_cache::TaskCacheSlot;
+use super::reorder_buffer::RobSlot;
+use super::task_stack::{StackedTask, ROB_SLOT};
+
+/// A task which was issued from the `TaskStack` and waits to be executed (i.e. its results
+/// are collected from the `ReorderBuffer` and its node is inserted into the node cache) and
+/// then retired (i.e. the node cache insertion is finished if it was not possible to do it
+/// in one step).
+#[derive(Copy, Clone)]
+pub struct PendingTask {
+    rob_slot: RobSlot,
+    variable: Variable,
+    task: (NodeIndex, NodeIndex),
+    result: (u64, u64),
+    task_cache_slot: TaskCacheSlot,
+    node_cache_slot: NodeCacheSlot,
+    /// The number of node cache collision chain links followed so far.
+    chain: usize,
+}
+
+impl PendingTask {
+    const EMPTY: PendingTask = PendingTask {
+        rob_slot: RobSlot::UNDEFINED,
+        variable: Variable::UNDEFINED,
+        task: (NodeIndex::UNDEFINED, NodeIndex::UNDEFINED),
+        result: (u64::MAX, u64::MAX),
+        task_cache_slot: TaskCacheSlot::UNDEFINED,
+        node_cache_slot: NodeCacheSlot::UNDEFINED,
+        chain: 0,
+    };
+
+    pub fn operands(&self) -> (NodeIndex, NodeIndex) {
+        self.task
+    }
+
+    /// The node of this task. Only valid once both results are known.
+    pub fn result_node(&self) -> Node {
+        debug_assert!(self.has_low_result() && self.has_high_result());
+        Node::pack(self.variable, self.get_low_result(), self.get_high_result())
+    }
+
+    pub fn has_low_result(&self) -> bool {
+        self.result.0 & ROB_SLOT == 0
+    }
+
+    pub fn has_high_result(&self) -> bool {
+        self.result.1 & ROB_SLOT == 0
+    }
+
+    pub fn get_rob(&self) -> RobSlot {
+        self.rob_slot
+    }
+
+    pub fn get_low_rob(&self) -> RobSlot {
+        RobSlot::from((self.result.0 ^ ROB_SLOT) as u32)
+    }
+
+    pub fn get_high_rob(&self) -> RobSlot {
+        RobSlot::from((self.result.1 ^ ROB_SLOT) as u32)
+    }
+
+    pub fn get_low_result(&self) -> NodeIndex {
+        NodeIndex::from(self.result.0)
+    }
+
+    pub fn get_high_result(&self) -> NodeIndex {
+        NodeIndex::from(self.result.1)
+    }
+
+    pub fn set_low_result(&mut self, node: NodeIndex) {
+        self.result.0 = node.into();
+    }
+
+    pub fn set_high_result(&mut self, node: NodeIndex) {
+        self.result.1 = node.into();
+    }
+
+    pub fn get_task_slot(&self) -> TaskCacheSlot {
+        self.task_cache_slot
+    }
+
+    pub fn get_node_slot(&self) -> NodeCacheSlot {
+        self.node_cache_slot
+    }
+
+    /// Save the node cache `slot` that should be tried next during retirement.
+    pub fn set_node_slot(&mut self, slot: NodeCacheSlot) {
+        self.node_cache_slot = slot;
+    }
+
+    /// Record one more followed link of the node cache collision chain.
+    pub fn follow_chain(&mut self) {
+        self.chain += 1;
+    }
+
+    pub fn chain_length(&self) -> usize {
+        self.chain
+    }
+
+    pub fn mark_as_retired(&mut self) {
+        self.rob_slot = RobSlot::UNDEFINED;
+    }
+
+    pub fn is_retired(&self) -> bool {
+        self.rob_slot == RobSlot::UNDEFINED
+    }
+}
+
+/// A cyclic buffer of `PendingTask` objects. The buffer is split into two consecutive queues:
+/// the *execution queue* (between `execution_head` and `execution_tail`) and the *retire queue*
+/// (between `retire_head` and `execution_head`). Tasks enter the execution queue, move into
+/// the retire queue once executed and leave the buffer once retired.
+///
+/// One slot of the buffer is always kept free to distinguish a full buffer from an empty one.
+pub struct ExecutionRetireQueue<const LEN: usize> {
+    queue: Vec<PendingTask>,
+    retire_head: usize,
+    execution_head: usize,
+    execution_tail: usize,
+}
+
+impl<const LEN: usize> ExecutionRetireQueue<LEN> {
+    pub fn new() -> ExecutionRetireQueue<LEN> {
+        ExecutionRetireQueue {
+            queue: vec![PendingTask::EMPTY; LEN],
+            retire_head: 0,
+            execution_head: 0,
+            execution_tail: 0,
+        }
+    }
+
+    /// The amount of memory used by the queue (in bytes).
+    pub fn memory(&self) -> usize {
+        self.queue.len() * std::mem::size_of::<PendingTask>()
+    }
+
+    /// True if no new tasks can be enqueued for execution.
+    pub fn is_full(&self) -> bool {
+        (self.execution_tail + 1) % LEN == self.retire_head
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.execution_tail == self.execution_head && self.execution_head == self.retire_head
+    }
+
+    /// True if the queue contains at least one task in the execution queue.
+    pub fn can_execute(&self) -> bool {
+        self.execution_head != self.execution_tail
+    }
+
+    /// True if the queue contains at least one task in the retire queue.
+    pub fn can_retire(&self) -> bool {
+        self.retire_head != self.execution_head
+    }
+
+    /// Add a new decoded `task` into the execution queue. Its result will be written
+    /// into the given `rob` slot.
+    ///
+    /// **Safety:** The method can be only called on a queue that is not full.
+    pub unsafe fn enqueue_for_execution(&mut self, rob: RobSlot, task: &StackedTask) {
+        debug_assert!(!self.is_full());
+        let slot = unsafe { self.queue.get_unchecked_mut(self.execution_tail) };
+        *slot = PendingTask {
+            rob_slot: rob,
+            variable: task.get_decision_variable(),
+            task: task.operands(),
+            result: task.get_raw_results(),
+            task_cache_slot: task.get_task_slot(),
+            node_cache_slot: NodeCacheSlot::UNDEFINED,
+            chain: 0,
+        };
+        self.execution_tail = (self.execution_tail + 1) % LEN;
+    }
+
+    /// Obtain the reference to the task that should be executed next.
+    ///
+    /// **Safety:** The execution queue must not be empty.
+    pub unsafe fn execute_task_reference(&mut self) -> &mut PendingTask {
+        debug_assert!(self.can_execute());
+        unsafe { self.queue.get_unchecked_mut(self.execution_head) }
+    }
+
+    /// Move the head of the execution queue into the retire queue.
+    ///
+    /// **Safety:** The execution queue must not be empty, and both results of its head
+    /// must be known.
+    pub unsafe fn move_to_retire(&mut self) {
+        debug_assert!(self.can_execute());
+        self.execution_head = (self.execution_head + 1) % LEN;
+    }
+
+    /// Obtain the reference to the task that should be retired next.
+    ///
+    /// **Safety:** The retire queue must not be empty.
+    pub unsafe fn retire_task_reference(&mut self) -> &mut PendingTask {
+        debug_assert!(self.can_retire());
+        unsafe { self.queue.get_unchecked_mut(self.retire_head) }
+    }
+
+    /// Remove the head of the retire queue.
+    ///
+    /// **Safety:** The retire queue must not be empty, and its head must be already committed
+    /// to the node cache, task cache and ROB.
+    pub unsafe fn retire(&mut self) {
+        debug_assert!(self.can_retire());
+        self.retire_head = (self.retire_head + 1) % LEN;
+    }
+}
@@ -0,0 +1,280 @@
+//! An out-of-order variant of the `apply` algorithm.
+//!
+//! The in-order `apply` finishes every task immediately once both of its sub-tasks are done.
+//! In particular, the node cache lookup of the task result has to wait for the results of
+//! the sub-tasks, which are typically created just before and may still be in flight (e.g.
+//! waiting for a cache miss). Here, the algorithm is instead organized like a CPU pipeline:
+//!
+//!  - *Decode:* The top of the `TaskStack` is resolved (terminal or task cache hit), or expanded
+//!    into two sub-tasks.
+//!  - *Issue:* Once both sub-tasks of a decoded task are popped, the task is moved into the
+//!    execution queue and receives a slot in the `ReorderBuffer` where its result will appear.
+//!  - *Execute:* Once the results of both sub-tasks are available in the ROB, the result node is
+//!    looked up in the node cache. If this does not succeed immediately (hash collision),
+//!    the lookup continues during retirement.
+//!  - *Retire:* The node cache lookup is finished and the result is written into the ROB.
+//!
+//! All three stages advance (at most) one task in every iteration, such that the memory accesses
+//! of independent tasks can overlap.
+
+use super::super::core::Bdd;
+use super::node_cache::NodeCache;
+use super::task_cache::TaskCache;
+use super::{check_limits, stack_capacity, ApplyConfig, ApplyError, ApplyStats, BooleanOperator, CacheCounters};
+use execution_queue::ExecutionRetireQueue;
+use reorder_buffer::{ReorderBuffer, RobSlot};
+use std::cmp::{max, min};
+use task_stack::{TaskStack, ROB_SLOT};
+
+mod execution_queue;
+mod reorder_buffer;
+mod task_stack;
+
+/// The number of slots in the execution-retire queue.
+const QUEUE_LENGTH: usize = 32;
+
+/// The out-of-order implementation of `apply` using the given (empty) caches.
+///
+/// The results are the same as for the in-order `apply`. However, a task that is in flight
+/// (decoded, but not retired) is not visible in the task cache, so the out-of-order version
+/// can expand slightly more tasks.
+pub(super) fn ooo_apply_in<Op: BooleanOperator>(
+    left_bdd: &Bdd,
+    right_bdd: &Bdd,
+    operator: Op,
+    config: &ApplyConfig,
+    task_cache: &mut TaskCache,
+    node_cache: &mut NodeCache,
+) -> Result<(Bdd, ApplyStats), ApplyError> {
+    task_cache.set_load_factor(config.task_cache_load_factor);
+    node_cache.set_growth_factor(config.node_cache_growth_factor);
+    let initial_counters = CacheCounters::read(task_cache, node_cache);
+    let mut stats = ApplyStats::default();
+
+    // The ROB is never blocked when it can hold one result for every decoded task on the stack
+    // (see `ReorderBuffer`), which is guaranteed by the stack capacity.
+    let mut stack = TaskStack::new(stack_capacity(left_bdd, right_bdd));
+    let mut rob = ReorderBuffer::new(stack_capacity(left_bdd, right_bdd));
+    let mut queue = ExecutionRetireQueue::<QUEUE_LENGTH>::new();
+    let pipeline_memory = stack.memory() + rob.memory() + queue.memory();
+
+    unsafe {
+        stack.push_new(0, (left_bdd.get_root_index(), right_bdd.get_root_index()));
+    }
+
+    'main: loop {
+        // Every iteration can create up to two nodes and write up to two task cache entries
+        // (one executed and one retired task), so the iteration counts are halved.
+        let base_iterations = max(left_bdd.get_size() >> config.check_interval_shift, config.min_check_interval);
+        check_limits(
+            config,
+            task_cache,
+            node_cache,
+            2 * base_iterations,
+            pipeline_memory,
+            &initial_counters,
+            &mut stats,
+        )?;
+        let node_iterations = node_cache.ensure_capacity(2 * base_iterations) / 2;
+        let task_iterations = max(task_cache.grow_if_necessary(), 2 * base_iterations) / 2;
+        let iterations = min(node_iterations, task_iterations);
+        for _ in 0..iterations {
+            if stack.is_empty() && queue.is_empty() {
+                break 'main;
+            }
+
+            unsafe {
+                if queue.can_retire() {
+                    let task = queue.retire_task_reference();
+                    if task.is_retired() {
+                        // The task was already finished during the execute step.
+                        queue.retire();
+                    } else {
+                        match node_cache.ensure_at(&task.result_node(), task.get_node_slot()) {
+                            Ok(result) => {
+                                rob.set_slot_value(task.get_rob(), result);
+                                task_cache.write(task.get_task_slot(), task.operands(), result);
+                                stats.node_cache_chain_steps += task.chain_length();
+                                stats.node_cache_max_chain = max(stats.node_cache_max_chain, task.chain_length());
+                                queue.retire();
+                            }
+                            Err(slot) => {
+                                task.set_node_slot(slot);
+                                task.follow_chain();
+                            }
+                        }
+                    }
+                }
+
+                if queue.can_execute() {
+                    let task = queue.execute_task_reference();
+                    if task.has_low_result() && task.has_high_result() {
+                        let (low_result, high_result) = (task.get_low_result(), task.get_high_result());
+                        if low_result == high_result {
+                            // No node is needed, the task can be finished immediately
+                            // (and will be skipped in the retire queue).
+                            rob.set_slot_value(task.get_rob(), low_result);
+                            task_cache.write(task.get_task_slot(), task.operands(), low_result);
+                            task.mark_as_retired();
+                        } else {
+                            match node_cache.ensure(&task.result_node()) {
+                                Ok(result) => {
+                                    rob.set_slot_value(task.get_rob(), result);
+                                    task_cache.write(task.get_task_slot(), task.operands(), result);
+                                    task.mark_as_retired();
+                                }
+                                Err(slot) => {
+                                    // Hash collision, continue in the retire step.
+                                    task.set_node_slot(slot);
+                                    task.follow_chain();
+                                }
+                            }
+                        }
+                        // Regardless of what happened, the task is moving into retirement.
+                        queue.move_to_retire();
+                    } else {
+                        // Try to collect the missing results from the ROB.
+                        if !task.has_low_result() {
+                            let slot = task.get_low_rob();
+                            let result = rob.get_slot_value(slot);
+                            if !result.is_undefined() {
+                                rob.free_slot(slot);
+                                task.set_low_result(result);
+                            }
+                        }
+                        if !task.has_high_result() {
+                            let slot = task.get_high_rob();
+                            let result = rob.get_slot_value(slot);
+                            if !result.is_undefined() {
+                                rob.free_slot(slot);
+                                task.set_high_result(result);
+                            }
+                        }
+                    }
+                }
+
+                if !stack.is_empty() {
+                    let task = stack.get_top_mut();
+                    if task.is_decoded() {
+                        // Both sub-tasks are popped, so the task can be issued (if there is
+                        // room in the pipeline, otherwise the front-end stalls).
+                        if !rob.is_full() && !queue.is_full() {
+                            let slot = rob.allocate_slot();
+                            queue.enqueue_for_execution(slot, task);
+                            stack.pop_with_rob_slot(slot);
+                        }
+                    } else {
+                        let (left, right) = task.operands();
+                        let result = operator.resolve(left, right);
+                        if !result.is_undefined() {
+                            stack.pop_with_node(result);
+                        } else {
+                            let (cached, slot) = task_cache.read((left, right));
+                            if !cached.is_undefined() {
+                                stats.task_cache_hits += 1;
+                                stack.pop_with_node(cached);
+                            } else {
+                                stats.tasks_expanded += 1;
+                                let (l_var, l_low, l_high) = left_bdd.get_node_unchecked(left).unpack();
+                                let (r_var, r_low, r_high) = right_bdd.get_node_unchecked(right).unpack();
+                                let variable = min(l_var, r_var);
+                                let (l_low, l_high) = if l_var == variable { (l_low, l_high) } else { (left, left) };
+                                let (r_low, r_high) = if r_var == variable { (r_low, r_high) } else { (right, right) };
+                                task.set_decoded(variable, slot);
+                                stack.push_new(1, (l_high, r_high));
+                                stack.push_new(2, (l_low, r_low));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // The root task saves its result into itself. If it was issued, the result is in the ROB.
+    let root_result = stack.root_result();
+    let root_result = if root_result & ROB_SLOT == 0 {
+        root_result.into()
+    } else {
+        unsafe { rob.get_slot_value(RobSlot::from((root_result ^ ROB_SLOT) as u32)) }
+    };
+    CacheCounters::read(task_cache, node_cache).update_stats(&initial_counters, &mut stats);
+    Ok((node_cache.export(root_result), stats))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::v4::apply::{
+        apply, apply_with_config, And, AndNot, ApplyConfig, ApplyContext, ApplyEngine, BooleanOperator, Iff, Imp,
+        Or, TruthTable, Xor,
+    };
+    use crate::v4::core::{Bdd, CancellationToken};
+    use crate::v4::test_util::nodes;
+    use crate::verify::{FunctionTable, Lcg};
+
+    fn ooo_config() -> ApplyConfig {
+        ApplyConfig {
+            engine: ApplyEngine::OutOfOrder,
+            ..ApplyConfig::default()
+        }
+    }
+
+    fn check_operator<Op: BooleanOperator>(left: &Bdd, right: &Bdd, operator: Op) {
+        let (expected, _) = apply(left, right, operator);
+        let (result, stats) = apply_with_config(left, right, operator, &ooo_config()).unwrap();
+        assert_eq!(nodes(&expected), nodes(&result));
+        assert!(expected.get_size() <= stats.tasks_expanded as u64 + 2);
+    }
+
+    #[test]
+    pub fn ooo_apply_matches_apply() {
+        let mut rng = Lcg::new(43);
+        let densities = [(50, 50), (5, 95), (30, 60), (0, 50), (100, 100), (90, 10)];
+        for vars in &[0u32, 1, 4, 10, 14] {
+            for (l_density, r_density) in &densities {
+                let left = FunctionTable::random(*vars, *l_density, &mut rng).to_bdd();
+                let right = FunctionTable::random(*vars, *r_density, &mut rng).to_bdd();
+                check_operator(&left, &right, And);
+                check_operator(&left, &right, Or);
+                check_operator(&left, &right, Xor);
+                check_operator(&left, &right, Iff);
+                check_operator(&left, &right, Imp);
+                check_operator(&left, &right, AndNot);
+                for table in 0..16 {
+                    check_operator(&left, &right, TruthTable::new(table));
+                }
+            }
+        }
+
+        // Contexts can also select the engine.
+        let left = FunctionTable::random(12, 50, &mut rng).to_bdd();
+        let right = FunctionTable::random(12, 50, &mut rng).to_bdd();
+        let mut context = ApplyContext::with_config(ooo_config());
+        for _ in 0..2 {
+            let (result, _) = context.apply(&left, &right, Xor);
+            assert_eq!(nodes(&apply(&left, &right, Xor).0), nodes(&result));
+        }
+
+        // Tiny caches have to grow while tasks are in flight.
+        let tiny = ApplyConfig {
+            task_cache_capacity: Some(1),
+            node_cache_capacity: Some(2),
+            task_cache_load_factor: 1,
+            min_check_interval: 1,
+            check_interval_shift: 10,
+            ..ooo_config()
+        };
+        let (result, stats) = apply_with_config(&left, &right, Or, &tiny).unwrap();
+        assert_eq!(nodes(&apply(&left, &right, Or).0), nodes(&result));
+        assert!(stats.node_cache_grow_events > 0);
+
+        // Limits are enforced the same way as in the in-order engine.
+        let token = CancellationToken::new();
+        token.cancel();
+        let config = ApplyConfig {
+            cancellation: Some(token),
+            ..ooo_config()
+        };
+        assert!(apply_with_config(&left, &right, And, &config).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use super::super::super::core::NodeIndex;
+
+/// `ReorderBuffer` (ROB) keeps track of outstanding dependencies of "in flight" tasks.
+///
+/// Each task that exits the `TaskStack` has a slot in the ROB that will be used to store the
+/// output of said task. That is, once a task X is finished, its result is written into the ROB.
+/// From there, it is later picked up by its parent task Y once Y starts executing. At that
+/// point the ROB slot is freed and can be used for another task that is exiting the stack.
+///
+/// Initially, the slots form a linked list of cells ready for allocation (accessed via the
+/// `next_free` property). Once a slot is allocated, it is detached from this list and its value
+/// is cleared, only to be eventually set to the result of the corresponding pending task.
+/// Finally, once the slot is freed, it is again prepended to the list of free slots. Hence, the
+/// slots are allocated in a mostly stack-like fashion and the unused part of the buffer does
+/// not pollute the CPU caches.
+///
+/// Only decoded tasks on the stack (at most one per level of the task tree) can hold a finished
+/// result in the ROB while waiting for their other child. Together with the slots of the top
+/// task and of the root, this means a ROB of `stack_capacity` slots can never block the pipeline
+/// indefinitely.
+///
+/// Slots are indexed by `u32`, since the ROB is proportional to the height of the BDDs.
+pub struct ReorderBuffer {
+    /// If the slot is free, it points to another free slot (or `u64::MAX` if it is last).
+    /// If it is allocated, it is either `NodeIndex::UNDEFINED`, or the task result.
+    buffer: Vec<u64>,
+    /// A pointer to the first free slot in the ROB.
+    next_free: RobSlot,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct RobSlot(u32);
+
+impl RobSlot {
+    pub const UNDEFINED: RobSlot = RobSlot(u32::MAX);
+}
+
+impl From<u32> for RobSlot {
+    fn from(value: u32) -> Self {
+        RobSlot(value)
+    }
+}
+
+impl From<RobSlot> for u32 {
+    fn from(value: RobSlot) -> Self {
+        value.0
+    }
+}
+
+impl ReorderBuffer {
+    pub fn new(capacity: usize) -> ReorderBuffer {
+        assert!(capacity > 0 && capacity < u32::MAX as usize);
+        // Create a linked list starting in zero and going through all slots in the vector.
+        // The last element has no successor, so it points to `RobSlot::UNDEFINED`.
+        let mut buffer: Vec<u64> = (1..=capacity as u64).collect();
+        buffer[capacity - 1] = u64::from(u32::from(RobSlot::UNDEFINED));
+        ReorderBuffer {
+            buffer,
+            next_free: RobSlot(0),
+        }
+    }
+
+    /// The amount of memory used by the buffer (in bytes).
+    pub fn memory(&self) -> usize {
+        self.buffer.len() * std::mem::size_of::<u64>()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.next_free == RobSlot::UNDEFINED
+    }
+
+    /// Returns the next free ROB slot and initializes said slot with an undefined value.
+    ///
+    /// **Safety:** The function can be called only when the reorder buffer is not full.
+    pub unsafe fn allocate_slot(&mut self) -> RobSlot {
+        debug_assert!(!self.is_full());
+        let slot = self.next_free;
+        let slot_value = unsafe { self.buffer.get_unchecked_mut(slot.0 as usize) };
+        // Free slots are a linked list, hence the value is the next free slot.
+        self.next_free = RobSlot(*slot_value as u32);
+        // Erase the linked list pointer, meaning that this slot contains an unfinished task.
+        *slot_value = u64::from(NodeIndex::UNDEFINED);
+        slot
+    }
+
+    /// Free the given `slot`.
+    ///
+    /// **Safety:** The given `slot` must be allocated in this ROB.
+    pub unsafe fn free_slot(&mut self, slot: RobSlot) {
+        debug_assert!((slot.0 as usize) < self.buffer.len());
+        let slot_value = unsafe { self.buffer.get_unchecked_mut(slot.0 as usize) };
+        *slot_value = u64::from(self.next_free.0);
+        self.next_free = slot;
+    }
+
+    /// Retrieve the `NodeIndex` that is stored in the given ROB `slot`. The value is
+    /// `NodeIndex::UNDEFINED` if the result is not computed yet.
+    ///
+    /// **Safety:** The `slot` must be allocated in this ROB.
+    pub unsafe fn get_slot_value(&self, slot: RobSlot) -> NodeIndex {
+        debug_assert!((slot.0 as usize) < self.buffer.len());
+        NodeIndex::from(unsafe { *self.buffer.get_unchecked(slot.0 as usize) })
+    }
+
+    /// Update the value of the given ROB `slot`.
+    ///
+    /// **Safety:** The `slot` must be allocated in this ROB.
+    pub unsafe fn set_slot_value(&mut self, slot: RobSlot, value: NodeIndex) {
+        debug_assert!((slot.0 as usize) < self.buffer.len());
+        let slot_value = unsafe { self.buffer.get_unchecked_mut(slot.0 as usize) };
+        *slot_value = u64::from(value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ReorderBuffer;
+    use crate::v4::core::NodeIndex;
+
+    #[test]
+    pub fn basic_rob_test() {
+        unsafe {
+            let mut rob = ReorderBuffer::new(3);
+            assert!(!rob.is_full());
+            let slot_1 = rob.allocate_slot();
+            let slot_2 = rob.allocate_slot();
+            assert!(!rob.is_full());
+            assert!(rob.get_slot_value(slot_1).is_undefined());
+            rob.set_slot_value(slot_1, NodeIndex::from(3));
+            rob.set_slot_value(slot_2, NodeIndex::from(5));
+            assert_eq!(NodeIndex::from(3), rob.get_slot_value(slot_1));
+            assert_eq!(NodeIndex::from(5), rob.get_slot_value(slot_2));
+            let slot_3 = rob.allocate_slot();
+            assert_ne!(slot_1, slot_3);
+            assert_ne!(slot_2, slot_3);
+            assert!(rob.is_full());
+            rob.free_slot(slot_2);
+            assert!(!rob.is_full());
+            assert_eq!(slot_2, rob.allocate_slot());
+        }
+    }
+}
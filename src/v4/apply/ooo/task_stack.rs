@@ -0,0 +1,144 @@
+use super::super::super::core::{NodeIndex, Variable};
+use super::super::task_cache::TaskCacheSlot;
+use super::reorder_buffer::RobSlot;
+
+const NOT_DECODED: u32 = 1 << 31;
+
+/// Marks a task result which is not a `NodeIndex`, but a `RobSlot` where the result
+/// will eventually appear.
+pub const ROB_SLOT: u64 = 1 << 63;
+
+/// A task waiting on the `TaskStack`, either to be decoded into sub-tasks, or (once decoded and
+/// both sub-tasks are popped) to be issued into the execution queue.
+#[derive(Copy, Clone)]
+pub struct StackedTask {
+    /// How many slots "below" this one is the spawning task, plus the `NOT_DECODED` flag.
+    offset: u32,
+    /// The decision variable of this task (valid once decoded).
+    variable: Variable,
+    /// The left/right BDD node indices.
+    task: (NodeIndex, NodeIndex),
+    /// The (low, high) results. Each is either a valid `NodeIndex`, or a `RobSlot` marked
+    /// with the `ROB_SLOT` bit where the result will be obtained.
+    results: (u64, u64),
+    /// The task cache slot where the result should be saved (valid once decoded).
+    task_cache_slot: TaskCacheSlot,
+}
+
+impl StackedTask {
+    const EMPTY: StackedTask = StackedTask {
+        offset: NOT_DECODED,
+        variable: Variable::UNDEFINED,
+        task: (NodeIndex::UNDEFINED, NodeIndex::UNDEFINED),
+        results: (u64::MAX, u64::MAX),
+        task_cache_slot: TaskCacheSlot::UNDEFINED,
+    };
+
+    pub fn is_decoded(&self) -> bool {
+        self.offset & NOT_DECODED == 0
+    }
+
+    /// Mark the task as decoded with the given decision `variable` and task cache `slot`.
+    pub fn set_decoded(&mut self, variable: Variable, slot: TaskCacheSlot) {
+        self.offset &= !NOT_DECODED;
+        self.variable = variable;
+        self.task_cache_slot = slot;
+    }
+
+    pub fn operands(&self) -> (NodeIndex, NodeIndex) {
+        self.task
+    }
+
+    pub fn get_task_slot(&self) -> TaskCacheSlot {
+        self.task_cache_slot
+    }
+
+    pub fn get_decision_variable(&self) -> Variable {
+        self.variable
+    }
+
+    pub fn get_raw_results(&self) -> (u64, u64) {
+        self.results
+    }
+}
+
+/// A stack of tasks which were not issued into the execution queue yet.
+///
+/// Similar to the stack of the in-order `apply`, but instead of storing the result of a popped
+/// task directly in its parent, the result can also be a reference to a `ReorderBuffer` slot.
+pub struct TaskStack {
+    index_after_last: usize,
+    items: Vec<StackedTask>,
+}
+
+impl TaskStack {
+    pub fn new(capacity: usize) -> TaskStack {
+        TaskStack {
+            index_after_last: 0,
+            items: vec![StackedTask::EMPTY; capacity],
+        }
+    }
+
+    /// The amount of memory used by the stack (in bytes).
+    pub fn memory(&self) -> usize {
+        self.items.len() * std::mem::size_of::<StackedTask>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index_after_last == 0
+    }
+
+    /// Push a new (not decoded) task. The `offset` is the distance to the parent task:
+    /// `1` for the high sub-task, `2` for the low sub-task and `0` for the root task.
+    ///
+    /// **Safety:** The stack must not be full.
+    pub unsafe fn push_new(&mut self, offset: u32, task: (NodeIndex, NodeIndex)) {
+        debug_assert!(self.index_after_last < self.items.len());
+        let slot = unsafe { self.items.get_unchecked_mut(self.index_after_last) };
+        self.index_after_last += 1;
+        slot.task = task;
+        slot.offset = offset | NOT_DECODED;
+        slot.results = (u64::MAX, u64::MAX);
+    }
+
+    /// **Safety:** The stack must not be empty.
+    pub unsafe fn get_top_mut(&mut self) -> &mut StackedTask {
+        debug_assert!(!self.is_empty());
+        unsafe { self.items.get_unchecked_mut(self.index_after_last - 1) }
+    }
+
+    /// The result of the root task, once it was popped from the stack. This is either
+    /// a `NodeIndex`, or a ROB slot marked with the `ROB_SLOT` bit.
+    pub fn root_result(&self) -> u64 {
+        self.items[0].results.0
+    }
+
+    /// Pop the top task and save its `result` into its parent.
+    ///
+    /// **Safety:** The stack must not be empty.
+    pub unsafe fn pop_with_node(&mut self, result: NodeIndex) {
+        unsafe { self.pop_with_result(u64::from(result)) }
+    }
+
+    /// Pop the top task and save the ROB `slot` of its future result into its parent.
+    ///
+    /// **Safety:** The stack must not be empty.
+    pub unsafe fn pop_with_rob_slot(&mut self, slot: RobSlot) {
+        unsafe { self.pop_with_result(u64::from(u32::from(slot)) | ROB_SLOT) }
+    }
+
+    unsafe fn pop_with_result(&mut self, result: u64) {
+        debug_assert!(!self.is_empty());
+        self.index_after_last -= 1;
+        let top = unsafe { self.items.get_unchecked(self.index_after_last) };
+        let offset = (top.offset & !NOT_DECODED) as usize; // Can be only 0/1/2.
+        let output = unsafe { self.items.get_unchecked_mut(self.index_after_last - offset) };
+        // Offset one is the high sub-task, offset two is the low sub-task. The root task
+        // (offset zero) saves its result as its own low result.
+        if offset == 1 {
+            output.results.1 = result;
+        } else {
+            output.results.0 = result;
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use super::super::core::NodeIndex;
+
+/// A binary Boolean operator that can be evaluated by the `apply` algorithm.
+///
+/// The operator is given the node indices of the two operands and returns the index of the
+/// result if it can be determined without expanding the task any further. Otherwise, it returns
+/// `NodeIndex::UNDEFINED`. The result can only be a terminal node (since only terminal nodes have
+/// the same index in all BDDs), and it must be always defined when both operands are terminals.
+///
+/// The operators are zero-sized types, such that the `apply` algorithm can be specialized
+/// for each operator by the compiler. For operators which are not known at compile time,
+/// you can use the `TruthTable` operator.
+pub trait BooleanOperator: Copy + Send + Sync {
+    fn resolve(&self, left: NodeIndex, right: NodeIndex) -> NodeIndex;
+}
+
+/// Logical conjunction (`left & right`).
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct And;
+
+/// Logical disjunction (`left | right`).
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Or;
+
+/// Exclusive disjunction (`left ^ right`).
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Xor;
+
+/// Logical equivalence (`left <=> right`).
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Iff;
+
+/// Logical implication (`left => right`).
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Imp;
+
+/// Conjunction with a negated right operand (`left & !right`).
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct AndNot;
+
+/// A general binary operator given by its truth table.
+///
+/// The bit at position `2 * left + right` is the value of the operator for the given
+/// pair of operands. For example, conjunction is `0b1000` and disjunction is `0b1110`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct TruthTable(u8);
+
+#[inline]
+fn is_constant(index: NodeIndex) -> bool {
+    index.is_zero() || index.is_one()
+}
+
+#[inline]
+fn from_bool(value: bool) -> NodeIndex {
+    if value {
+        NodeIndex::ONE
+    } else {
+        NodeIndex::ZERO
+    }
+}
+
+impl BooleanOperator for And {
+    #[inline]
+    fn resolve(&self, left: NodeIndex, right: NodeIndex) -> NodeIndex {
+        if left.is_zero() || right.is_zero() {
+            NodeIndex::ZERO
+        } else if left.is_one() && right.is_one() {
+            NodeIndex::ONE
+        } else {
+            NodeIndex::UNDEFINED
+        }
+    }
+}
+
+impl BooleanOperator for Or {
+    #[inline]
+    fn resolve(&self, left: NodeIndex, right: NodeIndex) -> NodeIndex {
+        if left.is_one() || right.is_one() {
+            NodeIndex::ONE
+        } else if left.is_zero() && right.is_zero() {
+            NodeIndex::ZERO
+        } else {
+            NodeIndex::UNDEFINED
+        }
+    }
+}
+
+impl BooleanOperator for Xor {
+    #[inline]
+    fn resolve(&self, left: NodeIndex, right: NodeIndex) -> NodeIndex {
+        if is_constant(left) && is_constant(right) {
+            from_bool(left != right)
+        } else {
+            NodeIndex::UNDEFINED
+        }
+    }
+}
+
+impl BooleanOperator for Iff {
+    #[inline]
+    fn resolve(&self, left: NodeIndex, right: NodeIndex) -> NodeIndex {
+        if is_constant(left) && is_constant(right) {
+            from_bool(left == right)
+        } else {
+            NodeIndex::UNDEFINED
+        }
+    }
+}
+
+impl BooleanOperator for Imp {
+    #[inline]
+    fn resolve(&self, left: NodeIndex, right: NodeIndex) -> NodeIndex {
+        if left.is_zero() || right.is_one() {
+            NodeIndex::ONE
+        } else if left.is_one() && right.is_zero() {
+            NodeIndex::ZERO
+        } else {
+            NodeIndex::UNDEFINED
+        }
+    }
+}
+
+impl BooleanOperator for AndNot {
+    #[inline]
+    fn resolve(&self, left: NodeIndex, right: NodeIndex) -> NodeIndex {
+        if left.is_zero() || right.is_one() {
+            NodeIndex::ZERO
+        } else if left.is_one() && right.is_zero() {
+            NodeIndex::ONE
+        } else {
+            NodeIndex::UNDEFINED
+        }
+    }
+}
+
+impl TruthTable {
+    /// Create a truth table operator from its 4-bit representation.
+    pub fn new(table: u8) -> TruthTable {
+        assert!(table < 16, "Truth table has only four bits.");
+        TruthTable(table)
+    }
+
+    /// Create a truth table operator by evaluating the given function on all inputs.
+    pub fn from_fn<F: Fn(bool, bool) -> bool>(function: F) -> TruthTable {
+        let mut table = 0;
+        for (i, (left, right)) in [(false, false), (false, true), (true, false), (true, true)]
+            .iter()
+            .enumerate()
+        {
+            if function(*left, *right) {
+                table |= 1 << i;
+            }
+        }
+        TruthTable(table)
+    }
+
+    /// Evaluate the operator for the given pair of Boolean values.
+    #[inline]
+    pub fn eval(&self, left: bool, right: bool) -> bool {
+        let position = 2 * u8::from(left) + u8::from(right);
+        (self.0 >> position) & 1 == 1
+    }
+}
+
+impl BooleanOperator for TruthTable {
+    #[inline]
+    fn resolve(&self, left: NodeIndex, right: NodeIndex) -> NodeIndex {
+        match (is_constant(left), is_constant(right)) {
+            (true, true) => from_bool(self.eval(left.is_one(), right.is_one())),
+            (true, false) => {
+                let left = left.is_one();
+                if self.eval(left, false) == self.eval(left, true) {
+                    from_bool(self.eval(left, false))
+                } else {
+                    NodeIndex::UNDEFINED
+                }
+            }
+            (false, true) => {
+                let right = right.is_one();
+                if self.eval(false, right) == self.eval(true, right) {
+                    from_bool(self.eval(false, right))
+                } else {
+                    NodeIndex::UNDEFINED
+                }
+            }
+            (false, false) => NodeIndex::UNDEFINED,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{And, AndNot, BooleanOperator, Iff, Imp, Or, TruthTable, Xor};
+    use crate::v4::core::NodeIndex;
+
+    fn check_operator<Op: BooleanOperator>(operator: Op, function: fn(bool, bool) -> bool) {
+        let table = TruthTable::from_fn(function);
+        let decision = NodeIndex::from(5);
+        let indices = [NodeIndex::ZERO, NodeIndex::ONE, decision];
+        for left in &indices {
+            for right in &indices {
+                let expected = table.resolve(*left, *right);
+                let actual = operator.resolve(*left, *right);
+                assert_eq!(expected, actual, "{:?} {:?}", left, right);
+            }
+        }
+    }
+
+    #[test]
+    pub fn operators_match_truth_tables() {
+        check_operator(And, |l, r| l && r);
+        check_operator(Or, |l, r| l || r);
+        check_operator(Xor, |l, r| l ^ r);
+        check_operator(Iff, |l, r| l == r);
+        check_operator(Imp, |l, r| !l || r);
+        check_operator(AndNot, |l, r| l && !r);
+        assert_eq!(TruthTable::new(0b1000), TruthTable::from_fn(|l, r| l && r));
+        assert_eq!(TruthTable::new(0b1110), TruthTable::from_fn(|l, r| l || r));
+    }
+}
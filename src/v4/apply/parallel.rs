@@ -0,0 +1,430 @@
+use super::super::core::{Bdd, Node, NodeIndex, Variable};
+use super::sharded_node_cache::ShardedNodeCache;
+use super::shared_task_cache::SharedTaskCache;
+use super::unsafe_stack::UnsafeStack;
+use super::super::core::{CancellationToken, Cancelled};
+use super::{apply_with_config, ApplyConfig, ApplyError, ApplyTask, BooleanOperator};
+use crate::IntoIndex;
+use fxhash::FxHashMap;
+use std::cmp::min;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// The number of independent tasks per thread that we try to create before starting the threads.
+/// Having more tasks than threads gives the work-stealing enough room to balance the load.
+const TASKS_PER_THREAD: usize = 64;
+
+/// The number of node cache shards per thread.
+const SHARDS_PER_THREAD: usize = 16;
+
+/// The number of iterations of `compute_task` after which the cancellation token is polled.
+const CANCELLATION_CHECK_INTERVAL: u64 = 1 << 14;
+
+/// Compute the result of the given Boolean `operator` applied to the two BDDs using
+/// multiple threads.
+///
+/// The algorithm first expands the top of the task graph sequentially (in BFS order) until there
+/// are enough independent sub-tasks for all threads. Each thread then has its own queue of
+/// sub-tasks, which it processes using the same DFS as the sequential `apply`. Once a thread runs
+/// out of work, it starts stealing sub-tasks from the other queues. All threads share one leaky
+/// task cache and one (sharded) node cache, such that no task is computed twice if it can be
+/// avoided and the result is a single reduced `Bdd`. Finally, the expanded top of the task
+/// graph is resolved sequentially using the results of the sub-tasks.
+///
+/// The result is always the same as the result of the sequential `apply`. With `threads <= 1`,
+/// the sequential `apply` is called directly, which is the recommended setting for small BDDs,
+/// where the cost of the shared data structures outweighs the benefits of parallelism.
+///
+/// Returns the result `Bdd` together with the number of expanded tasks. Note that some tasks
+/// may be computed by multiple threads concurrently (and thus counted more than once).
+pub fn parallel_apply<Op: BooleanOperator>(
+    left_bdd: &Bdd,
+    right_bdd: &Bdd,
+    operator: Op,
+    threads: usize,
+) -> (Bdd, usize) {
+    match parallel_apply_in(left_bdd, right_bdd, operator, threads, None) {
+        Ok(result) => result,
+        Err(Cancelled) => unreachable!("Parallel apply without a token cannot be cancelled."),
+    }
+}
+
+/// The same as `parallel_apply`, but stops with an error once the `cancellation` token is
+/// cancelled. Every thread polls the token before each of its sub-tasks and periodically
+/// while computing it.
+pub fn parallel_apply_cancellable<Op: BooleanOperator>(
+    left_bdd: &Bdd,
+    right_bdd: &Bdd,
+    operator: Op,
+    threads: usize,
+    cancellation: &CancellationToken,
+) -> Result<(Bdd, usize), Cancelled> {
+    parallel_apply_in(left_bdd, right_bdd, operator, threads, Some(cancellation))
+}
+
+fn parallel_apply_in<Op: BooleanOperator>(
+    left_bdd: &Bdd,
+    right_bdd: &Bdd,
+    operator: Op,
+    threads: usize,
+    cancellation: Option<&CancellationToken>,
+) -> Result<(Bdd, usize), Cancelled> {
+    if threads <= 1 {
+        let config = ApplyConfig {
+            cancellation: cancellation.cloned(),
+            ..ApplyConfig::default()
+        };
+        return match apply_with_config(left_bdd, right_bdd, operator, &config) {
+            Ok((result, stats)) => Ok((result, stats.tasks_expanded)),
+            Err(ApplyError::Cancelled { .. }) => Err(Cancelled),
+            Err(error) => unreachable!("Unlimited apply failed: {:?}", error),
+        };
+    }
+
+    let task_cache = SharedTaskCache::new(left_bdd.get_size() + right_bdd.get_size());
+    let node_cache = ShardedNodeCache::new(
+        min(255, SHARDS_PER_THREAD * threads),
+        left_bdd.get_size().into_index(),
+    );
+
+    let mut split = TaskSplit::new(left_bdd, right_bdd, operator, threads * TASKS_PER_THREAD);
+
+    // Distribute the unexpanded sub-tasks into the queues of individual threads.
+    let queues: Vec<Mutex<VecDeque<usize>>> =
+        (0..threads).map(|_| Mutex::new(VecDeque::new())).collect();
+    for (i, task_id) in split.unexpanded_tasks().enumerate() {
+        queues[i % threads].lock().unwrap().push_back(task_id);
+    }
+
+    let results: Vec<AtomicU64> = split
+        .results
+        .iter()
+        .map(|it| AtomicU64::new(u64::from(*it)))
+        .collect();
+    let task_count = AtomicUsize::new(split.expanded_count);
+    let is_cancelled = AtomicBool::new(false);
+
+    let stack_capacity = 2 * (left_bdd.get_height() + right_bdd.get_height()).into_index() + 1;
+    std::thread::scope(|scope| {
+        for thread_id in 0..threads {
+            let (queues, results, task_count) = (&queues, &results, &task_count);
+            let (task_cache, node_cache, split) = (&task_cache, &node_cache, &split);
+            let is_cancelled = &is_cancelled;
+            scope.spawn(move || {
+                let mut stack = UnsafeStack::new(stack_capacity);
+                let mut thread_task_count = 0;
+                while let Some(task_id) = next_task(queues, thread_id) {
+                    let computed = compute_task(
+                        left_bdd,
+                        right_bdd,
+                        operator,
+                        split.operands[task_id],
+                        &mut stack,
+                        task_cache,
+                        node_cache,
+                        cancellation,
+                    );
+                    if let Ok((result, count)) = computed {
+                        results[task_id].store(u64::from(result), Ordering::Relaxed);
+                        thread_task_count += count;
+                    } else {
+                        is_cancelled.store(true, Ordering::Relaxed);
+                        break;
+                    }
+                }
+                task_count.fetch_add(thread_task_count, Ordering::Relaxed);
+            });
+        }
+    });
+
+    if is_cancelled.into_inner() {
+        return Err(Cancelled);
+    }
+    for (task_id, result) in results.into_iter().enumerate() {
+        split.results[task_id] = NodeIndex::from(result.into_inner());
+    }
+    let root = split.resolve(&node_cache);
+    Ok((node_cache.export(root), task_count.into_inner()))
+}
+
+/// Pop a task from the queue of the given thread, or steal one from another thread.
+fn next_task(queues: &[Mutex<VecDeque<usize>>], thread_id: usize) -> Option<usize> {
+    if let Some(task) = queues[thread_id].lock().unwrap().pop_back() {
+        return Some(task);
+    }
+    // Steal from the opposite end of the queue than the one used by the owner.
+    for i in 1..queues.len() {
+        let victim = (thread_id + i) % queues.len();
+        if let Some(task) = queues[victim].lock().unwrap().pop_front() {
+            return Some(task);
+        }
+    }
+    None
+}
+
+/// The top part of the task graph which is explored sequentially before the parallel
+/// computation starts.
+///
+/// Tasks are identified by their position in the `operands` vector. An expanded task has
+/// a defined decision `variable` and two child tasks (low and high). A task that is neither
+/// expanded nor has a known result has to be computed by one of the threads.
+struct TaskSplit {
+    operands: Vec<(NodeIndex, NodeIndex)>,
+    variables: Vec<Variable>,
+    children: Vec<(usize, usize)>,
+    results: Vec<NodeIndex>,
+    expanded_count: usize,
+}
+
+impl TaskSplit {
+    /// Expand the task graph of the two BDDs in BFS order until there are at least
+    /// `task_target` unexpanded tasks (or the whole graph is explored).
+    fn new<Op: BooleanOperator>(
+        left_bdd: &Bdd,
+        right_bdd: &Bdd,
+        operator: Op,
+        task_target: usize,
+    ) -> TaskSplit {
+        let root = (left_bdd.get_root_index(), right_bdd.get_root_index());
+        let mut split = TaskSplit {
+            operands: vec![root],
+            variables: vec![Variable::UNDEFINED],
+            children: vec![(0, 0)],
+            results: vec![NodeIndex::UNDEFINED],
+            expanded_count: 0,
+        };
+        let mut task_ids: FxHashMap<(NodeIndex, NodeIndex), usize> = FxHashMap::default();
+        task_ids.insert(root, 0);
+
+        let mut queue = VecDeque::new();
+        queue.push_back(0);
+        while queue.len() < task_target {
+            let task_id = if let Some(task_id) = queue.pop_front() {
+                task_id
+            } else {
+                break;
+            };
+            let (left, right) = split.operands[task_id];
+            let result = operator.resolve(left, right);
+            if !result.is_undefined() {
+                split.results[task_id] = result;
+                continue;
+            }
+
+            split.expanded_count += 1;
+            let (l_var, l_low, l_high) = left_bdd.get_node(left).unpack();
+            let (r_var, r_low, r_high) = right_bdd.get_node(right).unpack();
+            let (variable, low, high) = if l_var == r_var {
+                (l_var, (l_low, r_low), (l_high, r_high))
+            } else if l_var < r_var {
+                (l_var, (l_low, right), (l_high, right))
+            } else {
+                (r_var, (left, r_low), (left, r_high))
+            };
+
+            let mut child_id = |operands: (NodeIndex, NodeIndex)| -> usize {
+                *task_ids.entry(operands).or_insert_with(|| {
+                    split.operands.push(operands);
+                    split.variables.push(Variable::UNDEFINED);
+                    split.children.push((0, 0));
+                    split.results.push(NodeIndex::UNDEFINED);
+                    queue.push_back(split.operands.len() - 1);
+                    split.operands.len() - 1
+                })
+            };
+            let children = (child_id(low), child_id(high));
+            split.variables[task_id] = variable;
+            split.children[task_id] = children;
+        }
+        split
+    }
+
+    /// Iterate over tasks that were neither expanded nor resolved.
+    fn unexpanded_tasks(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.operands.len())
+            .filter(move |i| self.variables[*i].is_undefined() && self.results[*i].is_undefined())
+    }
+
+    /// Compute the results of all expanded tasks, assuming the results of all unexpanded
+    /// tasks are known. Returns the result of the root task.
+    fn resolve(&mut self, node_cache: &ShardedNodeCache) -> NodeIndex {
+        // Children can have smaller ids than their parents, so we need a proper DFS post-order.
+        let mut stack: Vec<(usize, bool)> = vec![(0, false)];
+        while let Some((task_id, expanded)) = stack.pop() {
+            if !self.results[task_id].is_undefined() {
+                continue;
+            }
+            let (low_id, high_id) = self.children[task_id];
+            if expanded {
+                let (low, high) = (self.results[low_id], self.results[high_id]);
+                self.results[task_id] = if low == high {
+                    low
+                } else {
+                    node_cache.ensure(&Node::pack(self.variables[task_id], low, high))
+                };
+            } else {
+                stack.push((task_id, true));
+                stack.push((high_id, false));
+                stack.push((low_id, false));
+            }
+        }
+        self.results[0]
+    }
+}
+
+/// Compute the result of one task using the shared caches. This is the same algorithm
+/// as the sequential `apply`, just without the need to grow the caches.
+///
+/// Returns the result node together with the number of expanded tasks, or an error if the
+/// `cancellation` token is cancelled. In that case, the `stack` is left in an undefined state.
+#[allow(clippy::too_many_arguments)]
+fn compute_task<Op: BooleanOperator>(
+    left_bdd: &Bdd,
+    right_bdd: &Bdd,
+    operator: Op,
+    task: (NodeIndex, NodeIndex),
+    stack: &mut UnsafeStack<ApplyTask>,
+    task_cache: &SharedTaskCache,
+    node_cache: &ShardedNodeCache,
+    cancellation: Option<&CancellationToken>,
+) -> Result<(NodeIndex, usize), Cancelled> {
+    let mut task_count = 0;
+    let mut iterations: u64 = 0;
+    stack.push(ApplyTask::new(0, task));
+    loop {
+        if iterations.is_multiple_of(CANCELLATION_CHECK_INTERVAL) {
+            if let Some(cancellation) = cancellation {
+                cancellation.check()?;
+            }
+        }
+        iterations += 1;
+
+        let top = stack.peek();
+        let top_offset: usize = top.get_offset().into();
+
+        let mut result;
+        if top.is_not_decoded() {
+            top.mark_as_decoded();
+
+            let (left, right) = top.task;
+            result = operator.resolve(left, right);
+            if result.is_undefined() {
+                let cached = task_cache.read(top.task);
+                if !cached.is_undefined() {
+                    result = cached;
+                } else {
+                    task_count += 1;
+
+                    let left_node = unsafe { left_bdd.get_node_unchecked(left) };
+                    let right_node = unsafe { right_bdd.get_node_unchecked(right) };
+
+                    let (l_var, l_low, l_high) = left_node.unpack();
+                    let (r_var, r_low, r_high) = right_node.unpack();
+
+                    if l_var == r_var {
+                        top.variable = l_var;
+                        stack.push(ApplyTask::new(1, (l_high, r_high)));
+                        stack.push(ApplyTask::new(2, (l_low, r_low)));
+                    } else if l_var < r_var {
+                        top.variable = l_var;
+                        stack.push(ApplyTask::new(1, (l_high, right)));
+                        stack.push(ApplyTask::new(2, (l_low, right)));
+                    } else {
+                        top.variable = r_var;
+                        stack.push(ApplyTask::new(1, (left, r_high)));
+                        stack.push(ApplyTask::new(2, (left, r_low)));
+                    }
+                }
+            }
+        } else {
+            let (result_low, result_high) = (top.results[1], top.results[0]);
+            if result_low == result_high {
+                result = result_low;
+            } else {
+                result = node_cache.ensure(&Node::pack(top.variable, result_low, result_high));
+            }
+            task_cache.write(top.task, result);
+        }
+
+        if !result.is_undefined() {
+            stack.pop();
+            if stack.is_empty() {
+                return Ok((result, task_count));
+            }
+            let parent = stack.peek_at(top_offset);
+            parent.results[top_offset - 1] = result;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parallel_apply, parallel_apply_cancellable};
+    use crate::v4::apply::{apply, And, BooleanOperator, Imp, Or, TruthTable, Xor};
+    use crate::v4::core::{Bdd, CancellationToken, Cancelled};
+    use crate::v4::test_util::nodes;
+    use crate::verify::{FunctionTable, Lcg};
+
+    fn check_operator<Op: BooleanOperator>(operator: Op, function: fn(bool, bool) -> bool) {
+        let mut rng = Lcg::new(1234);
+        for density in &[5, 30, 50] {
+            let variables = 12;
+            let left = FunctionTable::random(variables, *density, &mut rng);
+            let right = FunctionTable::random(variables, *density, &mut rng);
+            let expected = left.apply(&right, function);
+            let left = left.to_bdd();
+            let right = right.to_bdd();
+            let expected = expected.to_bdd();
+
+            let (sequential, _) = apply(&left, &right, operator);
+            assert_eq!(nodes(&expected), nodes(&sequential));
+            assert_eq!(expected.get_height(), sequential.get_height());
+            for threads in &[1, 2, 3, 8] {
+                let (parallel, _) = parallel_apply(&left, &right, operator, *threads);
+                assert_eq!(nodes(&sequential), nodes(&parallel));
+            }
+        }
+    }
+
+    #[test]
+    pub fn parallel_apply_is_sequential_apply() {
+        check_operator(And, |l, r| l && r);
+        check_operator(Or, |l, r| l || r);
+        check_operator(Xor, |l, r| l ^ r);
+        check_operator(Imp, |l, r| !l || r);
+        check_operator(TruthTable::from_fn(|l, r| !(l || r)), |l, r| !(l || r));
+    }
+
+    #[test]
+    pub fn parallel_apply_constants() {
+        let one = Bdd::new_one();
+        let zero = Bdd::new_zero();
+        let bdd = FunctionTable::from_fn(6, |i| i % 3 == 0).to_bdd();
+        assert!(parallel_apply(&zero, &zero, Or, 4).0.is_zero());
+        assert!(parallel_apply(&bdd, &one, Or, 4).0.is_one());
+        let (result, _) = parallel_apply(&bdd, &zero, Or, 4);
+        assert_eq!(nodes(&bdd), nodes(&result));
+        let (result, _) = parallel_apply(&bdd, &one, And, 4);
+        assert_eq!(nodes(&bdd), nodes(&result));
+    }
+
+    #[test]
+    pub fn parallel_apply_cancellation() {
+        let mut rng = Lcg::new(99);
+        let left = FunctionTable::random(12, 50, &mut rng).to_bdd();
+        let right = FunctionTable::random(12, 50, &mut rng).to_bdd();
+        let (expected, _) = apply(&left, &right, Xor);
+        let token = CancellationToken::new();
+        for threads in &[1, 4] {
+            let (result, _) =
+                parallel_apply_cancellable(&left, &right, Xor, *threads, &token).unwrap();
+            assert_eq!(nodes(&expected), nodes(&result));
+        }
+        token.cancel();
+        for threads in &[1, 4] {
+            let result = parallel_apply_cancellable(&left, &right, Xor, *threads, &token);
+            assert_eq!(Err(Cancelled), result.map(|_| ()));
+        }
+    }
+}
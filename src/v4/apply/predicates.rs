@@ -0,0 +1,153 @@
+use super::super::core::{Bdd, NodeIndex, Variable};
+use super::task_cache::TaskCache;
+use super::{And, AndNot, BooleanOperator};
+use std::cmp::min;
+
+/// Find a valuation which satisfies the result of `operator` applied to the two BDDs, without
+/// computing the result itself.
+///
+/// This uses the same coupled DFS as `apply`, but no output nodes are created and the search
+/// stops as soon as a task resolves to the `1` terminal. The witness is returned as a list of
+/// literals sorted by variables. Variables which do not appear in the witness can have any
+/// value. If the result of the operation is `false`, returns `None`.
+pub fn find_witness<Op: BooleanOperator>(left_bdd: &Bdd, right_bdd: &Bdd, operator: Op) -> Option<Vec<(Variable, bool)>> {
+    // The task cache only stores tasks which were fully explored, i.e. they are known
+    // to resolve to the `0` terminal.
+    let mut task_cache = TaskCache::new(left_bdd.get_size());
+    let mut path: Vec<(Variable, bool)> = Vec::new();
+
+    let root_task = (left_bdd.get_root_index(), right_bdd.get_root_index());
+    let mut stack = vec![WitnessTask::new(root_task, 0, None)];
+    while let Some(WitnessTask { task, depth, literal, expanded }) = stack.pop() {
+        let (left, right) = task;
+        if expanded {
+            // Both sub-tasks are done and none of them found a witness.
+            let (_, slot) = task_cache.read(task);
+            task_cache.write(slot, task, NodeIndex::ZERO);
+            task_cache.grow_if_necessary();
+            continue;
+        }
+
+        path.truncate(depth);
+        if let Some(literal) = literal {
+            path.push(literal);
+        }
+
+        let result = operator.resolve(left, right);
+        if result.is_one() {
+            return Some(path);
+        }
+        if result.is_zero() || task_cache.read(task).0.is_zero() {
+            continue;
+        }
+
+        let (l_var, l_low, l_high) = left_bdd.get_node(left).unpack();
+        let (r_var, r_low, r_high) = right_bdd.get_node(right).unpack();
+        let variable = min(l_var, r_var);
+        let (l_low, l_high) = if l_var == variable { (l_low, l_high) } else { (left, left) };
+        let (r_low, r_high) = if r_var == variable { (r_low, r_high) } else { (right, right) };
+        let depth = path.len();
+        stack.push(WitnessTask {
+            expanded: true,
+            ..WitnessTask::new(task, depth, None)
+        });
+        stack.push(WitnessTask::new((l_high, r_high), depth, Some((variable, true))));
+        stack.push(WitnessTask::new((l_low, r_low), depth, Some((variable, false))));
+    }
+    None
+}
+
+/// A task of the witness search: the task itself, the length of the path to its parent,
+/// the literal on the edge from the parent, and whether the task was already expanded.
+struct WitnessTask {
+    task: (NodeIndex, NodeIndex),
+    depth: usize,
+    literal: Option<(Variable, bool)>,
+    expanded: bool,
+}
+
+impl WitnessTask {
+    fn new(task: (NodeIndex, NodeIndex), depth: usize, literal: Option<(Variable, bool)>) -> WitnessTask {
+        WitnessTask {
+            task,
+            depth,
+            literal,
+            expanded: false,
+        }
+    }
+}
+
+/// Predicates which do not need to compute the result of the operation.
+impl Bdd {
+    /// True if the conjunction of the two BDDs is the `false` function (i.e. they are disjoint).
+    pub fn and_is_zero(&self, other: &Bdd) -> bool {
+        find_witness(self, other, And).is_none()
+    }
+
+    /// True if there is a valuation which satisfies both BDDs.
+    pub fn intersects(&self, other: &Bdd) -> bool {
+        !self.and_is_zero(other)
+    }
+
+    /// True if every valuation which satisfies this `Bdd` also satisfies the `other` one.
+    pub fn is_subset(&self, other: &Bdd) -> bool {
+        find_witness(self, other, AndNot).is_none()
+    }
+
+    /// A valuation which satisfies both BDDs, or `None` if they are disjoint.
+    pub fn intersection_witness(&self, other: &Bdd) -> Option<Vec<(Variable, bool)>> {
+        find_witness(self, other, And)
+    }
+
+    /// A valuation which satisfies this `Bdd` but not the `other` one, or `None` if this `Bdd`
+    /// is a subset of the `other` one.
+    pub fn subset_counterexample(&self, other: &Bdd) -> Option<Vec<(Variable, bool)>> {
+        find_witness(self, other, AndNot)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::find_witness;
+    use crate::v4::apply::{apply, And, AndNot, Or, Xor};
+    use crate::v4::core::{Bdd, Variable};
+    use crate::verify::{FunctionTable, Lcg};
+
+    fn eval(bdd: &Bdd, witness: &[(Variable, bool)]) -> bool {
+        let mut index = bdd.get_root_index();
+        while !index.is_zero() && !index.is_one() {
+            let node = bdd.get_node(index);
+            // Variables missing in the witness can have any value, here we choose `false`.
+            let value = witness.iter().any(|(v, value)| *v == node.get_variable() && *value);
+            index = if value { node.get_high_link() } else { node.get_low_link() };
+        }
+        index.is_one()
+    }
+
+    #[test]
+    pub fn predicates_match_apply() {
+        let mut rng = Lcg::new(13);
+        for (l_density, r_density) in &[(5, 5), (50, 50), (10, 90), (100, 0), (30, 100)] {
+            let left = FunctionTable::random(8, *l_density, &mut rng).to_bdd();
+            let right = FunctionTable::random(8, *r_density, &mut rng).to_bdd();
+            let conjunction = apply(&left, &right, And).0;
+            let difference = apply(&left, &right, AndNot).0;
+            assert_eq!(conjunction.is_zero(), left.and_is_zero(&right));
+            assert_eq!(!conjunction.is_zero(), left.intersects(&right));
+            assert_eq!(difference.is_zero(), left.is_subset(&right));
+            if let Some(witness) = left.intersection_witness(&right) {
+                assert!(witness.windows(2).all(|pair| pair[0].0 < pair[1].0));
+                assert!(eval(&conjunction, &witness));
+            }
+            if let Some(witness) = left.subset_counterexample(&right) {
+                assert!(eval(&difference, &witness));
+            }
+            let xor = apply(&left, &right, Xor).0;
+            assert_eq!(xor.is_zero(), find_witness(&left, &right, Xor).is_none());
+        }
+        let a = Bdd::new_literal(Variable::from(0), true);
+        assert!(a.and_is_zero(&a.not()));
+        assert!(a.is_subset(&apply(&a, &Bdd::new_literal(Variable::from(1), true), Or).0));
+        assert_eq!(Some(vec![]), Bdd::new_one().intersection_witness(&Bdd::new_one()));
+    }
+}
@@ -0,0 +1,104 @@
+use super::super::core::{Bdd, Node, NodeIndex};
+use crate::{FromIndex, IntoIndex};
+use fxhash::FxHashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+/// A node cache that can be shared by multiple threads.
+///
+/// The nodes are partitioned into independent *shards* based on their hash, and each shard
+/// is protected by its own lock. As long as the number of shards is sufficiently larger than
+/// the number of threads, the threads should rarely compete for the same lock.
+///
+/// A node index produced by this cache identifies both the shard and the position of the node
+/// within that shard: The shard is stored in the upper 8 of the 48 bits that a `Node` can hold
+/// for each link, the rest is the position in the shard. The first shard never
+/// uses positions `0` and `1`, so the terminal node indices are valid in this cache as well.
+/// These indices are only meaningful within this cache and are remapped to normal indices once
+/// the result is exported into a `Bdd`.
+pub struct ShardedNodeCache {
+    shards: Vec<Mutex<NodeCacheShard>>,
+}
+
+struct NodeCacheShard {
+    nodes: Vec<Node>,
+    table: FxHashMap<Node, NodeIndex>,
+}
+
+impl ShardedNodeCache {
+    const SHARD_BITS: u32 = 40;
+    const POSITION_MASK: u64 = (1 << Self::SHARD_BITS) - 1;
+
+    /// Create a new cache with the given number of shards. Each shard is pre-allocated
+    /// such that the whole cache can hold at least `initial_capacity` nodes.
+    pub fn new(shard_count: usize, initial_capacity: usize) -> ShardedNodeCache {
+        assert!(shard_count > 0 && shard_count <= 255);
+        let shard_capacity = initial_capacity / shard_count;
+        let shards = (0..shard_count)
+            .map(|i| {
+                // Placeholders for the terminal nodes, which cannot be used by the first shard.
+                let nodes = if i == 0 {
+                    vec![Node::ZERO, Node::ONE]
+                } else {
+                    Vec::with_capacity(shard_capacity)
+                };
+                Mutex::new(NodeCacheShard {
+                    nodes,
+                    table: FxHashMap::default(),
+                })
+            })
+            .collect();
+        ShardedNodeCache { shards }
+    }
+
+    /// Find the index of the given `node`, or insert it into the cache if it is not there yet.
+    pub fn ensure(&self, node: &Node) -> NodeIndex {
+        let mut hasher = fxhash::FxHasher::default();
+        node.hash(&mut hasher);
+        let shard_id = (hasher.finish() >> 32) as usize % self.shards.len();
+        let mut shard = self.shards[shard_id].lock().unwrap();
+        if let Some(index) = shard.table.get(node) {
+            return *index;
+        }
+        let position = u64::from_index(shard.nodes.len());
+        let index = NodeIndex::from(((shard_id as u64) << Self::SHARD_BITS) | position);
+        shard.nodes.push(node.clone());
+        shard.table.insert(node.clone(), index);
+        index
+    }
+
+    /// Create a `Bdd` from the nodes of this cache that are reachable from the given `root`.
+    pub fn export(self, root: NodeIndex) -> Bdd {
+        let shards: Vec<NodeCacheShard> = self
+            .shards
+            .into_iter()
+            .map(|it| it.into_inner().unwrap())
+            .collect();
+        // Place the shards one after another into a single vector, translating the indices
+        // into positions within the vector.
+        let mut shard_offsets = Vec::with_capacity(shards.len());
+        let mut node_count = 0;
+        for shard in &shards {
+            shard_offsets.push(node_count);
+            node_count += shard.nodes.len();
+        }
+        let flatten = |index: NodeIndex| -> NodeIndex {
+            let index = u64::from(index);
+            let shard = (index >> Self::SHARD_BITS).into_index();
+            let position = (index & Self::POSITION_MASK).into_index();
+            NodeIndex::from_index(shard_offsets[shard] + position)
+        };
+        let mut nodes = Vec::with_capacity(node_count);
+        for shard in &shards {
+            for node in &shard.nodes {
+                if node.is_terminal() {
+                    nodes.push(node.clone());
+                } else {
+                    let (variable, low, high) = node.unpack();
+                    nodes.push(Node::pack(variable, flatten(low), flatten(high)));
+                }
+            }
+        }
+        Bdd::from_reachable_nodes(&nodes, flatten(root))
+    }
+}
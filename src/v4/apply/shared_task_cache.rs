@@ -0,0 +1,118 @@
+use super::super::core::NodeIndex;
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+
+/// A leaky task cache that can be shared by multiple threads.
+///
+/// Similar to `TaskCache`, entries are simply overwritten on collision. To avoid locking,
+/// every entry is protected by a *sequence lock*: the writer makes the version odd while it
+/// updates the entry and even once it is done. A reader then only accepts values for which it
+/// observed the same even version before and after reading them. If a reader or writer meets an
+/// entry that is being updated concurrently, it simply gives up, which is perfectly fine
+/// for a leaky cache.
+///
+/// The table does not grow, because growing would require synchronization of all threads.
+/// Instead, the capacity is chosen up-front as a power of two such that the index can
+/// be computed using a bit mask.
+pub struct SharedTaskCache {
+    mask: u64,
+    items: Vec<SharedTaskCacheEntry>,
+}
+
+struct SharedTaskCacheEntry {
+    version: AtomicU64,
+    left: AtomicU64,
+    right: AtomicU64,
+    result: AtomicU64,
+}
+
+impl SharedTaskCache {
+    const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+    /// Create a new cache that can hold at least `minimal_capacity` items.
+    pub fn new(minimal_capacity: u64) -> SharedTaskCache {
+        let capacity = minimal_capacity.max(2).next_power_of_two();
+        let items = (0..capacity)
+            .map(|_| SharedTaskCacheEntry {
+                version: AtomicU64::new(0),
+                left: AtomicU64::new(u64::from(NodeIndex::UNDEFINED)),
+                right: AtomicU64::new(u64::from(NodeIndex::UNDEFINED)),
+                result: AtomicU64::new(u64::from(NodeIndex::UNDEFINED)),
+            })
+            .collect();
+        SharedTaskCache {
+            mask: capacity - 1,
+            items,
+        }
+    }
+
+    /// Read the result of the given `task`, or `NodeIndex::UNDEFINED` if it is not cached.
+    #[inline]
+    pub fn read(&self, task: (NodeIndex, NodeIndex)) -> NodeIndex {
+        let entry = self.entry(task);
+        let version = entry.version.load(Ordering::Acquire);
+        if version & 1 == 1 {
+            return NodeIndex::UNDEFINED;
+        }
+        let left = entry.left.load(Ordering::Relaxed);
+        let right = entry.right.load(Ordering::Relaxed);
+        let result = entry.result.load(Ordering::Relaxed);
+        fence(Ordering::Acquire);
+        let is_consistent = entry.version.load(Ordering::Relaxed) == version;
+        if is_consistent && (NodeIndex::from(left), NodeIndex::from(right)) == task {
+            NodeIndex::from(result)
+        } else {
+            NodeIndex::UNDEFINED
+        }
+    }
+
+    /// Save the `result` of the given `task`, unless the entry is being written by another
+    /// thread at the same time.
+    #[inline]
+    pub fn write(&self, task: (NodeIndex, NodeIndex), result: NodeIndex) {
+        let entry = self.entry(task);
+        let version = entry.version.load(Ordering::Relaxed);
+        if version & 1 == 1 {
+            return;
+        }
+        let locked = entry.version.compare_exchange(
+            version,
+            version + 1,
+            Ordering::Acquire,
+            Ordering::Relaxed,
+        );
+        if locked.is_err() {
+            return;
+        }
+        // Order the odd version before the data: a reader that observes any of the new
+        // values must also observe the entry as locked on its second version check.
+        fence(Ordering::Release);
+        entry.left.store(u64::from(task.0), Ordering::Relaxed);
+        entry.right.store(u64::from(task.1), Ordering::Relaxed);
+        entry.result.store(u64::from(result), Ordering::Relaxed);
+        entry.version.store(version + 2, Ordering::Release);
+    }
+
+    fn entry(&self, task: (NodeIndex, NodeIndex)) -> &SharedTaskCacheEntry {
+        let (left, right) = (u64::from(task.0), u64::from(task.1));
+        let hash = (left.wrapping_mul(Self::SEED) ^ right).wrapping_mul(Self::SEED);
+        // The mask guarantees the index is always in bounds.
+        unsafe { self.items.get_unchecked((hash.rotate_left(32) & self.mask) as usize) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SharedTaskCache;
+    use crate::v4::core::NodeIndex;
+
+    #[test]
+    pub fn shared_task_cache_read_write() {
+        let cache = SharedTaskCache::new(10);
+        let task = (NodeIndex::from(4), NodeIndex::from(7));
+        assert!(cache.read(task).is_undefined());
+        cache.write(task, NodeIndex::from(12));
+        assert_eq!(NodeIndex::from(12), cache.read(task));
+        cache.write(task, NodeIndex::from(15));
+        assert_eq!(NodeIndex::from(15), cache.read(task));
+    }
+}
@@ -1,5 +1,7 @@
-use super::super::core::NodeIndex;
+use super::super::core::{Node, NodeIndex};
 use crate::IntoIndex;
+use std::cmp::max;
+use std::mem::size_of;
 use std::ops::Rem;
 
 type KeyValuePair = ((NodeIndex, NodeIndex), NodeIndex);
@@ -27,6 +29,10 @@ type KeyValuePair = ((NodeIndex, NodeIndex), NodeIndex);
 /// it is added to the base value, it will add a certain amount of pseudo-random noise to it.
 /// This noise will significantly reduce collisions, but it cannot make the hash diverge too much
 /// from the expected base value and should therefore preserve its locality.
+///
+/// To clear the cache without erasing the whole table, every stored left index is tagged with
+/// the current *generation* in its upper bits (above `Node::MAX_LINK`). Clearing then only
+/// starts a new generation, and entries of older generations no longer match any task.
 pub struct TaskCache {
     /// The number of elements inserted into the cache so far. Used to determine whether
     /// we should grow the cache.
@@ -36,7 +42,15 @@ pub struct TaskCache {
     bit_extension: u64,
     /// The actual capacity of the table when discounting the hash block size.
     capacity: u64,
-    items: Vec<KeyValuePair>
+    items: Vec<KeyValuePair>,
+    /// The cache grows once `load_factor * capacity` elements were inserted.
+    load_factor: u64,
+    /// The number of times the cache was grown.
+    grow_count: usize,
+    /// The number of writes that overwrote the result of a different task.
+    collisions: usize,
+    /// The generation of the current entries, already shifted above `Node::MAX_LINK`.
+    generation: u64,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
@@ -68,42 +82,91 @@ impl TaskCache {
     const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
     const HASH_BLOCK: u64 = 1 << 13;
     const UNDEFINED_ENTRY: KeyValuePair = ((NodeIndex::UNDEFINED, NodeIndex::UNDEFINED), NodeIndex::UNDEFINED);
+    const GENERATION_SHIFT: u32 = 64 - Node::MAX_LINK.leading_zeros();
+    /// The number of usable generations. The last possible generation is skipped, such that a
+    /// tagged index cannot be `NodeIndex::UNDEFINED`.
+    const GENERATIONS: u64 = u64::MAX >> Self::GENERATION_SHIFT;
 
     pub fn new(initial_capacity: u64) -> TaskCache {
         // By growing the cash capacity by the hash block size, we ensure that modulo is not needed
-        // on the computed hashed indices.
+        // on the computed hashed indices. The capacity is also at least one hash block, otherwise
+        // rehashing could insert more elements than the grown table is expected to hold.
+        let initial_capacity = initial_capacity.max(Self::HASH_BLOCK);
         let actual_capacity = initial_capacity + Self::HASH_BLOCK;
         TaskCache {
             elements: 0,
             bit_extension: 0,
             capacity: initial_capacity,
-            items: vec![Self::UNDEFINED_ENTRY; actual_capacity.into_index()]
+            items: vec![Self::UNDEFINED_ENTRY; actual_capacity.into_index()],
+            load_factor: 2,
+            grow_count: 0,
+            collisions: 0,
+            generation: 0,
         }
     }
 
+    /// Set the number of insertions (relative to capacity) after which the cache grows.
+    pub fn set_load_factor(&mut self, load_factor: u64) {
+        self.load_factor = max(load_factor, 1);
+    }
+
+    /// The number of times the cache was grown.
+    pub fn grow_count(&self) -> usize {
+        self.grow_count
+    }
+
+    /// The number of writes that overwrote the result of a different task.
+    pub fn collisions(&self) -> usize {
+        self.collisions
+    }
+
+    /// The amount of memory used by the cache table (in bytes).
+    pub fn memory(&self) -> usize {
+        self.items.len() * size_of::<KeyValuePair>()
+    }
+
     #[inline]
     pub fn read(&self, task: (NodeIndex, NodeIndex)) -> (NodeIndex, TaskCacheSlot) {
         // Note that this has been tested as slightly faster than a version that returns
         // the values as a Result<NodeIndex, TaskCacheSlot>.
         let slot = self.hashed_index(task);
         let slot_value = unsafe { self.items.get_unchecked(slot.into_index()) };
-        if slot_value.0 == task {
+        if slot_value.0 == self.tag(task) {
             (slot_value.1, slot)
         } else {
             (NodeIndex::UNDEFINED, slot)
         }
     }
 
+    /// Hint the CPU to load the cache slot of the given `task`, such that a later `read`
+    /// does not have to wait for memory. Does nothing on platforms other than `x86_64`.
+    #[inline]
+    pub fn prefetch(&self, task: (NodeIndex, NodeIndex)) {
+        #[cfg(target_arch = "x86_64")]
+        unsafe {
+            let pointer = self.items.as_ptr().wrapping_add(self.hashed_index(task).into_index());
+            std::arch::x86_64::_mm_prefetch::<3>(pointer as *const i8);
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        let _ = task;
+    }
+
     #[inline]
     pub fn write(&mut self, slot: TaskCacheSlot, task: (NodeIndex, NodeIndex), result: NodeIndex) {
+        let task = self.tag(task);
+        let generation = self.generation;
         let slot_value = unsafe { self.items.get_unchecked_mut(slot.into_index()) };
+        let is_current = u64::from((slot_value.0).0) & !Node::MAX_LINK == generation;
+        if is_current && slot_value.0 != task {
+            self.collisions += 1;
+        }
         *slot_value = (task, result);
         self.elements += 1;
     }
 
     pub fn grow_if_necessary(&mut self) -> u64 {
-        if self.elements >= 2 * self.capacity {
-            println!("Grow task cache. Current: {}.", self.items.len());
+        if self.elements >= self.load_factor * self.capacity {
+            self.grow_count += 1;
             // Add one extra bit into the right index bit mask, and reset element count.
             self.bit_extension = (self.bit_extension << 1) | 1;
             self.elements = 0;
@@ -111,16 +174,53 @@ impl TaskCache {
             self.capacity = self.capacity * 2;
             let mut items = vec![Self::UNDEFINED_ENTRY; (self.capacity + Self::HASH_BLOCK).into_index()];
             std::mem::swap(&mut items, &mut self.items);
-            // Rehash all values in the table.
+            // Rehash all values of the current generation.
             for (key, value) in items {
-                if !value.is_undefined() {
+                if u64::from(key.0) & !Node::MAX_LINK == self.generation {
+                    let key = (NodeIndex::from(u64::from(key.0) & Node::MAX_LINK), key.1);
                     let slot = self.hashed_index(key);
                     self.write(slot, key, value);
                 }
             }
-            println!("Task cache grown to {}.", self.items.len());
         }
-        2 * self.capacity - self.elements
+        self.load_factor * self.capacity - self.elements
+    }
+
+    /// Remove all entries from the cache, such that it can be reused for a new computation where
+    /// the left BDD has up to `minimal_capacity` nodes. The grown table (including its extra hash
+    /// bits) is preserved, unless it is too small, in which case the cache is reallocated.
+    ///
+    /// The table itself is only erased once all generations have been used up.
+    pub fn clear(&mut self, minimal_capacity: u64) {
+        // The hash base is the left index shifted by the number of extension bits, so this is
+        // the largest left BDD that the table can accommodate.
+        let base_capacity = self.capacity >> self.bit_extension.count_ones();
+        if base_capacity < minimal_capacity {
+            let load_factor = self.load_factor;
+            *self = TaskCache::new(minimal_capacity);
+            self.load_factor = load_factor;
+        } else {
+            self.elements = 0;
+            if (self.generation >> Self::GENERATION_SHIFT) + 1 == Self::GENERATIONS {
+                self.generation = 0;
+                for item in self.items.iter_mut() {
+                    *item = Self::UNDEFINED_ENTRY;
+                }
+            } else {
+                self.generation += 1 << Self::GENERATION_SHIFT;
+            }
+        }
+    }
+
+    /// The amount of memory (in bytes) the cache will need during the next call
+    /// to `grow_if_necessary`. While growing, both the old and the new table are allocated.
+    pub fn memory_after_growth(&self) -> usize {
+        if self.elements >= self.load_factor * self.capacity {
+            let grown = (2 * self.capacity + Self::HASH_BLOCK).into_index();
+            self.memory() + grown * size_of::<KeyValuePair>()
+        } else {
+            self.memory()
+        }
     }
 
     fn hashed_index(&self, task: (NodeIndex, NodeIndex)) -> TaskCacheSlot {
@@ -142,4 +242,34 @@ impl TaskCache {
         (block_base + block_offset).into()
     }
 
-}
\ No newline at end of file
+    /// Tag the left index of the `task` with the current generation.
+    #[inline]
+    fn tag(&self, task: (NodeIndex, NodeIndex)) -> (NodeIndex, NodeIndex) {
+        (NodeIndex::from(u64::from(task.0) | self.generation), task.1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TaskCache;
+    use crate::v4::core::NodeIndex;
+
+    #[test]
+    pub fn task_cache_clear_generations() {
+        let mut cache = TaskCache::new(10);
+        let task = (NodeIndex::from(4), NodeIndex::from(7));
+        let (_, slot) = cache.read(task);
+        cache.write(slot, task, NodeIndex::from(12));
+        assert_eq!(NodeIndex::from(12), cache.read(task).0);
+        // Clearing also has to work once the generations wrap around.
+        for _ in 0..TaskCache::GENERATIONS + 1 {
+            cache.clear(10);
+            assert!(cache.read(task).0.is_undefined());
+            let (_, slot) = cache.read(task);
+            cache.write(slot, task, NodeIndex::from(12));
+            cache.grow_if_necessary();
+            assert_eq!(NodeIndex::from(12), cache.read(task).0);
+        }
+        assert_eq!(0, cache.collisions());
+    }
+}
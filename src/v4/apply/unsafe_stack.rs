@@ -13,8 +13,17 @@ impl <T: Sized + Copy> UnsafeStack<T> {
         }
     }
 
-    pub fn len(&self) -> usize {
-        self.index_after_last
+    /// Remove all items and make sure the stack can hold at least `capacity` items.
+    pub fn reset(&mut self, capacity: usize) {
+        self.index_after_last = 0;
+        if self.items.len() < capacity {
+            *self = UnsafeStack::new(capacity);
+        }
+    }
+
+    /// The amount of memory used by the stack (in bytes).
+    pub fn memory(&self) -> usize {
+        self.items.len() * std::mem::size_of::<T>()
     }
 
     pub fn is_empty(&self) -> bool {
@@ -0,0 +1,439 @@
+use super::super::core::{Bdd, Node, NodeIndex, Variable};
+use super::_node_cache::ComplementNodeCache;
+use super::{ComplementEdge, ComplementNode};
+use crate::{FromIndex, IntoIndex};
+use fxhash::FxHashMap;
+use std::cmp::max;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+/// A `ComplementBdd` is a BDD which uses complemented edges (see module documentation).
+///
+/// The layout follows `Bdd`: The terminal node is always the first node, and the root node is
+/// the last node. However, the function is given by the root *edge*, which may be complemented.
+/// The height is defined in the same way as for `Bdd`.
+///
+/// The nodes are shared by all clones of a `ComplementBdd` (and its negation), hence cloning
+/// and negation are constant-time operations.
+#[derive(Clone)]
+pub struct ComplementBdd {
+    height: u32,
+    root: ComplementEdge,
+    nodes: Arc<Vec<ComplementNode>>,
+}
+
+/// Basic operations for examining the contents of a BDD.
+impl ComplementBdd {
+    /// Create a BDD representing the constant `0`.
+    pub fn new_zero() -> ComplementBdd {
+        ComplementBdd {
+            height: 0,
+            root: ComplementEdge::ZERO,
+            nodes: Arc::new(vec![ComplementNode::TERMINAL]),
+        }
+    }
+
+    /// Create a BDD representing the constant `1`.
+    pub fn new_one() -> ComplementBdd {
+        ComplementBdd {
+            height: 0,
+            root: ComplementEdge::ONE,
+            nodes: Arc::new(vec![ComplementNode::TERMINAL]),
+        }
+    }
+
+    /// Create a BDD representing the literal `variable = value`.
+    pub fn new_literal(variable: Variable, value: bool) -> ComplementBdd {
+        let node = ComplementNode::pack(variable, ComplementEdge::ZERO, ComplementEdge::ONE);
+        ComplementBdd {
+            height: 2,
+            root: ComplementEdge::new(NodeIndex::ONE, !value),
+            nodes: Arc::new(vec![ComplementNode::TERMINAL, node]),
+        }
+    }
+
+    /// Upper bound on the height of the BDD graph.
+    #[inline]
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// The number of nodes in the BDD graph (including the terminal node).
+    #[inline]
+    pub fn get_size(&self) -> u64 {
+        u64::from_index(self.nodes.len())
+    }
+
+    /// The edge which points to the root of the graph.
+    #[inline]
+    pub fn get_root(&self) -> ComplementEdge {
+        self.root
+    }
+
+    /// Get a reference to a `ComplementNode` using the given `index`.
+    #[inline]
+    pub fn get_node(&self, index: NodeIndex) -> &ComplementNode {
+        &self.nodes[index.into_index()]
+    }
+
+    /// True if the BDD represents a constant (terminal) value.
+    #[inline]
+    pub fn is_constant(&self) -> bool {
+        self.root.is_terminal()
+    }
+
+    /// True if the BDD represents a constant zero value.
+    #[inline]
+    pub fn is_zero(&self) -> bool {
+        self.root.is_zero()
+    }
+
+    /// True if the BDD represents a constant one value.
+    #[inline]
+    pub fn is_one(&self) -> bool {
+        self.root.is_one()
+    }
+
+    /// Create a BDD representing the negation of this BDD in constant time. The result shares
+    /// all nodes with this BDD, only the root edge is negated.
+    pub fn not(&self) -> ComplementBdd {
+        ComplementBdd {
+            height: self.height,
+            root: !self.root,
+            nodes: Arc::clone(&self.nodes),
+        }
+    }
+
+    /// Negate this BDD in place in constant time.
+    pub fn negate(&mut self) {
+        self.root = !self.root;
+    }
+}
+
+/// Validation and normalization methods.
+impl ComplementBdd {
+    /// Dynamically verify that the given nodes and root can be safely interpreted as
+    /// a canonical `ComplementBdd`.
+    ///
+    /// In case of error, returns an error string.
+    pub fn check_consistency_errors(nodes: &[ComplementNode], root: ComplementEdge) -> Option<String> {
+        if nodes.first() != Some(&ComplementNode::TERMINAL) {
+            return Some("The first node must be the terminal node.".to_string());
+        }
+        let last = NodeIndex::from_index(nodes.len() - 1);
+        if root.get_index() != last {
+            return Some(format!("Root {:?} is not the last node ({:?}).", root, last));
+        }
+        for node in nodes.iter().skip(1) {
+            if node.is_terminal() {
+                return Some(format!("Unexpected terminal node {:?}.", node));
+            }
+            let (variable, low, high) = node.unpack();
+            if low.get_index() > last || high.get_index() > last {
+                return Some(format!("Link in {:?} is out of bounds ({:?} is root).", node, last));
+            }
+            if high.is_complemented() {
+                return Some(format!("High link in {:?} is complemented.", node));
+            }
+            if low == high {
+                return Some(format!("Node {:?} is redundant.", node));
+            }
+            let low_variable = nodes[low.get_index().into_index()].get_variable();
+            let high_variable = nodes[high.get_index().into_index()].get_variable();
+            if low_variable <= variable || high_variable <= variable {
+                return Some(format!("Links in {:?} violate variable order.", node));
+            }
+        }
+        None
+    }
+
+    /// Create a new `ComplementBdd` which only contains the nodes reachable from `root`, with
+    /// the nodes sorted in DFS pre-order (root is the last node, since this is required).
+    pub(super) fn from_reachable_nodes(nodes: &[ComplementNode], root: ComplementEdge) -> ComplementBdd {
+        if root.is_terminal() {
+            return if root.is_one() {
+                ComplementBdd::new_one()
+            } else {
+                ComplementBdd::new_zero()
+            };
+        }
+
+        let mut new_index = vec![NodeIndex::UNDEFINED; nodes.len()];
+        let mut preorder: Vec<NodeIndex> = Vec::new();
+        let mut search_stack: Vec<NodeIndex> = vec![root.get_index()];
+        while let Some(task) = search_stack.pop() {
+            if task.is_zero() || !new_index[task.into_index()].is_undefined() {
+                continue;
+            }
+            new_index[task.into_index()] = NodeIndex::from_index(preorder.len());
+            preorder.push(task);
+
+            let node = &nodes[task.into_index()];
+            search_stack.push(node.get_high_link().get_index());
+            search_stack.push(node.get_low_link().get_index());
+        }
+
+        let remap = |edge: ComplementEdge| -> ComplementEdge {
+            if edge.is_terminal() {
+                edge
+            } else {
+                let order = new_index[edge.get_index().into_index()].into_index();
+                let index = NodeIndex::from_index(preorder.len() - order);
+                ComplementEdge::new(index, edge.is_complemented())
+            }
+        };
+        let mut new_nodes = vec![ComplementNode::TERMINAL; preorder.len() + 1];
+        for old_index in &preorder {
+            let (variable, low, high) = nodes[old_index.into_index()].unpack();
+            let new_node = ComplementNode::pack(variable, remap(low), remap(high));
+            let target = remap(ComplementEdge::new(*old_index, false)).get_index();
+            new_nodes[target.into_index()] = new_node;
+        }
+
+        let mut bdd = ComplementBdd {
+            height: u32::MAX,
+            root: remap(root),
+            nodes: Arc::new(new_nodes),
+        };
+        bdd.recompute_height();
+        bdd
+    }
+
+    /// Update the height value of this BDD with a true height obtained using a DFS search.
+    fn recompute_height(&mut self) {
+        if self.is_constant() {
+            self.height = 0;
+            return;
+        }
+
+        let mut height: Vec<u32> = vec![0; self.nodes.len()];
+        height[0] = 1;
+        let mut stack: Vec<(NodeIndex, bool)> = vec![(self.root.get_index(), false)];
+        while let Some((index, expanded)) = stack.pop() {
+            let low = self.get_node(index).get_low_link().get_index();
+            let high = self.get_node(index).get_high_link().get_index();
+            if expanded {
+                let children = max(height[low.into_index()], height[high.into_index()]);
+                height[index.into_index()] = children + 1;
+            } else if height[index.into_index()] == 0 {
+                stack.push((index, true));
+                if height[high.into_index()] == 0 {
+                    stack.push((high, false));
+                }
+                if height[low.into_index()] == 0 {
+                    stack.push((low, false));
+                }
+            }
+        }
+        self.height = height[self.root.get_index().into_index()];
+    }
+}
+
+/// Convert a standard `Bdd` into a `ComplementBdd`.
+impl From<&Bdd> for ComplementBdd {
+    fn from(bdd: &Bdd) -> Self {
+        let mut cache = ComplementNodeCache::new(bdd.get_size().into_index());
+        let mut results: Vec<Option<ComplementEdge>> = vec![None; bdd.get_size().into_index()];
+        results[0] = Some(ComplementEdge::ZERO);
+        if results.len() > 1 {
+            results[1] = Some(ComplementEdge::ONE);
+        }
+
+        let mut stack = vec![(bdd.get_root_index(), false)];
+        while let Some((index, expanded)) = stack.pop() {
+            if results[index.into_index()].is_some() {
+                continue;
+            }
+            let (variable, low, high) = bdd.get_node(index).unpack();
+            if expanded {
+                let low = results[low.into_index()].unwrap();
+                let high = results[high.into_index()].unwrap();
+                results[index.into_index()] = Some(cache.ensure(variable, low, high));
+            } else {
+                stack.push((index, true));
+                stack.push((high, false));
+                stack.push((low, false));
+            }
+        }
+
+        cache.export(results[bdd.get_root_index().into_index()].unwrap())
+    }
+}
+
+/// Convert a `ComplementBdd` into a standard `Bdd`. Every node of the `ComplementBdd` can
+/// correspond to up to two nodes of the `Bdd` (regular and negated).
+impl From<&ComplementBdd> for Bdd {
+    fn from(bdd: &ComplementBdd) -> Self {
+        if bdd.is_zero() {
+            return Bdd::new_zero();
+        }
+        if bdd.is_one() {
+            return Bdd::new_one();
+        }
+
+        let mut nodes = vec![Node::ZERO, Node::ONE];
+        let mut results: FxHashMap<ComplementEdge, NodeIndex> = FxHashMap::default();
+        results.insert(ComplementEdge::ZERO, NodeIndex::ZERO);
+        results.insert(ComplementEdge::ONE, NodeIndex::ONE);
+
+        let mut stack = vec![(bdd.get_root(), false)];
+        while let Some((edge, expanded)) = stack.pop() {
+            if results.contains_key(&edge) {
+                continue;
+            }
+            let (variable, low, high) = bdd.get_node(edge.get_index()).unpack();
+            let low = low.complement_if(edge.is_complemented());
+            let high = high.complement_if(edge.is_complemented());
+            if expanded {
+                // Distinct edges represent distinct functions, so no unique table is needed.
+                nodes.push(Node::pack(variable, results[&low], results[&high]));
+                results.insert(edge, NodeIndex::from_index(nodes.len() - 1));
+            } else {
+                stack.push((edge, true));
+                stack.push((high, false));
+                stack.push((low, false));
+            }
+        }
+
+        Bdd::from_reachable_nodes(&nodes, results[&bdd.get_root()])
+    }
+}
+
+fn write_edge(f: &mut Formatter<'_>, edge: ComplementEdge) -> std::fmt::Result {
+    if edge.is_complemented() {
+        write!(f, "!")?;
+    }
+    write!(f, "{}", u64::from(edge.get_index()))
+}
+
+fn parse_edge(data: &str) -> Result<ComplementEdge, String> {
+    let (is_complemented, index) = if let Some(index) = data.strip_prefix('!') {
+        (true, index)
+    } else {
+        (false, data)
+    };
+    match index.parse::<u64>() {
+        Ok(index) if index < (1 << 56) => Ok(ComplementEdge::new(NodeIndex::from(index), is_complemented)),
+        _ => Err(format!("Invalid edge `{}`.", data)),
+    }
+}
+
+/// Serialization into a simple string format: The root edge, followed by the nodes in the
+/// format `variable,low,high`, all separated by `|`. Complemented edges have a `!` prefix.
+/// For example, the negated literal of variable `3` is `!1|4294967295,0,0|3,!0,0`.
+impl Display for ComplementBdd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_edge(f, self.root)?;
+        for node in self.nodes.iter() {
+            let (variable, low, high) = node.unpack();
+            write!(f, "|{},", u32::from(variable))?;
+            write_edge(f, low)?;
+            write!(f, ",")?;
+            write_edge(f, high)?;
+        }
+        Ok(())
+    }
+}
+
+/// Deserialization of the format produced by `Display`.
+impl TryFrom<&str> for ComplementBdd {
+    type Error = String;
+
+    fn try_from(data: &str) -> Result<Self, Self::Error> {
+        let mut items = data.split('|').filter(|s| !s.is_empty());
+        let root = match items.next() {
+            Some(root) => parse_edge(root)?,
+            None => return Err("Missing root edge.".to_string()),
+        };
+        let mut nodes = Vec::new();
+        for node_string in items {
+            let node_items: Vec<&str> = node_string.split(',').collect();
+            if node_items.len() != 3 {
+                return Err(format!("Unexpected node representation `{}`.", node_string));
+            }
+            let variable = if let Ok(x) = node_items[0].parse::<u32>() {
+                Variable::from(x)
+            } else {
+                return Err(format!("Invalid variable numeral `{}`.", node_items[0]));
+            };
+            let low = parse_edge(node_items[1])?;
+            let high = parse_edge(node_items[2])?;
+            nodes.push(ComplementNode::pack(variable, low, high));
+        }
+        if nodes.is_empty() {
+            return Err("Missing terminal node.".to_string());
+        }
+        if nodes.len() == 1 && root.is_terminal() {
+            return Ok(ComplementBdd::from_reachable_nodes(&nodes, root));
+        }
+        if let Some(error) = ComplementBdd::check_consistency_errors(&nodes, root) {
+            Err(error)
+        } else {
+            let mut bdd = ComplementBdd {
+                height: u32::MAX,
+                root,
+                nodes: Arc::new(nodes),
+            };
+            bdd.recompute_height();
+            Ok(bdd)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ComplementBdd;
+    use crate::v4::core::{Bdd, Variable};
+    use crate::v4::test_util::nodes;
+    use crate::verify::{FunctionTable, Lcg};
+    use std::convert::TryFrom;
+
+    #[test]
+    pub fn complement_bdd_conversion() {
+        let mut rng = Lcg::new(5);
+        for density in &[0, 10, 50, 90, 100] {
+            let table = FunctionTable::random(6, *density, &mut rng);
+            let bdd = table.to_bdd();
+            let complement = ComplementBdd::from(&bdd);
+            assert!(complement.get_size() <= bdd.get_size());
+            assert_eq!(bdd.get_height(), complement.get_height());
+            assert_eq!(nodes(&bdd), nodes(&Bdd::from(&complement)));
+            assert_eq!(nodes(&bdd.not()), nodes(&Bdd::from(&complement.not())));
+            // The negation shares all nodes.
+            assert_eq!(complement.get_size(), ComplementBdd::from(&bdd.not()).get_size());
+        }
+    }
+
+    #[test]
+    pub fn complement_bdd_literals() {
+        let positive = ComplementBdd::new_literal(Variable::from(3), true);
+        let negative = ComplementBdd::new_literal(Variable::from(3), false);
+        assert_eq!(positive.get_root(), !negative.get_root());
+        let expected = Bdd::new_literal(Variable::from(3), false);
+        assert_eq!(nodes(&expected), nodes(&Bdd::from(&negative)));
+        assert!(ComplementBdd::new_zero().not().is_one());
+        assert!(ComplementBdd::from(&Bdd::new_zero()).is_zero());
+    }
+
+    #[test]
+    pub fn complement_bdd_serialization() {
+        let negative = ComplementBdd::new_literal(Variable::from(3), false);
+        assert_eq!("!1|4294967295,0,0|3,!0,0", negative.to_string());
+
+        let mut rng = Lcg::new(3);
+        let table = FunctionTable::random(5, 50, &mut rng);
+        let complement = ComplementBdd::from(&table.to_bdd());
+        for bdd in &[complement.clone(), complement.not(), ComplementBdd::new_zero()] {
+            let parsed = ComplementBdd::try_from(bdd.to_string().as_str()).unwrap();
+            assert_eq!(bdd.to_string(), parsed.to_string());
+            assert_eq!(bdd.get_height(), parsed.get_height());
+        }
+
+        assert!(ComplementBdd::try_from("").is_err());
+        assert!(ComplementBdd::try_from("1|4294967295,0,0|3,0,!0").is_err());
+        assert!(ComplementBdd::try_from("1|4294967295,0,0|3,!0,!0").is_err());
+        assert!(ComplementBdd::try_from("2|4294967295,0,0|3,!0,0").is_err());
+        assert!(ComplementBdd::try_from("1|4294967295,0,0|3,x,0").is_err());
+    }
+}
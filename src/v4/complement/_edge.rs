@@ -0,0 +1,113 @@
+use super::super::core::NodeIndex;
+use std::ops::Not;
+
+/// A reference to a node of a `ComplementBdd` together with a complement flag.
+///
+/// The flag is stored in the most significant bit of the underlying index. The index itself
+/// can be only obtained using `ComplementEdge::get_index`, which erases the flag.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ComplementEdge(u64);
+
+impl ComplementEdge {
+    const COMPLEMENT_BIT: u64 = 1 << 63;
+    /// The indices must fit into 56 bits, the rest is reserved for metadata.
+    const INDEX_MASK: u64 = (1 << 56) - 1;
+
+    /// A regular edge pointing to the terminal node.
+    pub const ONE: ComplementEdge = ComplementEdge(0);
+    /// A complemented edge pointing to the terminal node.
+    pub const ZERO: ComplementEdge = ComplementEdge(Self::COMPLEMENT_BIT);
+
+    /// Create a new edge pointing to the node at `index`.
+    ///
+    /// *Panics:* The index must fit into 56 bits (in particular, it cannot be undefined).
+    #[inline]
+    pub fn new(index: NodeIndex, is_complemented: bool) -> ComplementEdge {
+        let index = u64::from(index);
+        assert_eq!(index & Self::INDEX_MASK, index, "Index {} cannot store metadata.", index);
+        if is_complemented {
+            ComplementEdge(index | Self::COMPLEMENT_BIT)
+        } else {
+            ComplementEdge(index)
+        }
+    }
+
+    /// The index of the target node, without the complement flag.
+    #[inline]
+    pub fn get_index(&self) -> NodeIndex {
+        NodeIndex::from(self.0 & Self::INDEX_MASK)
+    }
+
+    #[inline]
+    pub fn is_complemented(&self) -> bool {
+        self.0 & Self::COMPLEMENT_BIT != 0
+    }
+
+    /// True if the edge points to the terminal node (i.e. it is either `ONE` or `ZERO`).
+    #[inline]
+    pub fn is_terminal(&self) -> bool {
+        self.0 & Self::INDEX_MASK == 0
+    }
+
+    #[inline]
+    pub fn is_one(&self) -> bool {
+        *self == Self::ONE
+    }
+
+    #[inline]
+    pub fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+
+    /// The same edge, complemented if `flag` is set.
+    #[inline]
+    pub fn complement_if(self, flag: bool) -> ComplementEdge {
+        if flag {
+            !self
+        } else {
+            self
+        }
+    }
+
+    /// The same edge without the complement flag.
+    #[inline]
+    pub fn regular(self) -> ComplementEdge {
+        ComplementEdge(self.0 & !Self::COMPLEMENT_BIT)
+    }
+}
+
+/// The same edge with the complement flag inverted.
+impl Not for ComplementEdge {
+    type Output = ComplementEdge;
+
+    #[inline]
+    fn not(self) -> ComplementEdge {
+        ComplementEdge(self.0 ^ Self::COMPLEMENT_BIT)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ComplementEdge;
+    use crate::v4::core::NodeIndex;
+
+    #[test]
+    pub fn complement_edge_flags() {
+        let edge = ComplementEdge::new(NodeIndex::from(5), false);
+        assert!(!edge.is_complemented() && !edge.is_terminal());
+        assert!((!edge).is_complemented());
+        assert_eq!(NodeIndex::from(5), (!edge).get_index());
+        assert_eq!(edge, !!edge);
+        assert_eq!(edge, (!edge).regular());
+        assert_eq!(!edge, edge.complement_if(true));
+        assert_eq!(ComplementEdge::ZERO, !ComplementEdge::ONE);
+        assert!(ComplementEdge::ZERO.is_terminal() && ComplementEdge::ZERO.is_zero());
+        assert_eq!(NodeIndex::ZERO, ComplementEdge::ZERO.get_index());
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn complement_edge_rejects_metadata() {
+        ComplementEdge::new(NodeIndex::UNDEFINED, false);
+    }
+}
@@ -0,0 +1,44 @@
+use super::super::core::Variable;
+use super::ComplementEdge;
+
+/// A decision node of a `ComplementBdd`.
+///
+/// Same as `Node`, but the children are referenced using `ComplementEdge`. The only terminal
+/// node (`1`) uses an undefined variable and regular self-loops.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ComplementNode(Variable, ComplementEdge, ComplementEdge);
+
+impl ComplementNode {
+    pub const TERMINAL: ComplementNode =
+        ComplementNode(Variable::UNDEFINED, ComplementEdge::ONE, ComplementEdge::ONE);
+
+    #[inline]
+    pub fn pack(variable: Variable, low: ComplementEdge, high: ComplementEdge) -> ComplementNode {
+        ComplementNode(variable, low, high)
+    }
+
+    #[inline]
+    pub fn unpack(&self) -> (Variable, ComplementEdge, ComplementEdge) {
+        (self.0, self.1, self.2)
+    }
+
+    #[inline]
+    pub fn is_terminal(&self) -> bool {
+        self.0.is_undefined()
+    }
+
+    #[inline]
+    pub fn get_variable(&self) -> Variable {
+        self.0
+    }
+
+    #[inline]
+    pub fn get_low_link(&self) -> ComplementEdge {
+        self.1
+    }
+
+    #[inline]
+    pub fn get_high_link(&self) -> ComplementEdge {
+        self.2
+    }
+}
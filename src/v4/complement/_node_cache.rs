@@ -0,0 +1,49 @@
+use super::super::core::{NodeIndex, Variable};
+use super::{ComplementBdd, ComplementEdge, ComplementNode};
+use crate::FromIndex;
+use fxhash::FxHashMap;
+
+/// A simple unique table for building a `ComplementBdd` node by node.
+///
+/// The cache makes sure that the created nodes are canonical: Redundant nodes are never created,
+/// and a node with a complemented high edge is instead stored with both edges negated and
+/// referenced through a complemented edge.
+pub(super) struct ComplementNodeCache {
+    nodes: Vec<ComplementNode>,
+    table: FxHashMap<ComplementNode, NodeIndex>,
+}
+
+impl ComplementNodeCache {
+    pub fn new(capacity: usize) -> ComplementNodeCache {
+        let mut nodes = Vec::with_capacity(capacity);
+        nodes.push(ComplementNode::TERMINAL);
+        ComplementNodeCache {
+            nodes,
+            table: FxHashMap::default(),
+        }
+    }
+
+    /// Get an edge representing the function `if variable { high } else { low }`.
+    pub fn ensure(&mut self, variable: Variable, low: ComplementEdge, high: ComplementEdge) -> ComplementEdge {
+        if low == high {
+            return low;
+        }
+        let is_complemented = high.is_complemented();
+        let node = ComplementNode::pack(
+            variable,
+            low.complement_if(is_complemented),
+            high.regular(),
+        );
+        let nodes = &mut self.nodes;
+        let index = *self.table.entry(node).or_insert_with_key(|node| {
+            nodes.push(node.clone());
+            NodeIndex::from_index(nodes.len() - 1)
+        });
+        ComplementEdge::new(index, is_complemented)
+    }
+
+    /// Create a `ComplementBdd` from the nodes of this cache reachable from the given `root`.
+    pub fn export(self, root: ComplementEdge) -> ComplementBdd {
+        ComplementBdd::from_reachable_nodes(&self.nodes, root)
+    }
+}
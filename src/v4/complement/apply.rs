@@ -0,0 +1,252 @@
+use super::super::apply::node_cache::NodeCache;
+use super::super::apply::task_cache::TaskCache;
+use super::super::apply::unsafe_stack::UnsafeStack;
+use super::super::apply::{ApplyConfig, ApplyTask, BooleanOperator};
+use super::super::core::{Node, NodeIndex, Variable};
+use super::{ComplementBdd, ComplementEdge, ComplementNode};
+use crate::IntoIndex;
+use std::cmp::{max, min};
+
+/// Compute the result of the given Boolean `operator` applied to the two `ComplementBdd`s.
+///
+/// Returns the resulting `ComplementBdd` together with the number of tasks that had to be
+/// expanded to compute it.
+///
+/// The algorithm is the same as the standard `apply`: it uses the leaky `TaskCache`, the
+/// `NodeCache` and an explicit task stack, and only checks whether the caches need to grow once
+/// per block of iterations. To fit into these structures, every edge is encoded as a node index
+/// (see `encode`) and the complement flag of the low edge of a result node is stored in the
+/// `NodeCache::LINK_FLAG` bit of its low link.
+pub fn apply<Op: BooleanOperator>(
+    left_bdd: &ComplementBdd,
+    right_bdd: &ComplementBdd,
+    operator: Op,
+) -> (ComplementBdd, usize) {
+    let config = ApplyConfig::default();
+    // Every node of the left BDD can appear in two encoded edges.
+    let mut task_cache = TaskCache::new(2 * left_bdd.get_size());
+    let mut node_cache = NodeCache::new(max(left_bdd.get_size() / 2, 2));
+    let height_limit = left_bdd.get_height() + right_bdd.get_height();
+    let mut stack = UnsafeStack::new(2 * height_limit.into_index() + 1);
+    let mut task_count = 0;
+
+    let root_task = (encode(left_bdd.get_root()), encode(right_bdd.get_root()));
+    stack.push(ApplyTask::new(0, root_task));
+    let root_result;
+
+    'main: loop {
+        let base_iterations = max(
+            left_bdd.get_size() >> config.check_interval_shift,
+            config.min_check_interval,
+        );
+        let node_iterations = node_cache.ensure_capacity(base_iterations);
+        let task_iterations = max(task_cache.grow_if_necessary(), base_iterations);
+        for _ in 0..min(node_iterations, task_iterations) {
+            let top = stack.peek();
+            let top_offset: usize = top.get_offset().into();
+
+            let mut result;
+            if top.is_not_decoded() {
+                top.mark_as_decoded();
+
+                // Constant edges are encoded as `NodeIndex::ZERO` and `NodeIndex::ONE`,
+                // so the operator can resolve them directly.
+                let (left, right) = top.task;
+                result = operator.resolve(left, right);
+                if result.is_undefined() {
+                    let (cached, slot) = task_cache.read(top.task);
+                    if !cached.is_undefined() {
+                        result = cached;
+                    } else {
+                        top.task_cache_slot = slot;
+                        task_count += 1;
+
+                        let (left, right) = (decode(left), decode(right));
+                        let variable = min(get_variable(left_bdd, left), get_variable(right_bdd, right));
+                        let (left_low, left_high) = cofactors(left_bdd, left, variable);
+                        let (right_low, right_high) = cofactors(right_bdd, right, variable);
+                        top.variable = variable;
+                        stack.push(ApplyTask::new(1, (encode(left_high), encode(right_high))));
+                        stack.push(ApplyTask::new(2, (encode(left_low), encode(right_low))));
+                    }
+                }
+            } else {
+                // Task is decoded, we have to create a new node for it.
+                let (result_low, result_high) = (top.results[1], top.results[0]);
+                if result_low == result_high {
+                    result = result_low;
+                } else {
+                    let (low, high) = (decode(result_low), decode(result_high));
+                    result = ensure_node(&mut node_cache, top.variable, low, high);
+                }
+                task_cache.write(top.task_cache_slot, top.task, result);
+            }
+
+            if !result.is_undefined() {
+                stack.pop();
+                if stack.is_empty() {
+                    root_result = result;
+                    break 'main;
+                }
+                let parent = stack.peek_at(top_offset);
+                parent.results[top_offset - 1] = result;
+            }
+        }
+    }
+
+    (export(&node_cache, decode(root_result)), task_count)
+}
+
+/// Encode an edge as a node index: regular edges are stored as `2 * index + 1`, complemented
+/// edges as `2 * index`. The constant edges are therefore `NodeIndex::ZERO` and `NodeIndex::ONE`.
+///
+/// The same encoding is used for edges of the input BDDs (in tasks) and for edges into the node
+/// cache (in results), where the terminal node is stored at index `0`.
+#[inline]
+fn encode(edge: ComplementEdge) -> NodeIndex {
+    let index = u64::from(edge.get_index());
+    NodeIndex::from(2 * index + u64::from(!edge.is_complemented()))
+}
+
+#[inline]
+fn decode(index: NodeIndex) -> ComplementEdge {
+    let index = u64::from(index);
+    ComplementEdge::new(NodeIndex::from(index >> 1), index & 1 == 0)
+}
+
+/// Find or create the node `if variable { high } else { low }` in the `node_cache` and return
+/// the encoded edge that represents it. The node is normalized such that its high edge
+/// is regular.
+fn ensure_node(
+    node_cache: &mut NodeCache,
+    variable: Variable,
+    low: ComplementEdge,
+    high: ComplementEdge,
+) -> NodeIndex {
+    let is_complemented = high.is_complemented();
+    let low = low.complement_if(is_complemented);
+    let mut low_link = u64::from(low.get_index());
+    if low.is_complemented() {
+        low_link |= NodeCache::LINK_FLAG;
+    }
+    let node = Node::pack(variable, NodeIndex::from(low_link), high.get_index());
+    let mut cached = node_cache.ensure(&node);
+    while let Err(slot) = cached {
+        cached = node_cache.ensure_at(&node, slot);
+    }
+    encode(ComplementEdge::new(cached.unwrap(), is_complemented))
+}
+
+/// Create a `ComplementBdd` from the nodes of the `node_cache` reachable from `root`.
+/// The second terminal of the cache is never referenced.
+fn export(node_cache: &NodeCache, root: ComplementEdge) -> ComplementBdd {
+    let nodes: Vec<ComplementNode> = node_cache
+        .export_nodes()
+        .iter()
+        .map(|node| {
+            if node.is_terminal() {
+                ComplementNode::TERMINAL
+            } else {
+                let (variable, low_link, high) = node.unpack();
+                let low_link = u64::from(low_link);
+                let low_index = NodeIndex::from(low_link & !NodeCache::LINK_FLAG);
+                let low = ComplementEdge::new(low_index, low_link & NodeCache::LINK_FLAG != 0);
+                let high = ComplementEdge::new(high, false);
+                ComplementNode::pack(variable, low, high)
+            }
+        })
+        .collect();
+    ComplementBdd::from_reachable_nodes(&nodes, root)
+}
+
+#[inline]
+fn get_variable(bdd: &ComplementBdd, edge: ComplementEdge) -> Variable {
+    bdd.get_node(edge.get_index()).get_variable()
+}
+
+/// The `(low, high)` cofactors of the function given by the `edge` w.r.t. `variable`.
+#[inline]
+fn cofactors(bdd: &ComplementBdd, edge: ComplementEdge, variable: Variable) -> (ComplementEdge, ComplementEdge) {
+    let (node_variable, low, high) = bdd.get_node(edge.get_index()).unpack();
+    if node_variable == variable {
+        let is_complemented = edge.is_complemented();
+        (low.complement_if(is_complemented), high.complement_if(is_complemented))
+    } else {
+        (edge, edge)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::apply;
+    use crate::v4::apply::{apply as bdd_apply, And, BooleanOperator, Iff, Imp, Or, TruthTable, Xor};
+    use crate::v4::complement::ComplementBdd;
+    use crate::v4::core::{Bdd, Variable, VariableSet};
+    use crate::v4::test_util::nodes;
+    use crate::verify::{FunctionTable, Lcg};
+
+    fn check_operator<Op: BooleanOperator>(operator: Op) {
+        let mut rng = Lcg::new(17);
+        for density in &[5, 30, 50, 95] {
+            let left = FunctionTable::random(8, *density, &mut rng).to_bdd();
+            let right = FunctionTable::random(8, *density, &mut rng).to_bdd();
+            let (expected, _) = bdd_apply(&left, &right, operator);
+            let left = ComplementBdd::from(&left);
+            let right = ComplementBdd::from(&right);
+            for (l, r) in &[(left.clone(), right.clone()), (left.not(), right.clone())] {
+                let (result, _) = apply(l, r, operator);
+                let (expected, _) = bdd_apply(&Bdd::from(l), &Bdd::from(r), operator);
+                assert_eq!(nodes(&expected), nodes(&Bdd::from(&result)));
+                assert_eq!(expected.get_height(), result.get_height());
+            }
+            let (result, _) = apply(&left, &right, operator);
+            assert_eq!(nodes(&expected), nodes(&Bdd::from(&result)));
+        }
+    }
+
+    #[test]
+    pub fn complement_apply_matches_apply() {
+        check_operator(And);
+        check_operator(Or);
+        check_operator(Xor);
+        check_operator(Iff);
+        check_operator(Imp);
+        check_operator(TruthTable::from_fn(|l, r| !(l && r)));
+    }
+
+    #[test]
+    pub fn complement_apply_xor_chain() {
+        // Parity of n variables needs 2n - 1 decision nodes, but only n with complement edges.
+        let mut bdd = Bdd::new_zero();
+        let mut complement = ComplementBdd::new_zero();
+        for i in 0..10 {
+            bdd = bdd_apply(&bdd, &Bdd::new_literal(Variable::from(i), true), Xor).0;
+            let literal = ComplementBdd::new_literal(Variable::from(i), true);
+            complement = apply(&complement, &literal, Xor).0;
+        }
+        assert_eq!(2 * 10 - 1 + 2, bdd.get_size());
+        assert_eq!(10 + 1, complement.get_size());
+        assert_eq!(nodes(&bdd), nodes(&Bdd::from(&complement)));
+    }
+
+    #[test]
+    pub fn complement_apply_allocated_variables() {
+        // `VariableSet` allocates variables downwards from `u32::MAX - 1`. The parity chain
+        // normalizes every node to a complemented low edge.
+        let mut variables = VariableSet::new();
+        let mut bdd = Bdd::new_zero();
+        let mut complement = ComplementBdd::new_zero();
+        for name in &["a", "b", "c", "d"] {
+            let variable = variables.allocate(name).unwrap();
+            bdd = bdd_apply(&bdd, &Bdd::new_literal(variable, true), Xor).0;
+            let literal = ComplementBdd::new_literal(variable, true);
+            complement = apply(&complement, &literal, Xor).0;
+            let (expected, _) = bdd_apply(&bdd, &Bdd::new_literal(variable, false), And);
+            let literal = ComplementBdd::new_literal(variable, false);
+            let (result, _) = apply(&complement, &literal, And);
+            assert_eq!(nodes(&expected), nodes(&Bdd::from(&result)));
+        }
+        assert_eq!(4 + 1, complement.get_size());
+        assert_eq!(nodes(&bdd), nodes(&Bdd::from(&complement)));
+    }
+}
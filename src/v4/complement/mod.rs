@@ -0,0 +1,21 @@
+//! A variant of the BDD representation which uses *complemented edges*.
+//!
+//! In this representation, there is only one terminal node (`1`), and every edge can carry
+//! a complement flag which negates the function of the node it points to. As a consequence,
+//! negation is a constant-time operation, and a function and its negation share all nodes.
+//! To keep the representation canonical, the high edge of a decision node is never complemented.
+//!
+//! The complement flag is stored in the most significant bit of the node index, i.e. in the
+//! metadata space reserved by `NodeIndex`. To avoid leaking the flag into plain indices,
+//! complemented edges always use the `ComplementEdge` wrapper.
+
+mod _edge;
+mod _node;
+mod _bdd;
+mod _node_cache;
+mod apply;
+
+pub use _edge::ComplementEdge;
+pub use _node::ComplementNode;
+pub use _bdd::ComplementBdd;
+pub use apply::apply;
@@ -1,7 +1,7 @@
-use super::{Node, Variable, NodeIndex};
+use super::{CancellationToken, Cancelled, Node, Variable, NodeIndex};
 use crate::{FromIndex, IntoIndex};
+use crate::convert::{ConversionError, RawBdd};
 use std::cmp::max;
-use std::collections::VecDeque;
 use std::iter::Map;
 use std::ops::Range;
 use std::convert::TryFrom;
@@ -61,7 +61,7 @@ impl Bdd {
         Bdd { height, nodes }
     }
 
-    /// Create a BDD from a vector of nodes. The height will be computed using a BFS search.
+    /// Create a BDD from a vector of nodes. The height will be computed using a DFS search.
     ///
     /// *Panics:* The nodes must form a valid BDD in terms of `Bdd::is_valid_bdd`.
     pub fn from_nodes(nodes: Vec<Node>) -> Bdd {
@@ -87,6 +87,19 @@ impl Bdd {
         }
     }
 
+    /// Create a BDD representing the literal `variable = value`.
+    pub fn new_literal(variable: Variable, value: bool) -> Bdd {
+        let node = if value {
+            Node::pack(variable, NodeIndex::ZERO, NodeIndex::ONE)
+        } else {
+            Node::pack(variable, NodeIndex::ONE, NodeIndex::ZERO)
+        };
+        Bdd {
+            height: 2,
+            nodes: vec![Node::ZERO, Node::ONE, node]
+        }
+    }
+
     /// Upper bound on the height of the BDD graph.
     #[inline]
     pub fn get_height(&self) -> u32 {
@@ -123,12 +136,33 @@ impl Bdd {
         &self.nodes[index.into_index()]
     }
 
+    /// All nodes of the BDD in their storage order (terminals first, root last).
+    #[inline]
+    pub(crate) fn get_nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
     /// Get a reference to a `Node` using the given `index` without checking bounds.
     #[inline]
     pub unsafe fn get_node_unchecked(&self, index: NodeIndex) -> &Node {
         unsafe { self.nodes.get_unchecked(index.into_index()) }
     }
 
+    /// Hint the CPU to load the `Node` with the given `index` into cache.
+    ///
+    /// Prefetching never faults, so this is safe even for invalid indices. On platforms other
+    /// than `x86_64`, this does nothing.
+    #[inline]
+    pub fn prefetch(&self, index: NodeIndex) {
+        #[cfg(target_arch = "x86_64")]
+        unsafe {
+            let pointer = self.nodes.as_ptr().wrapping_add(index.into_index());
+            std::arch::x86_64::_mm_prefetch::<3>(pointer as *const i8);
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        let _ = index;
+    }
+
     /// Create an iterator over all node indices of this BDD.
     #[inline]
     pub fn iter_indices(&self) -> NodeIndexIterator {
@@ -155,8 +189,46 @@ impl Bdd {
 
 }
 
+/// Basic logical operations that do not require the `apply` algorithm.
+impl Bdd {
+
+    /// Create a BDD representing the negation of this BDD.
+    ///
+    /// Since the terminal nodes are always at the same position, this only requires
+    /// swapping the references to the two terminals.
+    pub fn not(&self) -> Bdd {
+        if self.is_zero() {
+            return Bdd::new_one();
+        }
+        if self.is_one() {
+            return Bdd::new_zero();
+        }
+        let flip = |index: NodeIndex| -> NodeIndex {
+            if index.is_zero() {
+                NodeIndex::ONE
+            } else if index.is_one() {
+                NodeIndex::ZERO
+            } else {
+                index
+            }
+        };
+        let nodes = self.nodes.iter().map(|node| {
+            if node.is_terminal() {
+                node.clone()
+            } else {
+                let (variable, low, high) = node.unpack();
+                Node::pack(variable, flip(low), flip(high))
+            }
+        }).collect();
+        Bdd { height: self.height, nodes }
+    }
+
+}
+
 /// Some useful validation and normalization methods.
 impl Bdd {
+    /// The number of search steps after which the cancellation token is polled during sorting.
+    const CANCELLATION_CHECK_INTERVAL: u64 = 1 << 14;
 
     /// Dynamically verify that the given slice of nodes can be safely interpreted as a BDD.
     ///
@@ -206,7 +278,7 @@ impl Bdd {
     }
 
 
-    /// Update the height value of this BDD with a true height obtained using a BFS search.
+    /// Update the height value of this BDD with a true height obtained using a DFS search.
     pub fn recompute_height(&mut self) {
         // Constant BDD has height 0.
         if self.is_constant() {
@@ -214,44 +286,36 @@ impl Bdd {
             return;
         }
 
-        // Run a BFS through all nodes, determining the exact maximal height for each of them.
+        // Run a post-order DFS through all reachable nodes. The height of a node is the number
+        // of nodes on the longest path from that node to a terminal (terminal included).
         let mut height: Vec<u32> = vec![0; self.nodes.len()];
-        let mut visited: Vec<bool> = vec![false; self.nodes.len()];
-        let mut queue: VecDeque<NodeIndex> = VecDeque::with_capacity(128);
-
-        queue.push_back(self.get_root_index());
-        height[self.get_root_index().into_index()] = 1;
+        let mut stack: Vec<(NodeIndex, bool)> = Vec::with_capacity(128);
+        stack.push((self.get_root_index(), false));
 
-        while let Some(i_node) = queue.pop_front() {
+        while let Some((i_node, expanded)) = stack.pop() {
             let node = self.get_node(i_node);
-            let node_height = height[i_node.into_index()];
-
-            let low_link = node.get_low_link().into_index();
-            let high_link = node.get_high_link().into_index();
-            height[low_link] = max(height[low_link], node_height + 1);
-            height[high_link] = max(height[high_link], node_height + 1);
-
-            // If visited is true, the node is already somewhere in the queue and will be
-            // popped eventually.
-            if !visited[low_link] {
-                visited[low_link] = true;
-                queue.push_back(node.get_low_link());
+            if node.is_terminal() {
+                height[i_node.into_index()] = 1;
+                continue;
             }
-            if !visited[high_link] {
-                visited[high_link] = true;
-                queue.push_back(node.get_high_link());
+            let low_link = node.get_low_link();
+            let high_link = node.get_high_link();
+            if expanded {
+                let low_height = height[low_link.into_index()];
+                let high_height = height[high_link.into_index()];
+                height[i_node.into_index()] = max(low_height, high_height) + 1;
+            } else if height[i_node.into_index()] == 0 {
+                stack.push((i_node, true));
+                if height[high_link.into_index()] == 0 {
+                    stack.push((high_link, false));
+                }
+                if height[low_link.into_index()] == 0 {
+                    stack.push((low_link, false));
+                }
             }
         }
 
-        // The final height is the maximum among the terminal nodes:
-        self.height = 0;
-        for node in self.iter_indices() {
-            if !self.nodes[node.into_index()].is_terminal() {
-                return;
-            }
-
-            self.height = max(self.height, height[node.into_index()]);
-        }
+        self.height = height[self.get_root_index().into_index()];
     }
 
     /// Create a copy of the BDD by reordering the nodes based on the provided shuffle vector.
@@ -285,10 +349,82 @@ impl Bdd {
         }
     }
 
+    /// Create a new `Bdd` from the nodes of the given slice that are reachable from `root`.
+    ///
+    /// This is used to extract a result out of a larger node storage (e.g. a node cache or
+    /// a shared pool). The nodes are copied in DFS pre-order and the slice must follow the usual
+    /// conventions: terminals are at indices `0` and `1` and every link is valid in the slice.
+    pub(crate) fn from_reachable_nodes(nodes: &[Node], root: NodeIndex) -> Bdd {
+        Bdd::from_reachable(nodes.len(), root, |index| nodes[index.into_index()].clone())
+    }
+
+    /// The same as `Bdd::from_reachable_nodes`, but the `node_count` nodes of the storage are
+    /// read through `get_node`, such that they do not have to be copied into a slice first.
+    pub(crate) fn from_reachable<F>(node_count: usize, root: NodeIndex, get_node: F) -> Bdd
+    where
+        F: Fn(NodeIndex) -> Node,
+    {
+        if root.is_zero() {
+            return Bdd::new_zero();
+        }
+        if root.is_one() {
+            return Bdd::new_one();
+        }
+
+        // Collect the reachable decision nodes in DFS pre-order.
+        let mut new_index = vec![NodeIndex::UNDEFINED; node_count];
+        let mut preorder: Vec<NodeIndex> = Vec::new();
+        let mut search_stack: Vec<NodeIndex> = vec![root];
+        while let Some(task) = search_stack.pop() {
+            if task.is_zero() || task.is_one() || !new_index[task.into_index()].is_undefined() {
+                continue;
+            }
+            new_index[task.into_index()] = NodeIndex::from_index(preorder.len());
+            preorder.push(task);
+
+            let node = get_node(task);
+            search_stack.push(node.get_high_link());
+            search_stack.push(node.get_low_link());
+        }
+
+        // The root gets the last index, and the rest of the nodes is placed in descending order.
+        let remap = |index: NodeIndex| -> NodeIndex {
+            if index.is_zero() || index.is_one() {
+                index
+            } else {
+                let order = new_index[index.into_index()].into_index();
+                NodeIndex::from_index(preorder.len() + 1 - order)
+            }
+        };
+        let mut new_nodes = vec![Node::ZERO; preorder.len() + 2];
+        new_nodes[1] = Node::ONE;
+        for old_index in &preorder {
+            let (variable, low, high) = get_node(*old_index).unpack();
+            new_nodes[remap(*old_index).into_index()] = Node::pack(variable, remap(low), remap(high));
+        }
+
+        let mut bdd = unsafe { Bdd::from_raw_parts(u32::MAX, new_nodes) };
+        bdd.recompute_height();
+        bdd
+    }
+
     /// Create a copy of this `Bdd` that is sorted based on the DFS pre-order.
     pub fn sort_preorder(&self) -> Bdd {
+        match self.sort_preorder_in(None) {
+            Ok(result) => result,
+            Err(Cancelled) => unreachable!("Sorting without a token cannot be cancelled."),
+        }
+    }
+
+    /// The same as `Bdd::sort_preorder`, but stops with an error once the `cancellation`
+    /// token is cancelled.
+    pub fn sort_preorder_cancellable(&self, cancellation: &CancellationToken) -> Result<Bdd, Cancelled> {
+        self.sort_preorder_in(Some(cancellation))
+    }
+
+    fn sort_preorder_in(&self, cancellation: Option<&CancellationToken>) -> Result<Bdd, Cancelled> {
         if self.is_constant() { // Skip for trivial BDDs.
-            return self.clone();
+            return Ok(self.clone());
         }
 
         let mut shuffle_map = vec![NodeIndex::UNDEFINED; self.nodes.len()];
@@ -304,7 +440,14 @@ impl Bdd {
 
         // Populate `id_map` based on DFS preorder.
         let mut next_free_index = self.nodes.len() - 1;
+        let mut iterations: u64 = 0;
         while let Some(task) = search_stack.pop() {
+            iterations += 1;
+            if iterations.is_multiple_of(Self::CANCELLATION_CHECK_INTERVAL) {
+                if let Some(cancellation) = cancellation {
+                    cancellation.check()?;
+                }
+            }
             let task_id = shuffle_map[task.into_index()];
             if task_id.is_undefined() {
                 shuffle_map[task.into_index()] = NodeIndex::from_index(next_free_index);
@@ -319,13 +462,26 @@ impl Bdd {
         // Every node should have been assigned a new index except for the terminals,
         // which we fixed in the beginning.
         debug_assert_eq!(terminals_count - 1, next_free_index);
-        unsafe { self.shuffle_unchecked(&shuffle_map) }
+        Ok(unsafe { self.shuffle_unchecked(&shuffle_map) })
     }
 
     /// Create a copy of this `Bdd` that is sorted based on the DFS post-order.
     pub fn sort_postorder(&self) -> Bdd {
+        match self.sort_postorder_in(None) {
+            Ok(result) => result,
+            Err(Cancelled) => unreachable!("Sorting without a token cannot be cancelled."),
+        }
+    }
+
+    /// The same as `Bdd::sort_postorder`, but stops with an error once the `cancellation`
+    /// token is cancelled.
+    pub fn sort_postorder_cancellable(&self, cancellation: &CancellationToken) -> Result<Bdd, Cancelled> {
+        self.sort_postorder_in(Some(cancellation))
+    }
+
+    fn sort_postorder_in(&self, cancellation: Option<&CancellationToken>) -> Result<Bdd, Cancelled> {
         if self.is_constant() { // Skip for trivial BDDs.
-            return self.clone();
+            return Ok(self.clone());
         }
 
         let mut shuffle_map = vec![NodeIndex::UNDEFINED; self.nodes.len()];
@@ -341,7 +497,14 @@ impl Bdd {
 
         // First non-terminal index that can be assigned:
         let mut next_free_index = terminals_count;
+        let mut iterations: u64 = 0;
         while let Some((task, expended)) = search_stack.pop() {
+            iterations += 1;
+            if iterations.is_multiple_of(Self::CANCELLATION_CHECK_INTERVAL) {
+                if let Some(cancellation) = cancellation {
+                    cancellation.check()?;
+                }
+            }
             let task_id = shuffle_map[task.into_index()];
             if expended {
                 // All children are exported and the task can get its ID now:
@@ -357,11 +520,32 @@ impl Bdd {
         }
 
         assert_eq!(next_free_index, self.nodes.len());
-        unsafe { self.shuffle_unchecked(&shuffle_map) }
+        Ok(unsafe { self.shuffle_unchecked(&shuffle_map) })
     }
 
 }
 
+impl Bdd {
+    /// **(internal)** A representation-independent copy of this `Bdd` (see `crate::convert`).
+    pub(crate) fn to_raw_bdd(&self) -> RawBdd {
+        RawBdd::new(0, self.nodes.len(), |index| {
+            let (variable, low, high) = self.nodes[index].unpack();
+            (u32::from(variable), u64::from(low), u64::from(high))
+        })
+    }
+
+    /// **(internal)** Create a `Bdd` from its representation-independent copy.
+    pub(crate) fn from_raw_bdd(raw: &RawBdd) -> Result<Bdd, ConversionError> {
+        raw.check_limits(u32::MAX - 1, Node::MAX_LINK)?;
+        let nodes = raw.map_nodes(Node::ZERO, Node::ONE, |variable, low, high| {
+            Node::pack(Variable::from(variable), NodeIndex::from(low), NodeIndex::from(high))
+        });
+        let mut bdd = unsafe { Bdd::from_raw_parts(u32::MAX, nodes) };
+        bdd.recompute_height();
+        Ok(bdd)
+    }
+}
+
 /// Deserialization of a simple string format for sharing BDDs.
 impl TryFrom<&str> for Bdd {
     type Error = String;
@@ -401,6 +585,10 @@ impl TryFrom<&str> for Bdd {
                     right_pointer.unwrap()
                 ));
             };
+            // Larger pointers cannot be packed into a `Node`.
+            if u64::from(low_pointer) > Node::MAX_LINK || u64::from(high_pointer) > Node::MAX_LINK {
+                return Err(format!("Pointer out of range in `{}`.", node_string));
+            }
             nodes.push(Node::pack(variable, low_pointer, high_pointer));
         }
         // Replace terminals because the files currently use the old format:
@@ -444,4 +632,13 @@ mod test {
         assert_eq!(postorder.nodes, preorder.sort_postorder().nodes);
     }
 
+    #[test]
+    pub fn string_pointer_out_of_range() {
+        let bdd = Bdd::try_from("0,0,0|0,1,1|0,0,1").unwrap();
+        assert_eq!(3, bdd.get_size());
+        let error = Bdd::try_from("0,0,0|0,1,1|0,0,281474976710656").err();
+        assert_eq!(Some("Pointer out of range in `0,0,281474976710656`.".to_string()), error);
+        assert!(Bdd::try_from("0,0,0|0,1,1|0,18446744073709551615,1").is_err());
+    }
+
 }
\ No newline at end of file
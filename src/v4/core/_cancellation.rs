@@ -0,0 +1,125 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A token which can be used to stop a long-running operation.
+///
+/// The token is cancelled either explicitly using `CancellationToken::cancel` (possibly from
+/// another thread, since clones of the token share the same flag), or implicitly once its
+/// deadline passes. Long operations poll the token periodically (typically at the same points
+/// where they check whether their caches need to grow), so the operation can continue for
+/// a short while after the token is cancelled.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+/// The error returned by an operation which was stopped using a `CancellationToken`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Cancelled;
+
+impl CancellationToken {
+    /// Create a new token without a deadline.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Create a new token which is cancelled once the given `deadline` passes.
+    pub fn with_deadline(deadline: Instant) -> CancellationToken {
+        CancellationToken {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            deadline: Some(deadline),
+        }
+    }
+
+    /// Create a new token which is cancelled once the given `timeout` elapses.
+    pub fn with_timeout(timeout: Duration) -> CancellationToken {
+        CancellationToken::with_deadline(Instant::now() + timeout)
+    }
+
+    /// Cancel this token and all its clones.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// True if the token was cancelled or its deadline passed.
+    pub fn is_cancelled(&self) -> bool {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return true;
+        }
+        match self.deadline {
+            Some(deadline) => Instant::now() >= deadline,
+            None => false,
+        }
+    }
+
+    /// Returns `Err(Cancelled)` if the token is cancelled.
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Two tokens are equal when they are clones of each other.
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.cancelled, &other.cancelled) && self.deadline == other.deadline
+    }
+}
+
+impl Eq for CancellationToken {}
+
+impl Debug for CancellationToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.cancelled.load(Ordering::Relaxed))
+            .field("deadline", &self.deadline)
+            .finish()
+    }
+}
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Operation cancelled.")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CancellationToken, Cancelled};
+    use crate::v4::test_util::nodes;
+    use crate::verify::{FunctionTable, Lcg};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    pub fn cancellation_token() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert_eq!(token, clone);
+        assert_ne!(token, CancellationToken::new());
+        assert_eq!(Ok(()), clone.check());
+        token.cancel();
+        assert_eq!(Err(Cancelled), clone.check());
+
+        assert!(CancellationToken::with_deadline(Instant::now()).is_cancelled());
+        assert!(!CancellationToken::with_timeout(Duration::from_secs(3600)).is_cancelled());
+    }
+
+    #[test]
+    pub fn cancellable_sorting() {
+        let mut rng = Lcg::new(1);
+        let bdd = FunctionTable::random(18, 50, &mut rng).to_bdd();
+        let token = CancellationToken::new();
+        let sorted = bdd.sort_postorder_cancellable(&token).unwrap();
+        assert_eq!(nodes(&bdd.sort_postorder()), nodes(&sorted));
+        assert_eq!(nodes(&bdd), nodes(&bdd.sort_preorder_cancellable(&token).unwrap()));
+        token.cancel();
+        assert_eq!(Err(Cancelled), bdd.sort_preorder_cancellable(&token).map(|_| ()));
+        assert_eq!(Err(Cancelled), bdd.sort_postorder_cancellable(&token).map(|_| ()));
+    }
+}
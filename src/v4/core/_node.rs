@@ -1,5 +1,6 @@
 use super::Variable;
 use super::NodeIndex;
+use std::fmt::{Debug, Formatter};
 
 /// A collection of values which together describe a BDD decision node.
 ///
@@ -16,40 +17,95 @@ use super::NodeIndex;
 /// Also note that we assume a BDD is sorted in such a way that smaller variables are closer to the
 /// root. This means a `Variable::UNDEFINED` in the terminal node is always the correct termination
 /// of any growing sequence of variables that one may encounter on any path in the BDD.
-#[derive(Clone, Eq, PartialEq, Hash, Debug)]
-pub struct Node(Variable, NodeIndex, NodeIndex);
+///
+/// Internally, the node is packed into 128 bits: The 32-bit variable is split into two 16-bit
+/// halves which are stored in the lower bits of two 64-bit words, and the upper 48 bits of each
+/// word store the low and high link, respectively. Hence, node indices stored in a `Node` are
+/// limited to 48 bits (which is still enough for `2^48` nodes). Unpacking the values takes
+/// a bit of bit-manipulation, but the smaller nodes waste less memory and pollute the CPU
+/// caches less (see `PERF.md`).
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct Node(u64, u64);
 
 impl Node {
-    pub const ZERO: Node = Node(Variable::UNDEFINED, NodeIndex::ZERO, NodeIndex::ZERO);
-    pub const ONE: Node = Node(Variable::UNDEFINED, NodeIndex::ONE, NodeIndex::ONE);
+    pub const ZERO: Node = Node(Self::VARIABLE_MASK, Self::VARIABLE_MASK);
+    pub const ONE: Node = Node(
+        (1 << Self::LINK_SHIFT) | Self::VARIABLE_MASK,
+        (1 << Self::LINK_SHIFT) | Self::VARIABLE_MASK,
+    );
+
+    /// The number of bits reserved for the variable half in each word.
+    const LINK_SHIFT: u32 = 16;
+    const VARIABLE_MASK: u64 = (1 << Self::LINK_SHIFT) - 1;
+    /// The largest node index that can be stored in a `Node`.
+    pub const MAX_LINK: u64 = (1 << (64 - Self::LINK_SHIFT)) - 1;
 
     #[inline]
     pub fn pack(variable: Variable, low: NodeIndex, high: NodeIndex) -> Node {
-        Node(variable, low, high)
+        let (variable, low, high) = (u64::from(u32::from(variable)), u64::from(low), u64::from(high));
+        debug_assert!(low <= Self::MAX_LINK && high <= Self::MAX_LINK);
+        Node(
+            (low << Self::LINK_SHIFT) | (variable & Self::VARIABLE_MASK),
+            (high << Self::LINK_SHIFT) | (variable >> Self::LINK_SHIFT),
+        )
     }
 
     #[inline]
     pub fn unpack(&self) -> (Variable, NodeIndex, NodeIndex) {
-        (self.0, self.1, self.2)
+        (self.get_variable(), self.get_low_link(), self.get_high_link())
     }
 
     #[inline]
     pub fn is_terminal(&self) -> bool {
-        self.0.is_undefined()
+        self.get_variable().is_undefined()
     }
 
     #[inline]
     pub fn get_variable(&self) -> Variable {
-        self.0
+        let lower = self.0 & Self::VARIABLE_MASK;
+        let upper = (self.1 & Self::VARIABLE_MASK) << Self::LINK_SHIFT;
+        Variable::from((upper | lower) as u32)
     }
 
     #[inline]
     pub fn get_low_link(&self) -> NodeIndex {
-        self.1
+        NodeIndex::from(self.0 >> Self::LINK_SHIFT)
     }
 
     #[inline]
     pub fn get_high_link(&self) -> NodeIndex {
-        self.2
+        NodeIndex::from(self.1 >> Self::LINK_SHIFT)
+    }
+}
+
+/// Nodes are printed in their unpacked form.
+impl Debug for Node {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (variable, low, high) = self.unpack();
+        f.debug_tuple("Node").field(&variable).field(&low).field(&high).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Node;
+    use crate::v4::core::{NodeIndex, Variable};
+
+    #[test]
+    pub fn packed_node_layout() {
+        assert_eq!(16, std::mem::size_of::<Node>());
+        let max_link = NodeIndex::from(Node::MAX_LINK);
+        for variable in &[Variable::from(0), Variable::from(0x1234_5678), Variable::from(u32::MAX - 1)] {
+            for (low, high) in &[(NodeIndex::ZERO, NodeIndex::ONE), (max_link, NodeIndex::from(12345)), (NodeIndex::ONE, max_link)] {
+                let node = Node::pack(*variable, *low, *high);
+                assert_eq!((*variable, *low, *high), node.unpack());
+                assert!(!node.is_terminal());
+            }
+        }
+        assert_eq!((Variable::UNDEFINED, NodeIndex::ZERO, NodeIndex::ZERO), Node::ZERO.unpack());
+        assert_eq!((Variable::UNDEFINED, NodeIndex::ONE, NodeIndex::ONE), Node::ONE.unpack());
+        assert!(Node::ZERO.is_terminal() && Node::ONE.is_terminal());
+        assert_eq!(Node::ONE, Node::pack(Variable::UNDEFINED, NodeIndex::ONE, NodeIndex::ONE));
+        assert_eq!("Node(Variable(3), NodeIndex(0), NodeIndex(1))", format!("{:?}", Node::pack(Variable::from(3), NodeIndex::ZERO, NodeIndex::ONE)));
     }
-}
\ No newline at end of file
+}
@@ -7,6 +7,9 @@ use crate::{IntoIndex, FromIndex};
 /// `2^56` on any real computer in this century, so you can use the upper 8 bits for some
 /// metadata if you want to. Note that this is not checked anywhere, so always make sure you
 /// erase the metadata when interfacing with the BDD implementation (e.g. using a wrapper type).
+///
+/// Also note that a `Node` only stores the lower 48 bits of its links (see `Node::MAX_LINK`),
+/// so the metadata of indices that are saved into nodes must fit within these 48 bits.
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct NodeIndex(u64);
 
@@ -0,0 +1,167 @@
+use super::{Bdd, Variable};
+use crate::IntoIndex;
+use std::collections::HashMap;
+
+/// A registry of named BDD variables.
+///
+/// The variables are allocated in descending order, as recommended by the `Bdd` documentation.
+/// Since `u32::MAX` is reserved for `Variable::UNDEFINED`, the first variable is `2^32 - 2`,
+/// the second is `2^32 - 3`, and so on. Consequently, variables allocated later are placed
+/// higher in the variable ordering (closer to the root).
+///
+/// Since all variables of the set are at the end of the `u32` range, the root variable of
+/// a BDD over these variables determines how many variables can appear below the root,
+/// which gives a tight upper bound on the height of the BDD (see `VariableSet::height_bound`).
+#[derive(Clone, Default)]
+pub struct VariableSet {
+    names: Vec<String>,
+    variables: HashMap<String, Variable>,
+}
+
+impl VariableSet {
+    /// The variable which is allocated first.
+    const FIRST: u32 = u32::MAX - 1;
+
+    /// Create a new empty set of variables.
+    pub fn new() -> VariableSet {
+        VariableSet::default()
+    }
+
+    /// Create a new set with variables allocated for the given names, in the given order.
+    pub fn from_names(names: &[&str]) -> Result<VariableSet, String> {
+        let mut result = VariableSet::new();
+        for name in names {
+            result.allocate(name)?;
+        }
+        Ok(result)
+    }
+
+    /// Allocate a new variable with the given name.
+    ///
+    /// Returns an error if the name is already used.
+    pub fn allocate(&mut self, name: &str) -> Result<Variable, String> {
+        if self.variables.contains_key(name) {
+            return Err(format!("Variable `{}` already exists.", name));
+        }
+        if self.names.len() > Self::FIRST.into_index() {
+            return Err("No more variables can be allocated.".to_string());
+        }
+        let variable = Variable::from(Self::FIRST - self.names.len() as u32);
+        self.names.push(name.to_string());
+        self.variables.insert(name.to_string(), variable);
+        Ok(variable)
+    }
+
+    /// The number of allocated variables.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// True if the `variable` was allocated by this set.
+    pub fn contains(&self, variable: Variable) -> bool {
+        self.allocation_index(variable).is_some()
+    }
+
+    /// Find the variable with the given `name`.
+    pub fn find(&self, name: &str) -> Option<Variable> {
+        self.variables.get(name).cloned()
+    }
+
+    /// Find the name of the given `variable`.
+    pub fn name(&self, variable: Variable) -> Option<&str> {
+        self.allocation_index(variable).map(|i| self.names[i].as_str())
+    }
+
+    /// All allocated variables, in the order in which they appear in a BDD (root first).
+    pub fn variables(&self) -> Vec<Variable> {
+        (0..self.names.len())
+            .rev()
+            .map(|i| Variable::from(Self::FIRST - i as u32))
+            .collect()
+    }
+
+    /// Create a BDD representing the literal `variable = value`.
+    ///
+    /// *Panics:* The variable must be allocated by this set.
+    pub fn mk_literal(&self, variable: Variable, value: bool) -> Bdd {
+        assert!(self.contains(variable), "Unknown variable {:?}.", variable);
+        Bdd::new_literal(variable, value)
+    }
+
+    /// Create a BDD representing the literal `name = value`.
+    pub fn mk_named_literal(&self, name: &str, value: bool) -> Result<Bdd, String> {
+        match self.find(name) {
+            Some(variable) => Ok(Bdd::new_literal(variable, value)),
+            None => Err(format!("Unknown variable `{}`.", name)),
+        }
+    }
+
+    /// An upper bound on the height of a BDD which only uses variables of this set.
+    ///
+    /// Every path starting in the root can only visit the root variable, the variables
+    /// allocated before it, and the terminal node. The result is never larger than the
+    /// height stored in the `bdd` itself.
+    pub fn height_bound(&self, bdd: &Bdd) -> u32 {
+        if bdd.is_constant() {
+            return 0;
+        }
+        let root = u32::from(bdd.get_root_node().get_variable());
+        let bound = (u32::MAX - root).saturating_add(1);
+        bound.min(bdd.get_height())
+    }
+
+    /// The position of the variable in the allocation order.
+    fn allocation_index(&self, variable: Variable) -> Option<usize> {
+        let variable = u32::from(variable);
+        if variable > Self::FIRST {
+            return None;
+        }
+        let index = (Self::FIRST - variable).into_index();
+        if index < self.names.len() {
+            Some(index)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::VariableSet;
+    use crate::v4::apply::{apply, And, Or};
+    use crate::v4::core::Variable;
+
+    #[test]
+    pub fn variable_set_allocation() {
+        let mut set = VariableSet::from_names(&["a", "b"]).unwrap();
+        let c = set.allocate("c").unwrap();
+        assert!(set.allocate("a").is_err());
+        assert_eq!(3, set.len());
+        assert_eq!(Variable::from(u32::MAX - 1), set.find("a").unwrap());
+        assert_eq!(Variable::from(u32::MAX - 3), c);
+        assert_eq!(Some("c"), set.name(c));
+        assert_eq!(None, set.name(Variable::from(5)));
+        assert_eq!(None, set.name(Variable::UNDEFINED));
+        assert_eq!(vec![c, set.find("b").unwrap(), set.find("a").unwrap()], set.variables());
+        assert!(set.mk_named_literal("d", true).is_err());
+    }
+
+    #[test]
+    pub fn variable_set_height_bound() {
+        let set = VariableSet::from_names(&["a", "b", "c", "d"]).unwrap();
+        let a = set.mk_named_literal("a", true).unwrap();
+        let c = set.mk_named_literal("c", false).unwrap();
+        let d = set.mk_literal(set.find("d").unwrap(), true);
+        assert_eq!(2, set.height_bound(&a));
+        let (ac, _) = apply(&a, &c, And);
+        assert_eq!(ac.get_height(), set.height_bound(&ac));
+        let (acd, _) = apply(&ac, &d, Or);
+        assert_eq!(4, acd.get_height());
+        assert_eq!(4, set.height_bound(&acd));
+        assert_eq!(0, set.height_bound(&apply(&a, &a.not(), And).0));
+    }
+}
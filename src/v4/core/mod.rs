@@ -3,8 +3,12 @@ mod _variable;
 mod _node_index;
 mod _node;
 mod _bdd;
+mod _variable_set;
+mod _cancellation;
 
 pub use _variable::Variable;
 pub use _node_index::NodeIndex;
 pub use _node::Node;
-pub use _bdd::Bdd;
\ No newline at end of file
+pub use _bdd::Bdd;
+pub use _variable_set::VariableSet;
+pub use _cancellation::{CancellationToken, Cancelled};
\ No newline at end of file
@@ -0,0 +1,89 @@
+use super::super::core::{Bdd, NodeIndex, Variable};
+
+/// A conjunction of literals, i.e. a partial valuation of variables. The literals are sorted
+/// by their variables, and the variables which do not appear in the cube can have any value.
+pub type Cube = Vec<(Variable, bool)>;
+
+/// Iterates over all paths from the root of a `Bdd` to the `1` terminal, represented as cubes.
+///
+/// Since every two paths differ in the value of the variable where they split, the cubes
+/// are pairwise disjoint, and together they cover exactly the satisfying valuations of the `Bdd`.
+/// The cubes are produced in DFS order, exploring the low edge first.
+pub struct CubeIterator<'a> {
+    bdd: &'a Bdd,
+    path: Cube,
+    stack: Vec<CubeTask>,
+}
+
+/// A node which still has to be explored, together with the length of the path to its parent
+/// and the literal on the edge from the parent (if any).
+type CubeTask = (NodeIndex, usize, Option<(Variable, bool)>);
+
+impl Bdd {
+    /// Iterate over the disjoint cubes which together cover this `Bdd` (see `CubeIterator`).
+    pub fn cubes(&self) -> CubeIterator<'_> {
+        CubeIterator {
+            bdd: self,
+            path: Vec::new(),
+            stack: vec![(self.get_root_index(), 0, None)],
+        }
+    }
+}
+
+impl Iterator for CubeIterator<'_> {
+    type Item = Cube;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((index, depth, literal)) = self.stack.pop() {
+            // Restore the path to the state in which the parent of this node was explored.
+            self.path.truncate(depth);
+            if let Some(literal) = literal {
+                self.path.push(literal);
+            }
+            if index.is_one() {
+                return Some(self.path.clone());
+            }
+            if index.is_zero() {
+                continue;
+            }
+            let (variable, low, high) = self.bdd.get_node(index).unpack();
+            let depth = self.path.len();
+            self.stack.push((high, depth, Some((variable, true))));
+            self.stack.push((low, depth, Some((variable, false))));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::v4::core::{Bdd, Variable};
+    use crate::verify::{FunctionTable, Lcg};
+
+    #[test]
+    pub fn cubes_cover_bdd() {
+        let mut rng = Lcg::new(8);
+        for density in &[0, 20, 50, 100] {
+            let table = FunctionTable::random(6, *density, &mut rng);
+            let bdd = table.to_bdd();
+            let mut covered = vec![0; 64];
+            for cube in bdd.cubes() {
+                assert!(cube.windows(2).all(|pair| pair[0].0 < pair[1].0));
+                for (row, count) in covered.iter_mut().enumerate() {
+                    let matches = cube.iter().all(|(variable, value)| {
+                        let bit = (row >> (5 - u32::from(*variable))) & 1 == 1;
+                        bit == *value
+                    });
+                    if matches {
+                        *count += 1;
+                    }
+                }
+            }
+            // Every satisfying row is covered exactly once, the rest not at all.
+            let expected: Vec<i32> = (0..64).map(|row| i32::from(table.get(row))).collect();
+            assert_eq!(expected, covered);
+        }
+        assert_eq!(0, Bdd::new_zero().cubes().count());
+        assert_eq!(vec![Vec::<(Variable, bool)>::new()], Bdd::new_one().cubes().collect::<Vec<_>>());
+    }
+}
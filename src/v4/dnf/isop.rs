@@ -0,0 +1,328 @@
+use super::super::apply::{
+    apply_with_config, And, AndNot, ApplyConfig, ApplyError, BooleanOperator, Or,
+};
+use super::super::core::{Bdd, CancellationToken, Cancelled, Variable};
+use super::Cube;
+use fxhash::{FxHashMap, FxHasher};
+use std::cmp::min;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+
+/// The reasons why `isop` can fail.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IsopError {
+    /// The `lower` bound does not imply the `upper` bound.
+    BoundsNotImplied,
+    /// The `cancellation` token was cancelled.
+    Cancelled,
+}
+
+impl Display for IsopError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IsopError::BoundsNotImplied => {
+                write!(f, "The lower bound of ISOP must imply the upper bound.")
+            }
+            IsopError::Cancelled => write!(f, "{}", Cancelled),
+        }
+    }
+}
+
+impl From<Cancelled> for IsopError {
+    fn from(_: Cancelled) -> Self {
+        IsopError::Cancelled
+    }
+}
+
+/// The computed table of `isop_recursive`.
+///
+/// All intermediate BDDs are exported in DFS pre-order, hence equal bounds have equal node
+/// vectors. Every distinct bound is interned once (grouped by the hash of its nodes) and
+/// the results are indexed by the ids of the interned `lower` and `upper` bound.
+#[derive(Default)]
+struct IsopCache {
+    bounds: FxHashMap<u64, Vec<(Bdd, usize)>>,
+    bound_count: usize,
+    results: FxHashMap<(usize, usize), (Vec<Cube>, Bdd)>,
+}
+
+impl IsopCache {
+    fn get(&self, lower: &Bdd, upper: &Bdd) -> Option<&(Vec<Cube>, Bdd)> {
+        let key = (self.find(lower)?, self.find(upper)?);
+        self.results.get(&key)
+    }
+
+    fn insert(&mut self, lower: &Bdd, upper: &Bdd, result: (Vec<Cube>, Bdd)) {
+        let key = (self.intern(lower), self.intern(upper));
+        self.results.insert(key, result);
+    }
+
+    /// The id of the interned `bdd`, if it has been interned before.
+    fn find(&self, bdd: &Bdd) -> Option<usize> {
+        self.bounds
+            .get(&Self::hash(bdd))?
+            .iter()
+            .find(|(interned, _)| interned.get_nodes() == bdd.get_nodes())
+            .map(|(_, id)| *id)
+    }
+
+    fn intern(&mut self, bdd: &Bdd) -> usize {
+        if let Some(id) = self.find(bdd) {
+            return id;
+        }
+        let id = self.bound_count;
+        self.bound_count += 1;
+        self.bounds.entry(Self::hash(bdd)).or_default().push((bdd.clone(), id));
+        id
+    }
+
+    fn hash(bdd: &Bdd) -> u64 {
+        let mut hasher = FxHasher::default();
+        bdd.get_nodes().hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// An irredundant sum-of-products cover of an (incompletely specified) Boolean function.
+#[derive(Clone)]
+pub struct Isop {
+    /// The cubes of the cover, each sorted by variables.
+    pub cubes: Vec<Cube>,
+    /// The function represented by the disjunction of `cubes`.
+    pub cover: Bdd,
+}
+
+/// Compute an irredundant sum-of-products for the incompletely specified function given by
+/// the `lower` and `upper` bound using the Minato–Morreale algorithm.
+///
+/// The resulting cover satisfies `lower => cover => upper`. No cube can be removed from the
+/// cover and no literal can be removed from any cube without violating these bounds.
+///
+/// Returns `IsopError::BoundsNotImplied` if `lower` does not imply `upper`.
+pub fn isop(lower: &Bdd, upper: &Bdd) -> Result<Isop, IsopError> {
+    isop_in(lower, upper, &ApplyConfig::default())
+}
+
+/// The same as `isop`, but stops with `IsopError::Cancelled` once the `cancellation` token
+/// is cancelled. The token is polled by every `apply` of the algorithm.
+pub fn isop_cancellable(
+    lower: &Bdd,
+    upper: &Bdd,
+    cancellation: &CancellationToken,
+) -> Result<Isop, IsopError> {
+    isop_in(lower, upper, &cancellable_config(cancellation))
+}
+
+fn isop_in(lower: &Bdd, upper: &Bdd, config: &ApplyConfig) -> Result<Isop, IsopError> {
+    if !apply_in(lower, upper, AndNot, config)?.is_zero() {
+        return Err(IsopError::BoundsNotImplied);
+    }
+    let (cubes, cover) = isop_recursive(lower, upper, &mut IsopCache::default(), config)?;
+    Ok(Isop { cubes, cover })
+}
+
+impl Bdd {
+    /// Compute an irredundant sum-of-products cover of this `Bdd` (see `isop`).
+    pub fn to_isop(&self) -> Isop {
+        match isop_recursive(self, self, &mut IsopCache::default(), &ApplyConfig::default()) {
+            Ok((cubes, cover)) => Isop { cubes, cover },
+            Err(Cancelled) => unreachable!("ISOP without a token cannot be cancelled."),
+        }
+    }
+
+    /// The same as `Bdd::to_isop`, but stops with an error once the `cancellation` token
+    /// is cancelled.
+    pub fn to_isop_cancellable(&self, cancellation: &CancellationToken) -> Result<Isop, Cancelled> {
+        let config = cancellable_config(cancellation);
+        let (cubes, cover) = isop_recursive(self, self, &mut IsopCache::default(), &config)?;
+        Ok(Isop { cubes, cover })
+    }
+}
+
+fn cancellable_config(cancellation: &CancellationToken) -> ApplyConfig {
+    ApplyConfig {
+        cancellation: Some(cancellation.clone()),
+        ..ApplyConfig::default()
+    }
+}
+
+/// Apply the `operator` using the given `config`, which can only fail due to cancellation.
+fn apply_in<Op: BooleanOperator>(
+    left: &Bdd,
+    right: &Bdd,
+    operator: Op,
+    config: &ApplyConfig,
+) -> Result<Bdd, Cancelled> {
+    match apply_with_config(left, right, operator, config) {
+        Ok((result, _)) => Ok(result),
+        Err(ApplyError::Cancelled { .. }) => Err(Cancelled),
+        Err(error) => unreachable!("Unlimited apply failed: {:?}", error),
+    }
+}
+
+/// The recursive step of the Minato–Morreale algorithm. The recursion depth is bounded by
+/// the number of variables, so a native recursion is sufficient here. Results of non-trivial
+/// sub-problems are saved in the `cache`.
+fn isop_recursive(
+    lower: &Bdd,
+    upper: &Bdd,
+    cache: &mut IsopCache,
+    config: &ApplyConfig,
+) -> Result<(Vec<Cube>, Bdd), Cancelled> {
+    if lower.is_zero() {
+        return Ok((Vec::new(), Bdd::new_zero()));
+    }
+    if upper.is_one() {
+        return Ok((vec![Vec::new()], Bdd::new_one()));
+    }
+    if let Some(cached) = cache.get(lower, upper) {
+        return Ok(cached.clone());
+    }
+
+    // Both bounds are non-constant, since `lower` is not `0` and implies `upper`.
+    let variable = min(
+        lower.get_root_node().get_variable(),
+        upper.get_root_node().get_variable(),
+    );
+    let (lower_0, lower_1) = cofactors(lower, variable);
+    let (upper_0, upper_1) = cofactors(upper, variable);
+
+    // Cubes that must contain the literal `!x` (resp. `x`): the parts of the lower bound
+    // which cannot be covered when the variable is set to the opposite value.
+    let must_0 = apply_in(&lower_0, &upper_1, AndNot, config)?;
+    let (cubes_0, cover_0) = isop_recursive(&must_0, &upper_0, cache, config)?;
+    let must_1 = apply_in(&lower_1, &upper_0, AndNot, config)?;
+    let (cubes_1, cover_1) = isop_recursive(&must_1, &upper_1, cache, config)?;
+
+    // The remaining parts of the lower bound are covered by cubes independent of `x`.
+    let remaining_0 = apply_in(&lower_0, &cover_0, AndNot, config)?;
+    let remaining_1 = apply_in(&lower_1, &cover_1, AndNot, config)?;
+    let remaining = apply_in(&remaining_0, &remaining_1, Or, config)?;
+    let upper_d = apply_in(&upper_0, &upper_1, And, config)?;
+    let (cubes_d, cover_d) = isop_recursive(&remaining, &upper_d, cache, config)?;
+
+    // The variable is smaller than all variables of the sub-results, so prepending
+    // the literal keeps the cubes sorted.
+    let mut cubes = Vec::with_capacity(cubes_0.len() + cubes_1.len() + cubes_d.len());
+    for (value, sub_cubes) in [(false, cubes_0), (true, cubes_1)] {
+        for sub_cube in sub_cubes {
+            let mut cube = Vec::with_capacity(sub_cube.len() + 1);
+            cube.push((variable, value));
+            cube.extend(sub_cube);
+            cubes.push(cube);
+        }
+    }
+    cubes.extend(cubes_d);
+
+    let cover_0 = apply_in(&Bdd::new_literal(variable, false), &cover_0, And, config)?;
+    let cover_1 = apply_in(&Bdd::new_literal(variable, true), &cover_1, And, config)?;
+    let cover = apply_in(&apply_in(&cover_0, &cover_1, Or, config)?, &cover_d, Or, config)?;
+    cache.insert(lower, upper, (cubes.clone(), cover.clone()));
+    Ok((cubes, cover))
+}
+
+/// The `(low, high)` cofactors of the `bdd` w.r.t. the given `variable`, assuming
+/// the `variable` is not greater than the root variable.
+fn cofactors(bdd: &Bdd, variable: Variable) -> (Bdd, Bdd) {
+    let (root_variable, low, high) = bdd.get_root_node().unpack();
+    if root_variable != variable {
+        return (bdd.clone(), bdd.clone());
+    }
+    (
+        Bdd::from_reachable_nodes(bdd.get_nodes(), low),
+        Bdd::from_reachable_nodes(bdd.get_nodes(), high),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{isop, isop_cancellable, IsopError};
+    use crate::v4::apply::{apply, And, AndNot, Or};
+    use crate::v4::core::{Bdd, CancellationToken};
+    use crate::v4::dnf::Cube;
+    use crate::v4::test_util::nodes;
+    use crate::verify::{FunctionTable, Lcg};
+
+    fn cube_to_bdd(cube: &Cube) -> Bdd {
+        cube.iter().fold(Bdd::new_one(), |result, (variable, value)| {
+            apply(&result, &Bdd::new_literal(*variable, *value), And).0
+        })
+    }
+
+    fn implies(left: &Bdd, right: &Bdd) -> bool {
+        apply(left, right, AndNot).0.is_zero()
+    }
+
+    /// Check that the cubes form an irredundant cover of prime implicants between the bounds.
+    fn check_isop(lower: &Bdd, upper: &Bdd, cubes: &[Cube], cover: &Bdd) {
+        let union = cubes.iter().fold(Bdd::new_zero(), |result, cube| {
+            apply(&result, &cube_to_bdd(cube), Or).0
+        });
+        assert_eq!(nodes(cover), nodes(&union));
+        assert!(implies(lower, cover));
+        assert!(implies(cover, upper));
+        for (i, cube) in cubes.iter().enumerate() {
+            assert!(cube.windows(2).all(|pair| pair[0].0 < pair[1].0));
+            let others = cubes
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .fold(Bdd::new_zero(), |result, (_, cube)| apply(&result, &cube_to_bdd(cube), Or).0);
+            assert!(!implies(lower, &others));
+            for j in 0..cube.len() {
+                let mut reduced = cube.clone();
+                reduced.remove(j);
+                assert!(!implies(&cube_to_bdd(&reduced), upper));
+            }
+        }
+    }
+
+    #[test]
+    pub fn isop_completely_specified() {
+        let mut rng = Lcg::new(5);
+        for density in &[0, 20, 50, 80, 100] {
+            let table = FunctionTable::random(5, *density, &mut rng);
+            let bdd = table.to_bdd();
+            let result = bdd.to_isop();
+            assert_eq!(nodes(&bdd), nodes(&result.cover));
+            check_isop(&bdd, &bdd, &result.cubes, &result.cover);
+            assert!(result.cubes.len() <= bdd.cubes().count());
+        }
+        assert_eq!(vec![Cube::new()], Bdd::new_one().to_isop().cubes);
+        assert!(Bdd::new_zero().to_isop().cubes.is_empty());
+    }
+
+    #[test]
+    pub fn isop_incompletely_specified() {
+        let mut rng = Lcg::new(17);
+        for _ in 0..5 {
+            let function = FunctionTable::random(5, 50, &mut rng).to_bdd();
+            let dont_care = FunctionTable::random(5, 30, &mut rng).to_bdd();
+            let lower = apply(&function, &dont_care, AndNot).0;
+            let upper = apply(&function, &dont_care, Or).0;
+            let result = isop(&lower, &upper).unwrap();
+            check_isop(&lower, &upper, &result.cubes, &result.cover);
+            let reversed = isop(&upper, &lower).map(|_| ());
+            let is_equal = nodes(&upper) == nodes(&lower);
+            assert!(reversed == Err(IsopError::BoundsNotImplied) || is_equal);
+        }
+    }
+
+    #[test]
+    pub fn isop_cancellation() {
+        let mut rng = Lcg::new(23);
+        let lower = FunctionTable::random(5, 40, &mut rng).to_bdd();
+        let extra = FunctionTable::random(5, 30, &mut rng).to_bdd();
+        let upper = apply(&lower, &extra, Or).0;
+        let token = CancellationToken::new();
+        let expected = isop(&lower, &upper).unwrap();
+        let result = isop_cancellable(&lower, &upper, &token).unwrap();
+        assert_eq!(expected.cubes, result.cubes);
+        let cover = lower.to_isop_cancellable(&token).unwrap().cover;
+        assert_eq!(nodes(&lower.to_isop().cover), nodes(&cover));
+        token.cancel();
+        let cancelled = isop_cancellable(&lower, &upper, &token).map(|_| ());
+        assert_eq!(Err(IsopError::Cancelled), cancelled);
+        assert!(lower.to_isop_cancellable(&token).is_err());
+    }
+}
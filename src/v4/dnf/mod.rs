@@ -0,0 +1,9 @@
+//! Conversion of BDDs into human-readable disjunctive normal form and irredundant covers.
+
+mod cubes;
+mod isop;
+mod render;
+
+pub use cubes::{Cube, CubeIterator};
+pub use isop::{isop, isop_cancellable, Isop, IsopError};
+pub use render::DnfOptions;
@@ -0,0 +1,222 @@
+use super::super::apply::{apply, AndNot};
+use super::super::core::{Bdd, Node, NodeIndex, Variable};
+use super::Cube;
+use crate::FromIndex;
+use std::collections::HashSet;
+
+/// Options for the conversion of a `Bdd` into a disjunctive normal form.
+///
+/// By default, the DNF simply consists of the disjoint cubes given by `Bdd::cubes`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct DnfOptions {
+    /// Repeatedly merge pairs of cubes which only differ in the value of one variable into
+    /// a single cube without that variable. The cubes remain disjoint.
+    pub merge_cubes: bool,
+    /// Remove every literal from a cube if the cube remains an implicant of the function
+    /// without it (i.e. the variable is a don't-care for the cube). Afterwards, duplicate
+    /// and subsumed cubes are removed. The cubes are then no longer disjoint, but usually
+    /// the formula becomes much shorter.
+    pub suppress_dont_cares: bool,
+}
+
+impl Bdd {
+    /// Compute a list of cubes whose disjunction is equivalent to this `Bdd`.
+    pub fn to_dnf(&self, options: DnfOptions) -> Vec<Cube> {
+        let mut cubes: Vec<Cube> = self.cubes().collect();
+        if options.merge_cubes {
+            cubes = merge_cubes(cubes);
+        }
+        if options.suppress_dont_cares {
+            cubes = suppress_dont_cares(self, cubes);
+        }
+        cubes
+    }
+
+    /// Render this `Bdd` as a DNF formula, e.g. `(a & !b) | c`. Variables are rendered
+    /// using the `names` function. Constants are rendered as `true` and `false`.
+    pub fn to_dnf_string<F: Fn(Variable) -> String>(&self, names: F, options: DnfOptions) -> String {
+        let cubes = self.to_dnf(options);
+        if cubes.is_empty() {
+            return "false".to_string();
+        }
+        let is_single = cubes.len() == 1;
+        let terms: Vec<String> = cubes
+            .iter()
+            .map(|cube| {
+                if cube.is_empty() {
+                    return "true".to_string();
+                }
+                let literals: Vec<String> = cube
+                    .iter()
+                    .map(|(variable, value)| {
+                        if *value {
+                            names(*variable)
+                        } else {
+                            format!("!{}", names(*variable))
+                        }
+                    })
+                    .collect();
+                if is_single || literals.len() == 1 {
+                    literals.join(" & ")
+                } else {
+                    format!("({})", literals.join(" & "))
+                }
+            })
+            .collect();
+        terms.join(" | ")
+    }
+}
+
+/// Merge cubes of the form `x & C` and `!x & C` into `C` until no such pair exists.
+fn merge_cubes(cubes: Vec<Cube>) -> Vec<Cube> {
+    let mut cubes: HashSet<Cube> = cubes.into_iter().collect();
+    loop {
+        let mut merge = None;
+        'search: for cube in &cubes {
+            for i in 0..cube.len() {
+                let mut flipped = cube.clone();
+                flipped[i].1 = !flipped[i].1;
+                if cubes.contains(&flipped) {
+                    merge = Some((cube.clone(), flipped, i));
+                    break 'search;
+                }
+            }
+        }
+        match merge {
+            None => break,
+            Some((cube, flipped, i)) => {
+                cubes.remove(&cube);
+                cubes.remove(&flipped);
+                let mut merged = cube;
+                merged.remove(i);
+                cubes.insert(merged);
+            }
+        }
+    }
+    let mut cubes: Vec<Cube> = cubes.into_iter().collect();
+    cubes.sort();
+    cubes
+}
+
+/// Greedily remove literals from cubes while they remain implicants of the `bdd`.
+fn suppress_dont_cares(bdd: &Bdd, cubes: Vec<Cube>) -> Vec<Cube> {
+    let mut result: Vec<Cube> = Vec::new();
+    for mut cube in cubes {
+        let mut i = 0;
+        while i < cube.len() {
+            let mut candidate = cube.clone();
+            candidate.remove(i);
+            let (outside, _) = apply(&cube_to_bdd(&candidate), bdd, AndNot);
+            if outside.is_zero() {
+                cube = candidate;
+            } else {
+                i += 1;
+            }
+        }
+        if !result.contains(&cube) {
+            result.push(cube);
+        }
+    }
+
+    // Remove cubes which are subsumed by a shorter cube.
+    let is_subsumed = |cube: &Cube, other: &Cube| {
+        other.len() < cube.len() && other.iter().all(|literal| cube.contains(literal))
+    };
+    let subsumed: Vec<bool> = result
+        .iter()
+        .map(|cube| result.iter().any(|other| is_subsumed(cube, other)))
+        .collect();
+    let mut result: Vec<Cube> = result
+        .into_iter()
+        .zip(subsumed)
+        .filter(|(_, subsumed)| !subsumed)
+        .map(|(cube, _)| cube)
+        .collect();
+    result.sort();
+    result
+}
+
+/// Create a `Bdd` which is satisfied exactly by the valuations in the given `cube`.
+fn cube_to_bdd(cube: &Cube) -> Bdd {
+    let mut nodes = vec![Node::ZERO, Node::ONE];
+    let mut root = NodeIndex::ONE;
+    for (variable, value) in cube.iter().rev() {
+        let node = if *value {
+            Node::pack(*variable, NodeIndex::ZERO, root)
+        } else {
+            Node::pack(*variable, root, NodeIndex::ZERO)
+        };
+        nodes.push(node);
+        root = NodeIndex::from_index(nodes.len() - 1);
+    }
+    Bdd::from_reachable_nodes(&nodes, root)
+}
+
+#[cfg(test)]
+mod test {
+    use super::DnfOptions;
+    use crate::v4::apply::{apply, And, Or};
+    use crate::v4::core::{Bdd, Variable};
+    use crate::v4::test_util::nodes;
+    use crate::verify::{FunctionTable, Lcg};
+
+    fn name(variable: Variable) -> String {
+        ["a", "b", "c", "d"][u32::from(variable) as usize].to_string()
+    }
+
+    fn literal(variable: u32, value: bool) -> Bdd {
+        Bdd::new_literal(Variable::from(variable), value)
+    }
+
+    /// Rebuild a `Bdd` from the DNF cubes to check that the DNF is equivalent.
+    fn from_dnf(cubes: &[super::Cube]) -> Bdd {
+        cubes.iter().fold(Bdd::new_zero(), |result, cube| {
+            let term = cube.iter().fold(Bdd::new_one(), |term, (variable, value)| {
+                apply(&term, &Bdd::new_literal(*variable, *value), And).0
+            });
+            apply(&result, &term, Or).0
+        })
+    }
+
+    #[test]
+    pub fn dnf_rendering() {
+        // a | b
+        let bdd = apply(&literal(0, true), &literal(1, true), Or).0;
+        let plain = DnfOptions::default();
+        assert_eq!("(!a & b) | a", bdd.to_dnf_string(name, plain));
+        let options = DnfOptions {
+            suppress_dont_cares: true,
+            ..DnfOptions::default()
+        };
+        assert_eq!("a | b", bdd.to_dnf_string(name, options));
+
+        // (a & c) | (!a & c) = c
+        let bdd = apply(&literal(0, true), &literal(2, true), And).0;
+        let bdd = apply(&bdd, &apply(&literal(0, false), &literal(2, true), And).0, Or).0;
+        assert_eq!("c", bdd.to_dnf_string(name, plain));
+        assert_eq!("!a & !b", apply(&literal(0, false), &literal(1, false), And).0.to_dnf_string(name, plain));
+        assert_eq!("false", Bdd::new_zero().to_dnf_string(name, plain));
+        assert_eq!("true", Bdd::new_one().to_dnf_string(name, plain));
+    }
+
+    #[test]
+    pub fn dnf_options_preserve_function() {
+        let mut rng = Lcg::new(31);
+        let all_options = [
+            DnfOptions::default(),
+            DnfOptions { merge_cubes: true, suppress_dont_cares: false },
+            DnfOptions { merge_cubes: false, suppress_dont_cares: true },
+            DnfOptions { merge_cubes: true, suppress_dont_cares: true },
+        ];
+        for density in &[10, 50, 90] {
+            let table = FunctionTable::random(4, *density, &mut rng);
+            let bdd = table.to_bdd();
+            let disjoint = bdd.to_dnf(DnfOptions::default()).len();
+            for options in &all_options {
+                let cubes = bdd.to_dnf(*options);
+                assert!(cubes.len() <= disjoint);
+                assert_eq!(nodes(&bdd), nodes(&from_dnf(&cubes)));
+            }
+        }
+    }
+}
@@ -2,4 +2,12 @@
 /// Private declarations of the core data structures of the BDD implementation. Everything
 /// essential is re-exported in this module for public use.
 pub mod core;
-pub mod apply;
\ No newline at end of file
+pub mod apply;
+pub mod pool;
+pub mod reorder;
+pub mod complement;
+pub mod zdd;
+pub mod mtbdd;
+pub mod dnf;
+#[cfg(test)]
+pub(crate) mod test_util;
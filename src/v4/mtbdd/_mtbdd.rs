@@ -0,0 +1,207 @@
+use super::super::core::{Bdd, Node, NodeIndex, Variable};
+use crate::{FromIndex, IntoIndex};
+use std::hash::Hash;
+
+/// An `Mtbdd` is a decision diagram which maps every valuation of its variables to a value
+/// of type `T`.
+///
+/// The layout is the same as for `Bdd`, except that there can be any number of terminal nodes:
+/// The first `k` nodes are terminals, and the `i`-th terminal has the value `values[i]`. Every
+/// terminal value is unique and every terminal is reachable from the root. The root is the last
+/// node and the height is defined in the same way as for `Bdd`.
+///
+/// Note that `f64` does not implement `Eq` and `Hash`. Use the `Float` wrapper instead.
+#[derive(Clone)]
+pub struct Mtbdd<T: Clone + Eq + Hash> {
+    height: u32,
+    nodes: Vec<Node>,
+    values: Vec<T>,
+}
+
+/// Basic operations for examining the contents of an MTBDD.
+impl<T: Clone + Eq + Hash> Mtbdd<T> {
+    /// Create an `Mtbdd` directly from its parts. The parts must satisfy the layout invariants.
+    pub(super) fn from_parts(height: u32, nodes: Vec<Node>, values: Vec<T>) -> Mtbdd<T> {
+        Mtbdd { height, nodes, values }
+    }
+
+    /// Create an `Mtbdd` representing the constant function with the given `value`.
+    pub fn new_constant(value: T) -> Mtbdd<T> {
+        Mtbdd {
+            height: 0,
+            nodes: vec![Node::ZERO],
+            values: vec![value],
+        }
+    }
+
+    /// Create an `Mtbdd` which evaluates to `high` when `variable` is true and to `low` otherwise.
+    pub fn new_literal(variable: Variable, low: T, high: T) -> Mtbdd<T> {
+        if low == high {
+            return Mtbdd::new_constant(low);
+        }
+        Mtbdd {
+            height: 2,
+            nodes: vec![Node::ZERO, Node::ONE, Node::pack(variable, NodeIndex::ZERO, NodeIndex::ONE)],
+            values: vec![low, high],
+        }
+    }
+
+    /// Create an `Mtbdd` which evaluates to `one` for the satisfying valuations of the `bdd`
+    /// and to `zero` for the rest.
+    pub fn from_bdd(bdd: &Bdd, zero: T, one: T) -> Mtbdd<T> {
+        if bdd.is_zero() || zero == one {
+            return Mtbdd::new_constant(zero);
+        }
+        if bdd.is_one() {
+            return Mtbdd::new_constant(one);
+        }
+        Mtbdd {
+            height: bdd.get_height(),
+            nodes: bdd.iter_indices().map(|i| bdd.get_node(i).clone()).collect(),
+            values: vec![zero, one],
+        }
+    }
+
+    /// Upper bound on the height of the MTBDD graph.
+    #[inline]
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// The number of nodes in the MTBDD graph (including all terminals).
+    #[inline]
+    pub fn get_size(&self) -> u64 {
+        u64::from_index(self.nodes.len())
+    }
+
+    /// The index of the graph root node.
+    #[inline]
+    pub fn get_root_index(&self) -> NodeIndex {
+        NodeIndex::from_index(self.nodes.len() - 1)
+    }
+
+    /// Get a reference to a `Node` using the given `index`.
+    #[inline]
+    pub fn get_node(&self, index: NodeIndex) -> &Node {
+        &self.nodes[index.into_index()]
+    }
+
+    /// The value of the terminal node at the given `index`, or `None` if the node
+    /// is not a terminal.
+    #[inline]
+    pub fn get_value(&self, index: NodeIndex) -> Option<&T> {
+        self.values.get(index.into_index())
+    }
+
+    /// The values of all terminal nodes.
+    #[inline]
+    pub fn get_values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// True if the MTBDD represents a constant function.
+    #[inline]
+    pub fn is_constant(&self) -> bool {
+        self.nodes.len() == 1
+    }
+
+    /// Find the value of the function for the given `valuation` of variables.
+    pub fn eval<F: Fn(Variable) -> bool>(&self, valuation: F) -> &T {
+        let mut index = self.get_root_index();
+        loop {
+            let node = self.get_node(index);
+            if node.is_terminal() {
+                return &self.values[index.into_index()];
+            }
+            index = if valuation(node.get_variable()) {
+                node.get_high_link()
+            } else {
+                node.get_low_link()
+            };
+        }
+    }
+
+    /// Create a `Bdd` which is satisfied exactly by the valuations for which the value of this
+    /// function satisfies the given `predicate`.
+    pub fn threshold<F: Fn(&T) -> bool>(&self, predicate: F) -> Bdd {
+        Bdd::from(&self.map(predicate))
+    }
+}
+
+/// An `Mtbdd` with Boolean values is just a `Bdd`.
+impl From<&Mtbdd<bool>> for Bdd {
+    fn from(mtbdd: &Mtbdd<bool>) -> Self {
+        if mtbdd.is_constant() {
+            return if mtbdd.values[0] {
+                Bdd::new_one()
+            } else {
+                Bdd::new_zero()
+            };
+        }
+        // There are exactly two terminals, because the values are unique and both are reachable.
+        let remap = |index: NodeIndex| -> NodeIndex {
+            match mtbdd.get_value(index) {
+                Some(true) => NodeIndex::ONE,
+                Some(false) => NodeIndex::ZERO,
+                None => index,
+            }
+        };
+        let nodes: Vec<Node> = mtbdd
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| {
+                if i == 1 {
+                    Node::ONE
+                } else if node.is_terminal() {
+                    Node::ZERO
+                } else {
+                    let (variable, low, high) = node.unpack();
+                    Node::pack(variable, remap(low), remap(high))
+                }
+            })
+            .collect();
+        Bdd::from_reachable_nodes(&nodes, mtbdd.get_root_index())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Mtbdd;
+    use crate::v4::core::{Bdd, Variable};
+    use crate::v4::test_util::nodes;
+    use crate::verify::{FunctionTable, Lcg};
+
+    #[test]
+    pub fn mtbdd_from_bdd_and_threshold() {
+        let mut rng = Lcg::new(21);
+        for density in &[0, 30, 70, 100] {
+            let table = FunctionTable::random(6, *density, &mut rng);
+            let bdd = table.to_bdd();
+            let mtbdd = Mtbdd::from_bdd(&bdd, 10u32, 20u32);
+            assert_eq!(bdd.get_height(), mtbdd.get_height());
+            for row in 0..64u64 {
+                let expected = if table.get(row) { 20 } else { 10 };
+                let valuation = |v: Variable| (row >> (5 - u32::from(v))) & 1 == 1;
+                assert_eq!(expected, *mtbdd.eval(valuation));
+            }
+            assert_eq!(nodes(&bdd), nodes(&mtbdd.threshold(|v| *v > 15)));
+            assert_eq!(nodes(&bdd.not()), nodes(&mtbdd.threshold(|v| *v < 15)));
+            assert!(mtbdd.threshold(|v| *v > 100).is_zero());
+        }
+    }
+
+    #[test]
+    pub fn mtbdd_constants_and_literals() {
+        let constant = Mtbdd::new_constant(5);
+        assert!(constant.is_constant());
+        assert_eq!(0, constant.get_height());
+        assert_eq!(5, *constant.eval(|_| true));
+        let literal = Mtbdd::new_literal(Variable::from(2), 'a', 'b');
+        assert_eq!('b', *literal.eval(|_| true));
+        assert_eq!('a', *literal.eval(|_| false));
+        assert_eq!(2, literal.get_height());
+        assert!(Mtbdd::new_literal(Variable::from(2), 'a', 'a').is_constant());
+        assert!(Mtbdd::from_bdd(&Bdd::new_one(), 1, 2).is_constant());
+    }
+}
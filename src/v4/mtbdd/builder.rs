@@ -0,0 +1,135 @@
+use super::super::core::{Node, NodeIndex, Variable};
+use super::Mtbdd;
+use crate::{FromIndex, IntoIndex};
+use fxhash::FxHashMap;
+use std::cmp::max;
+use std::hash::Hash;
+
+/// A unique table for building an `Mtbdd` node by node. Terminal and decision nodes can be
+/// created in any order, the layout of the `Mtbdd` is established when it is exported.
+pub(super) struct MtbddBuilder<T: Clone + Eq + Hash> {
+    nodes: Vec<Node>,
+    values: Vec<Option<T>>,
+    node_table: FxHashMap<Node, NodeIndex>,
+    terminal_table: FxHashMap<T, NodeIndex>,
+}
+
+impl<T: Clone + Eq + Hash> MtbddBuilder<T> {
+    pub fn new() -> MtbddBuilder<T> {
+        MtbddBuilder {
+            nodes: Vec::new(),
+            values: Vec::new(),
+            node_table: FxHashMap::default(),
+            terminal_table: FxHashMap::default(),
+        }
+    }
+
+    /// Get the index of the terminal node with the given `value`.
+    pub fn ensure_terminal(&mut self, value: T) -> NodeIndex {
+        let nodes = &mut self.nodes;
+        let values = &mut self.values;
+        *self.terminal_table.entry(value).or_insert_with_key(|value| {
+            let index = NodeIndex::from_index(nodes.len());
+            nodes.push(Node::pack(Variable::UNDEFINED, index, index));
+            values.push(Some(value.clone()));
+            index
+        })
+    }
+
+    /// Get the index of the reduced decision node `(variable, low, high)`.
+    pub fn ensure(&mut self, variable: Variable, low: NodeIndex, high: NodeIndex) -> NodeIndex {
+        if low == high {
+            return low;
+        }
+        let nodes = &mut self.nodes;
+        let values = &mut self.values;
+        *self
+            .node_table
+            .entry(Node::pack(variable, low, high))
+            .or_insert_with_key(|node| {
+                nodes.push(node.clone());
+                values.push(None);
+                NodeIndex::from_index(nodes.len() - 1)
+            })
+    }
+
+    /// Create an `Mtbdd` from the nodes reachable from the given `root` (see `export_reachable`).
+    pub fn export(self, root: NodeIndex) -> Mtbdd<T> {
+        export_reachable(&self.nodes, self.values, root)
+    }
+}
+
+/// Create an `Mtbdd` from the `nodes` reachable from the given `root`, where `values[i]` is the
+/// value of node `i` if it is a terminal. The links of terminal nodes are ignored. The reachable
+/// terminals are placed first, followed by the decision nodes in DFS pre-order with the root last.
+pub(super) fn export_reachable<T: Clone + Eq + Hash>(
+    nodes: &[Node],
+    mut values: Vec<Option<T>>,
+    root: NodeIndex,
+) -> Mtbdd<T> {
+    let mut new_index = vec![NodeIndex::UNDEFINED; nodes.len()];
+    let mut terminals: Vec<NodeIndex> = Vec::new();
+    let mut preorder: Vec<NodeIndex> = Vec::new();
+    let mut search_stack = vec![root];
+    while let Some(task) = search_stack.pop() {
+        if !new_index[task.into_index()].is_undefined() {
+            continue;
+        }
+        let node = &nodes[task.into_index()];
+        if node.is_terminal() {
+            new_index[task.into_index()] = NodeIndex::from_index(terminals.len());
+            terminals.push(task);
+        } else {
+            new_index[task.into_index()] = NodeIndex::from_index(preorder.len());
+            preorder.push(task);
+            search_stack.push(node.get_high_link());
+            search_stack.push(node.get_low_link());
+        }
+    }
+
+    let terminal_count = terminals.len();
+    let node_count = terminal_count + preorder.len();
+    let remap = |index: NodeIndex| -> NodeIndex {
+        let order = new_index[index.into_index()].into_index();
+        if nodes[index.into_index()].is_terminal() {
+            NodeIndex::from_index(order)
+        } else {
+            NodeIndex::from_index(node_count - 1 - order)
+        }
+    };
+
+    let mut new_nodes = Vec::with_capacity(node_count);
+    let mut new_values = Vec::with_capacity(terminal_count);
+    for (i, old_index) in terminals.iter().enumerate() {
+        let index = NodeIndex::from_index(i);
+        new_nodes.push(Node::pack(Variable::UNDEFINED, index, index));
+        new_values.push(values[old_index.into_index()].take().unwrap());
+    }
+    new_nodes.resize(node_count, Node::ZERO);
+    for old_index in &preorder {
+        let (variable, low, high) = nodes[old_index.into_index()].unpack();
+        new_nodes[remap(*old_index).into_index()] = Node::pack(variable, remap(low), remap(high));
+    }
+
+    // Compute the height using a post-order DFS. Terminals have height one.
+    let mut height = vec![0u32; node_count];
+    let mut stack = vec![(NodeIndex::from_index(node_count - 1), false)];
+    while let Some((index, expanded)) = stack.pop() {
+        let node = &new_nodes[index.into_index()];
+        if node.is_terminal() {
+            height[index.into_index()] = 1;
+            continue;
+        }
+        let (low, high) = (node.get_low_link().into_index(), node.get_high_link().into_index());
+        if expanded {
+            height[index.into_index()] = max(height[low], height[high]) + 1;
+        } else if height[index.into_index()] == 0 {
+            stack.push((index, true));
+            stack.push((node.get_high_link(), false));
+            stack.push((node.get_low_link(), false));
+        }
+    }
+    let height = if preorder.is_empty() { 0 } else { height[node_count - 1] };
+
+    Mtbdd::from_parts(height, new_nodes, new_values)
+}
@@ -0,0 +1,80 @@
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, Mul};
+
+/// A wrapper for `f64` which can be used as a terminal value of an `Mtbdd`.
+///
+/// The values are compared by their bit representation, with `-0.0` treated as `0.0` and all
+/// `NaN` values treated as equal. The ordering is the IEEE total order of these normalized values.
+#[derive(Copy, Clone, Debug)]
+pub struct Float(f64);
+
+impl Float {
+    fn normalized_bits(&self) -> u64 {
+        if self.0 == 0.0 {
+            0
+        } else if self.0.is_nan() {
+            f64::NAN.to_bits()
+        } else {
+            self.0.to_bits()
+        }
+    }
+
+    fn normalized(&self) -> f64 {
+        f64::from_bits(self.normalized_bits())
+    }
+}
+
+impl From<f64> for Float {
+    fn from(value: f64) -> Self {
+        Float(value)
+    }
+}
+
+impl From<Float> for f64 {
+    fn from(value: Float) -> Self {
+        value.0
+    }
+}
+
+impl PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        self.normalized_bits() == other.normalized_bits()
+    }
+}
+
+impl Eq for Float {}
+
+impl Hash for Float {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.normalized_bits().hash(state)
+    }
+}
+
+impl PartialOrd for Float {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Float {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.normalized().total_cmp(&other.normalized())
+    }
+}
+
+impl Add for Float {
+    type Output = Float;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Float(self.0 + rhs.0)
+    }
+}
+
+impl Mul for Float {
+    type Output = Float;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Float(self.0 * rhs.0)
+    }
+}
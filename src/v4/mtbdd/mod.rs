@@ -0,0 +1,15 @@
+//! Multi-terminal BDDs (also known as algebraic decision diagrams).
+//!
+//! An `Mtbdd` uses the same nodes as a `Bdd`, but it can have any number of terminal nodes,
+//! each associated with a value. As anticipated by the documentation of `Node`, a terminal is
+//! any node with an undefined variable and self-loop links, and all terminals are stored before
+//! the decision nodes.
+
+mod _mtbdd;
+mod builder;
+mod operations;
+mod float;
+
+pub use _mtbdd::Mtbdd;
+pub use float::Float;
+pub use operations::{apply, apply_cancellable};
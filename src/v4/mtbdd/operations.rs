@@ -0,0 +1,392 @@
+use super::super::apply::node_cache::NodeCache;
+use super::super::apply::task_cache::TaskCache;
+use super::super::apply::unsafe_stack::UnsafeStack;
+use super::super::apply::{ApplyConfig, ApplyTask};
+use super::super::core::{CancellationToken, Cancelled, Node, NodeIndex, Variable};
+use super::builder::{export_reachable, MtbddBuilder};
+use super::Mtbdd;
+use crate::{FromIndex, IntoIndex};
+use fxhash::FxHashMap;
+use std::cmp::{max, min};
+use std::hash::Hash;
+use std::ops::Add;
+
+/// Compute the function `operator(left(x), right(x))` for the two given MTBDDs.
+///
+/// The operator is only evaluated for pairs of terminal values, and it is evaluated at most
+/// once for each such pair.
+///
+/// The algorithm is the same as the BDD `apply` and uses the same `TaskCache`, `NodeCache` and
+/// task stack. Terminal nodes are stored in the node cache as `(UNDEFINED, k, k)`, where `k` is
+/// the position of their value in a separate value table.
+pub fn apply<A, B, R, F>(left: &Mtbdd<A>, right: &Mtbdd<B>, operator: F) -> Mtbdd<R>
+where
+    A: Clone + Eq + Hash,
+    B: Clone + Eq + Hash,
+    R: Clone + Eq + Hash,
+    F: Fn(&A, &B) -> R,
+{
+    match apply_in(left, right, operator, None) {
+        Ok(result) => result,
+        Err(Cancelled) => unreachable!("Apply without a token cannot be cancelled."),
+    }
+}
+
+/// The same as `apply`, but stops with an error once the `cancellation` token is cancelled.
+/// The token is polled every time the caches are checked for growth.
+pub fn apply_cancellable<A, B, R, F>(
+    left: &Mtbdd<A>,
+    right: &Mtbdd<B>,
+    operator: F,
+    cancellation: &CancellationToken,
+) -> Result<Mtbdd<R>, Cancelled>
+where
+    A: Clone + Eq + Hash,
+    B: Clone + Eq + Hash,
+    R: Clone + Eq + Hash,
+    F: Fn(&A, &B) -> R,
+{
+    apply_in(left, right, operator, Some(cancellation))
+}
+
+fn apply_in<A, B, R, F>(
+    left: &Mtbdd<A>,
+    right: &Mtbdd<B>,
+    operator: F,
+    cancellation: Option<&CancellationToken>,
+) -> Result<Mtbdd<R>, Cancelled>
+where
+    A: Clone + Eq + Hash,
+    B: Clone + Eq + Hash,
+    R: Clone + Eq + Hash,
+    F: Fn(&A, &B) -> R,
+{
+    let config = ApplyConfig::default();
+    let mut task_cache = TaskCache::new(left.get_size());
+    let mut node_cache = NodeCache::new(max(left.get_size() / 2, 2));
+    let height_limit = left.get_height() + right.get_height();
+    let mut stack = UnsafeStack::new(2 * height_limit.into_index() + 1);
+    let mut terminals = TerminalTable::new();
+    // The task cache is leaky, so terminal pairs are cached separately to evaluate them once.
+    let mut terminal_tasks: FxHashMap<(NodeIndex, NodeIndex), NodeIndex> = FxHashMap::default();
+
+    stack.push(ApplyTask::new(0, (left.get_root_index(), right.get_root_index())));
+    let root_result;
+
+    'main: loop {
+        if let Some(cancellation) = cancellation {
+            cancellation.check()?;
+        }
+        let base_iterations = max(
+            left.get_size() >> config.check_interval_shift,
+            config.min_check_interval,
+        );
+        let node_iterations = node_cache.ensure_capacity(base_iterations);
+        let task_iterations = max(task_cache.grow_if_necessary(), base_iterations);
+        for _ in 0..min(node_iterations, task_iterations) {
+            let top = stack.peek();
+            let top_offset: usize = top.get_offset().into();
+
+            let result;
+            if top.is_not_decoded() {
+                top.mark_as_decoded();
+
+                let (l, r) = top.task;
+                let (l_var, l_low, l_high) = left.get_node(l).unpack();
+                let (r_var, r_low, r_high) = right.get_node(r).unpack();
+                if l_var.is_undefined() && r_var.is_undefined() {
+                    result = *terminal_tasks.entry(top.task).or_insert_with(|| {
+                        let l_value = left.get_value(l).unwrap();
+                        let r_value = right.get_value(r).unwrap();
+                        terminals.ensure(&mut node_cache, operator(l_value, r_value))
+                    });
+                } else {
+                    let (cached, slot) = task_cache.read(top.task);
+                    result = cached;
+                    if cached.is_undefined() {
+                        top.task_cache_slot = slot;
+                        let variable = min(l_var, r_var);
+                        let (l_low, l_high) = if l_var == variable {
+                            (l_low, l_high)
+                        } else {
+                            (l, l)
+                        };
+                        let (r_low, r_high) = if r_var == variable {
+                            (r_low, r_high)
+                        } else {
+                            (r, r)
+                        };
+                        top.variable = variable;
+                        stack.push(ApplyTask::new(1, (l_high, r_high)));
+                        stack.push(ApplyTask::new(2, (l_low, r_low)));
+                    }
+                }
+            } else {
+                // Task is decoded, we have to create a new node for it.
+                let (result_low, result_high) = (top.results[1], top.results[0]);
+                if result_low == result_high {
+                    result = result_low;
+                } else {
+                    let node = Node::pack(top.variable, result_low, result_high);
+                    result = ensure_node(&mut node_cache, &node);
+                }
+                task_cache.write(top.task_cache_slot, top.task, result);
+            }
+
+            if !result.is_undefined() {
+                stack.pop();
+                if stack.is_empty() {
+                    root_result = result;
+                    break 'main;
+                }
+                let parent = stack.peek_at(top_offset);
+                parent.results[top_offset - 1] = result;
+            }
+        }
+    }
+
+    Ok(terminals.export(&node_cache, root_result))
+}
+
+/// Find or create the given `node` in the `node_cache`.
+fn ensure_node(node_cache: &mut NodeCache, node: &Node) -> NodeIndex {
+    let mut cached = node_cache.ensure(node);
+    while let Err(slot) = cached {
+        cached = node_cache.ensure_at(node, slot);
+    }
+    cached.unwrap()
+}
+
+/// The values of the terminal nodes created by `apply` in a `NodeCache`.
+struct TerminalTable<R: Clone + Eq + Hash> {
+    values: Vec<R>,
+    table: FxHashMap<R, NodeIndex>,
+}
+
+impl<R: Clone + Eq + Hash> TerminalTable<R> {
+    fn new() -> TerminalTable<R> {
+        TerminalTable {
+            values: Vec::new(),
+            table: FxHashMap::default(),
+        }
+    }
+
+    /// Get the index of the terminal node with the given `value`. The node stores the position
+    /// of the value in both links, which is always smaller than the size of the node cache,
+    /// as required by its hash function.
+    fn ensure(&mut self, node_cache: &mut NodeCache, value: R) -> NodeIndex {
+        if let Some(index) = self.table.get(&value) {
+            return *index;
+        }
+        let position = NodeIndex::from_index(self.values.len());
+        let index = ensure_node(node_cache, &Node::pack(Variable::UNDEFINED, position, position));
+        self.values.push(value.clone());
+        self.table.insert(value, index);
+        index
+    }
+
+    /// Create an `Mtbdd` from the nodes of the `node_cache` reachable from `root`. The two
+    /// terminals that the cache creates by default are never referenced.
+    fn export(self, node_cache: &NodeCache, root: NodeIndex) -> Mtbdd<R> {
+        let nodes = node_cache.export_nodes();
+        let mut values: Vec<Option<R>> = vec![None; nodes.len()];
+        let mut table_values: Vec<Option<R>> = self.values.into_iter().map(Some).collect();
+        for (index, node) in nodes.iter().enumerate().skip(2) {
+            if node.is_terminal() {
+                values[index] = table_values[node.get_low_link().into_index()].take();
+            }
+        }
+        export_reachable(&nodes, values, root)
+    }
+}
+
+/// Unary operations and abstraction.
+impl<T: Clone + Eq + Hash> Mtbdd<T> {
+    /// Create an `Mtbdd` where every terminal value `v` is replaced with `function(v)`.
+    pub fn map<R: Clone + Eq + Hash, F: Fn(&T) -> R>(&self, function: F) -> Mtbdd<R> {
+        self.rebuild(function, None)
+    }
+
+    /// Create an `Mtbdd` where the given `variable` is fixed to the given `value`.
+    pub fn restrict(&self, variable: Variable, value: bool) -> Mtbdd<T> {
+        self.rebuild(T::clone, Some((variable, value)))
+    }
+
+    /// Eliminate the given `variables`, such that the value of the result for every valuation
+    /// is obtained by combining the values for both values of each eliminated variable
+    /// using the `operator`. The operator should be associative and commutative.
+    pub fn abstract_variables<F: Fn(&T, &T) -> T>(&self, variables: &[Variable], operator: F) -> Mtbdd<T> {
+        match self.abstract_variables_in(variables, operator, None) {
+            Ok(result) => result,
+            Err(Cancelled) => unreachable!("Abstraction without a token cannot be cancelled."),
+        }
+    }
+
+    /// The same as `Mtbdd::abstract_variables`, but stops with an error once the `cancellation`
+    /// token is cancelled. The token is polled before each variable is eliminated, as well as
+    /// periodically while the restrictions of a variable are combined.
+    pub fn abstract_variables_cancellable<F: Fn(&T, &T) -> T>(
+        &self,
+        variables: &[Variable],
+        operator: F,
+        cancellation: &CancellationToken,
+    ) -> Result<Mtbdd<T>, Cancelled> {
+        self.abstract_variables_in(variables, operator, Some(cancellation))
+    }
+
+    fn abstract_variables_in<F: Fn(&T, &T) -> T>(
+        &self,
+        variables: &[Variable],
+        operator: F,
+        cancellation: Option<&CancellationToken>,
+    ) -> Result<Mtbdd<T>, Cancelled> {
+        let mut result = self.clone();
+        for variable in variables {
+            if let Some(cancellation) = cancellation {
+                cancellation.check()?;
+            }
+            result = result.abstract_variable(*variable, &operator, cancellation)?;
+        }
+        Ok(result)
+    }
+
+    fn abstract_variable<F: Fn(&T, &T) -> T>(
+        &self,
+        variable: Variable,
+        operator: F,
+        cancellation: Option<&CancellationToken>,
+    ) -> Result<Mtbdd<T>, Cancelled> {
+        let low = self.restrict(variable, false);
+        let high = self.restrict(variable, true);
+        apply_in(&low, &high, operator, cancellation)
+    }
+
+    /// Copy this `Mtbdd`, replacing terminal values using `function` and optionally fixing
+    /// the value of one variable.
+    fn rebuild<R: Clone + Eq + Hash, F: Fn(&T) -> R>(&self, function: F, fixed: Option<(Variable, bool)>) -> Mtbdd<R> {
+        let mut builder = MtbddBuilder::new();
+        let mut results = vec![NodeIndex::UNDEFINED; self.get_size().into_index()];
+        let mut stack = vec![(self.get_root_index(), false)];
+        while let Some((index, expanded)) = stack.pop() {
+            if !results[index.into_index()].is_undefined() {
+                continue;
+            }
+            let (variable, low, high) = self.get_node(index).unpack();
+            if let Some(value) = self.get_value(index) {
+                results[index.into_index()] = builder.ensure_terminal(function(value));
+                continue;
+            }
+            // If the variable is fixed, the node is replaced by the selected child.
+            let (low, high) = match fixed {
+                Some((fixed, true)) if fixed == variable => (high, high),
+                Some((fixed, false)) if fixed == variable => (low, low),
+                _ => (low, high),
+            };
+            if expanded {
+                let low = results[low.into_index()];
+                let high = results[high.into_index()];
+                results[index.into_index()] = builder.ensure(variable, low, high);
+            } else {
+                stack.push((index, true));
+                stack.push((high, false));
+                stack.push((low, false));
+            }
+        }
+        builder.export(results[self.get_root_index().into_index()])
+    }
+}
+
+impl<T: Clone + Eq + Hash + Ord> Mtbdd<T> {
+    /// Eliminate the `variables` by taking the minimum over both of their values.
+    pub fn abstract_min(&self, variables: &[Variable]) -> Mtbdd<T> {
+        self.abstract_variables(variables, |a, b| min(a, b).clone())
+    }
+
+    /// Eliminate the `variables` by taking the maximum over both of their values.
+    pub fn abstract_max(&self, variables: &[Variable]) -> Mtbdd<T> {
+        self.abstract_variables(variables, |a, b| max(a, b).clone())
+    }
+}
+
+impl<T: Clone + Eq + Hash + Add<Output = T>> Mtbdd<T> {
+    /// Eliminate the `variables` by summing the values for both of their values.
+    pub fn abstract_sum(&self, variables: &[Variable]) -> Mtbdd<T> {
+        self.abstract_variables(variables, |a, b| a.clone() + b.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{apply, apply_cancellable};
+    use crate::v4::core::{CancellationToken, Variable};
+    use crate::v4::mtbdd::{Float, Mtbdd};
+
+    /// The function `sum(weights[i] * x_i)`.
+    fn weighted_sum(weights: &[i64]) -> Mtbdd<i64> {
+        weights.iter().enumerate().fold(Mtbdd::new_constant(0), |result, (i, w)| {
+            let literal = Mtbdd::new_literal(Variable::from(i as u32), 0, *w);
+            apply(&result, &literal, |a, b| a + b)
+        })
+    }
+
+    fn valuation(row: usize, variables: usize) -> impl Fn(Variable) -> bool {
+        move |v: Variable| (row >> (variables - 1 - u32::from(v) as usize)) & 1 == 1
+    }
+
+    #[test]
+    pub fn mtbdd_apply() {
+        let weights = [3, -1, 4, 1, -5];
+        let sum = weighted_sum(&weights);
+        let product = apply(&sum, &weighted_sum(&[1, 1, 1, 1, 1]), |a, b| a * b);
+        for row in 0..32 {
+            let expected: i64 = (0..5).filter(|i| (row >> (4 - i)) & 1 == 1).map(|i| weights[i]).sum();
+            let ones = (row as u32).count_ones() as i64;
+            assert_eq!(expected, *sum.eval(valuation(row, 5)));
+            assert_eq!(expected * ones, *product.eval(valuation(row, 5)));
+        }
+        // Values can be also of a different type.
+        let sign = apply(&sum, &Mtbdd::new_constant(()), |a, _| a.signum());
+        assert_eq!(3, sign.get_values().len());
+        assert!(apply(&sum, &sum, |a, b| a - b).is_constant());
+    }
+
+    #[test]
+    pub fn mtbdd_abstraction() {
+        let weights = [3, -1, 4, 1];
+        let sum = weighted_sum(&weights);
+        let eliminated = [Variable::from(1), Variable::from(3)];
+        let min = sum.abstract_min(&eliminated);
+        let max = sum.abstract_max(&eliminated);
+        let total = sum.abstract_sum(&eliminated);
+        for row in 0..16 {
+            let kept: i64 = [0, 2].iter().filter(|i| (row >> (3 - **i)) & 1 == 1).map(|i| weights[*i]).sum();
+            assert_eq!(kept - 1, *min.eval(valuation(row, 4)));
+            assert_eq!(kept + 1, *max.eval(valuation(row, 4)));
+            assert_eq!(4 * kept, *total.eval(valuation(row, 4)));
+        }
+        assert!(sum.abstract_sum(&[0, 1, 2, 3].iter().map(|v| Variable::from(*v)).collect::<Vec<_>>()).is_constant());
+
+        let probability = Mtbdd::new_literal(Variable::from(0), Float::from(0.25), Float::from(0.75));
+        let total = probability.abstract_sum(&[Variable::from(0)]);
+        assert_eq!(1.0, f64::from(*total.eval(|_| false)));
+
+        let token = CancellationToken::new();
+        let max = sum.abstract_variables_cancellable(&eliminated, |a, b| *a.max(b), &token);
+        assert_eq!(sum.abstract_max(&eliminated).get_size(), max.unwrap().get_size());
+        token.cancel();
+        assert!(sum.abstract_variables_cancellable(&eliminated, |a, b| a + b, &token).is_err());
+    }
+
+    #[test]
+    pub fn mtbdd_apply_cancellation() {
+        let left = weighted_sum(&[1, 2, 3]);
+        let right = weighted_sum(&[4, 5, 6]);
+        let token = CancellationToken::new();
+        let expected = apply(&left, &right, |a, b| a * b);
+        let result = apply_cancellable(&left, &right, |a, b| a * b, &token).unwrap();
+        assert_eq!(expected.get_size(), result.get_size());
+        assert_eq!(expected.get_values(), result.get_values());
+        token.cancel();
+        assert!(apply_cancellable(&left, &right, |a, b| a * b, &token).is_err());
+    }
+}
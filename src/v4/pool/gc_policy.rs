@@ -0,0 +1,52 @@
+/// Determines when a `BddPool` automatically runs the garbage collector.
+///
+/// The pool checks the policy at safe points (i.e. when new BDDs are imported). Since counting
+/// dead nodes requires a full marking pass over the live nodes, the pool does not check the
+/// policy every time. Instead, after each check, the pool waits until the storage grows by
+/// another `dead_fraction` of its size before checking again. This ensures the cost of marking
+/// is amortized over the newly created nodes.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct GcPolicy {
+    /// The collector is never triggered automatically while the pool has fewer nodes than this.
+    pub min_nodes: usize,
+    /// The collector is triggered once the dead nodes make up at least this fraction
+    /// of the pool storage.
+    pub dead_fraction: f64,
+}
+
+impl GcPolicy {
+    /// A policy that never triggers the collector. Garbage can be still collected manually
+    /// using `BddPool::collect_garbage`.
+    pub const DISABLED: GcPolicy = GcPolicy {
+        min_nodes: usize::MAX,
+        dead_fraction: 1.0,
+    };
+
+    /// True if the policy allows automatic collection for a pool with `node_count` nodes.
+    pub(super) fn should_check(&self, node_count: usize) -> bool {
+        node_count >= self.min_nodes
+    }
+
+    /// True if `dead_count` dead nodes out of `node_count` are enough to trigger the collector.
+    pub(super) fn should_collect(&self, node_count: usize, dead_count: usize) -> bool {
+        (dead_count as f64) >= self.dead_fraction * (node_count as f64)
+    }
+
+    /// Compute the pool size at which the policy should be checked again, assuming the pool
+    /// currently has `node_count` nodes.
+    pub(super) fn next_check(&self, node_count: usize) -> usize {
+        let growth = (self.dead_fraction * (node_count as f64)) as usize;
+        node_count.saturating_add(growth.max(1))
+    }
+}
+
+/// By default, the garbage is collected once it takes up at least half of a pool with
+/// at least `2^16` nodes.
+impl Default for GcPolicy {
+    fn default() -> Self {
+        GcPolicy {
+            min_nodes: 1 << 16,
+            dead_fraction: 0.5,
+        }
+    }
+}
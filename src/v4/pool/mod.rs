@@ -0,0 +1,324 @@
+mod gc_policy;
+mod root_table;
+
+pub use gc_policy::GcPolicy;
+pub use root_table::BddRoot;
+
+use super::core::{Bdd, Node, NodeIndex, Variable};
+use crate::{FromIndex, IntoIndex};
+use fxhash::FxHashMap;
+use root_table::RootTable;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// A shared storage for multiple BDDs.
+///
+/// All BDDs in the pool share a single node vector with a unique table, so equivalent
+/// sub-graphs are stored only once. The BDDs are referenced using `BddRoot` handles. Once
+/// a handle is dropped, the nodes which are no longer reachable from any root become garbage.
+///
+/// The garbage is reclaimed by a mark-and-compact collector. The collector renumbers all
+/// surviving nodes such that the nodes of each root are sorted in the DFS pre-order (in the same
+/// way as `Bdd::sort_preorder` does it), meaning BDDs in the pool preserve the memory locality
+/// expected by other algorithms. Since the nodes move, raw `NodeIndex` values obtained from
+/// the pool are only valid until the next collection. The collector is either invoked manually
+/// (`BddPool::collect_garbage`), or automatically at safe points based on the `GcPolicy` of the
+/// pool.
+///
+/// Similar to `Bdd`, the first two nodes of the pool are always the `0` and `1` terminals.
+pub struct BddPool {
+    nodes: Vec<Node>,
+    unique_table: FxHashMap<Node, NodeIndex>,
+    roots: Rc<RefCell<RootTable>>,
+    policy: GcPolicy,
+    /// The number of nodes at which the `policy` should be checked again.
+    next_gc_check: usize,
+}
+
+impl BddPool {
+    /// Create a new empty pool with the default `GcPolicy`.
+    pub fn new() -> BddPool {
+        BddPool::with_policy(GcPolicy::default())
+    }
+
+    /// Create a new empty pool that uses the given `GcPolicy`.
+    pub fn with_policy(policy: GcPolicy) -> BddPool {
+        BddPool {
+            nodes: vec![Node::ZERO, Node::ONE],
+            unique_table: FxHashMap::default(),
+            roots: Rc::new(RefCell::new(RootTable::new())),
+            policy,
+            next_gc_check: 0,
+        }
+    }
+
+    /// The number of nodes in the pool storage (including terminals and dead nodes).
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The number of roots that are currently alive.
+    pub fn root_count(&self) -> usize {
+        self.roots.borrow().len()
+    }
+
+    pub fn get_policy(&self) -> GcPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: GcPolicy) {
+        self.policy = policy;
+        self.next_gc_check = 0;
+    }
+
+    /// The current node index of the given `root`.
+    ///
+    /// *The index is only valid until the next garbage collection.*
+    pub fn get_root_index(&self, root: &BddRoot) -> NodeIndex {
+        assert!(root.is_in(&self.roots), "Root belongs to a different pool.");
+        root.get()
+    }
+
+    /// Get a reference to a `Node` using the given `index`.
+    pub fn get_node(&self, index: NodeIndex) -> &Node {
+        &self.nodes[index.into_index()]
+    }
+
+    /// Copy the given `Bdd` into this pool and register its root.
+    ///
+    /// Importing a BDD is a safe point, so the garbage collector can run before the BDD is
+    /// copied into the pool.
+    pub fn import(&mut self, bdd: &Bdd) -> BddRoot {
+        self.collect_garbage_if_necessary();
+
+        // Copy nodes in DFS post-order, such that children are always copied before parents.
+        let mut new_index = vec![NodeIndex::UNDEFINED; bdd.get_size().into_index()];
+        new_index[0] = NodeIndex::ZERO;
+        if !bdd.is_zero() {
+            new_index[1] = NodeIndex::ONE;
+        }
+        let mut search_stack: Vec<(NodeIndex, bool)> = vec![(bdd.get_root_index(), false)];
+        while let Some((task, expanded)) = search_stack.pop() {
+            let node = bdd.get_node(task);
+            if expanded {
+                let (variable, low, high) = node.unpack();
+                let low = new_index[low.into_index()];
+                let high = new_index[high.into_index()];
+                new_index[task.into_index()] = self.ensure_node(variable, low, high);
+            } else if new_index[task.into_index()].is_undefined() {
+                search_stack.push((task, true));
+                search_stack.push((node.get_high_link(), false));
+                search_stack.push((node.get_low_link(), false));
+            }
+        }
+
+        BddRoot::new(&self.roots, new_index[bdd.get_root_index().into_index()])
+    }
+
+    /// Create a stand-alone `Bdd` copy of the given `root`.
+    pub fn export(&self, root: &BddRoot) -> Bdd {
+        Bdd::from_reachable_nodes(&self.nodes, self.get_root_index(root))
+    }
+
+    /// Find or create a node with the given `variable` and links. If both links are the same,
+    /// no node is created and the link is returned instead.
+    fn ensure_node(&mut self, variable: Variable, low: NodeIndex, high: NodeIndex) -> NodeIndex {
+        if low == high {
+            return low;
+        }
+        let node = Node::pack(variable, low, high);
+        if let Some(index) = self.unique_table.get(&node) {
+            return *index;
+        }
+        let index = NodeIndex::from_index(self.nodes.len());
+        self.nodes.push(node.clone());
+        self.unique_table.insert(node, index);
+        index
+    }
+}
+
+/// Garbage collection.
+impl BddPool {
+    /// Run the garbage collector regardless of the pool `GcPolicy`.
+    ///
+    /// Returns the number of reclaimed nodes.
+    pub fn collect_garbage(&mut self) -> usize {
+        let marking = self.mark_preorder();
+        self.compact(marking)
+    }
+
+    /// Check the `GcPolicy` of this pool and run the garbage collector if necessary.
+    ///
+    /// Returns the number of reclaimed nodes (`0` if the collector did not run).
+    pub fn collect_garbage_if_necessary(&mut self) -> usize {
+        let node_count = self.nodes.len();
+        if !self.policy.should_check(node_count) || node_count < self.next_gc_check {
+            return 0;
+        }
+        let marking = self.mark_preorder();
+        let dead_count = node_count - marking.len() - 2;
+        let reclaimed = if self.policy.should_collect(node_count, dead_count) {
+            self.compact(marking)
+        } else {
+            0
+        };
+        self.next_gc_check = self.policy.next_check(self.nodes.len());
+        reclaimed
+    }
+
+    /// Mark all nodes that are reachable from the registered roots. The result is the list of
+    /// live decision nodes in the DFS pre-order (roots are explored in the order of their slots).
+    fn mark_preorder(&self) -> Vec<NodeIndex> {
+        let mut visited = vec![false; self.nodes.len()];
+        let mut preorder = Vec::new();
+        let mut search_stack: Vec<NodeIndex> = Vec::new();
+        for root in self.roots.borrow().iter() {
+            search_stack.push(root);
+            while let Some(task) = search_stack.pop() {
+                if task.is_zero() || task.is_one() || visited[task.into_index()] {
+                    continue;
+                }
+                visited[task.into_index()] = true;
+                preorder.push(task);
+
+                let node = self.get_node(task);
+                search_stack.push(node.get_high_link());
+                search_stack.push(node.get_low_link());
+            }
+        }
+        preorder
+    }
+
+    /// Move the live nodes given in `preorder` to the beginning of the storage and update all
+    /// references. The first node in `preorder` gets the largest index.
+    fn compact(&mut self, preorder: Vec<NodeIndex>) -> usize {
+        let live_count = preorder.len();
+        let mut new_index = vec![NodeIndex::UNDEFINED; self.nodes.len()];
+        new_index[0] = NodeIndex::ZERO;
+        new_index[1] = NodeIndex::ONE;
+        for (order, old_index) in preorder.iter().enumerate() {
+            new_index[old_index.into_index()] = NodeIndex::from_index(live_count + 1 - order);
+        }
+
+        let mut new_nodes = vec![Node::ZERO; live_count + 2];
+        new_nodes[1] = Node::ONE;
+        for old_index in &preorder {
+            let (variable, low, high) = self.get_node(*old_index).unpack();
+            let low = new_index[low.into_index()];
+            let high = new_index[high.into_index()];
+            new_nodes[new_index[old_index.into_index()].into_index()] =
+                Node::pack(variable, low, high);
+        }
+
+        self.unique_table.clear();
+        for (index, node) in new_nodes.iter().enumerate().skip(2) {
+            self.unique_table.insert(node.clone(), NodeIndex::from_index(index));
+        }
+        self.roots.borrow_mut().remap(&new_index);
+
+        let reclaimed = self.nodes.len() - new_nodes.len();
+        self.nodes = new_nodes;
+        reclaimed
+    }
+}
+
+impl Default for BddPool {
+    fn default() -> Self {
+        BddPool::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BddPool, GcPolicy};
+    use crate::v4::core::{Bdd, Node, NodeIndex};
+    use std::convert::TryFrom;
+
+    // (x_1 & x_2), (x_1 | x_3) and (x_2 ^ x_3), where the last two share the x_3 decision node.
+    const AND_1_2: &str = "0,0,0|0,1,1|2,0,1|1,0,2|";
+    const OR_1_3: &str = "0,0,0|0,1,1|3,0,1|1,2,1|";
+    const XOR_2_3: &str = "0,0,0|0,1,1|3,0,1|3,1,0|2,2,3|";
+
+    fn bdd(data: &str) -> Bdd {
+        Bdd::try_from(data).unwrap().sort_preorder()
+    }
+
+    fn assert_same_nodes(left: &Bdd, right: &Bdd) {
+        let left: Vec<Node> = left.iter_indices().map(|i| left.get_node(i).clone()).collect();
+        let right: Vec<Node> = right.iter_indices().map(|i| right.get_node(i).clone()).collect();
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    pub fn pool_import_export() {
+        let mut pool = BddPool::with_policy(GcPolicy::DISABLED);
+        let and = pool.import(&bdd(AND_1_2));
+        let or = pool.import(&bdd(OR_1_3));
+        let xor = pool.import(&bdd(XOR_2_3));
+        let zero = pool.import(&Bdd::new_zero());
+        let one = pool.import(&Bdd::new_one());
+
+        assert_eq!(5, pool.root_count());
+        // Two terminals, two nodes for `and`, two for `or` and two extra nodes for `xor`.
+        assert_eq!(8, pool.node_count());
+        assert_eq!(NodeIndex::ZERO, pool.get_root_index(&zero));
+        assert_eq!(NodeIndex::ONE, pool.get_root_index(&one));
+
+        assert_same_nodes(&bdd(AND_1_2), &pool.export(&and));
+        assert_same_nodes(&bdd(OR_1_3), &pool.export(&or));
+        assert_same_nodes(&bdd(XOR_2_3), &pool.export(&xor));
+        assert!(pool.export(&zero).is_zero());
+        assert!(pool.export(&one).is_one());
+    }
+
+    #[test]
+    pub fn pool_garbage_collection() {
+        let mut pool = BddPool::with_policy(GcPolicy::DISABLED);
+        let and = pool.import(&bdd(AND_1_2));
+        let or = pool.import(&bdd(OR_1_3));
+        let xor = pool.import(&bdd(XOR_2_3));
+        let xor_copy = xor.clone();
+        assert_eq!(4, pool.root_count());
+        assert_eq!(0, pool.collect_garbage());
+
+        // The `xor` nodes are still referenced by the copy.
+        drop(xor);
+        assert_eq!(0, pool.collect_garbage());
+
+        // The `xor` root and the negated x_3 node are now dead, but x_3 is still shared.
+        drop(xor_copy);
+        assert_eq!(2, pool.collect_garbage());
+        assert_eq!(6, pool.node_count());
+        assert_same_nodes(&bdd(AND_1_2), &pool.export(&and));
+        assert_same_nodes(&bdd(OR_1_3), &pool.export(&or));
+
+        // The surviving nodes are sorted in pre-order with the first root at the end.
+        let root = pool.get_root_index(&and);
+        assert_eq!(NodeIndex::from(5), root);
+        assert_eq!(NodeIndex::from(4), pool.get_node(root).get_high_link());
+
+        drop(and);
+        drop(or);
+        assert_eq!(4, pool.collect_garbage());
+        assert_eq!(2, pool.node_count());
+        assert_eq!(0, pool.root_count());
+    }
+
+    #[test]
+    pub fn pool_gc_policy() {
+        let policy = GcPolicy {
+            min_nodes: 4,
+            dead_fraction: 0.5,
+        };
+        let mut pool = BddPool::with_policy(policy);
+        let xor = pool.import(&bdd(XOR_2_3));
+        let and = pool.import(&bdd(AND_1_2));
+        assert_eq!(7, pool.node_count());
+        drop(xor);
+        drop(and);
+        // Everything is dead now and the next import is a safe point.
+        let or = pool.import(&bdd(OR_1_3));
+        assert_eq!(4, pool.node_count());
+        assert_same_nodes(&bdd(OR_1_3), &pool.export(&or));
+    }
+}
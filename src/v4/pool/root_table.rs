@@ -0,0 +1,110 @@
+use super::super::core::NodeIndex;
+use crate::IntoIndex;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// A table of all BDD roots that are currently registered in a `BddPool`.
+///
+/// Every registered root occupies one slot which stores the current `NodeIndex` of the root.
+/// The slot itself never changes while the root is alive, but its value is rewritten whenever
+/// the garbage collector renumbers the nodes of the pool. Free slots are recycled using a simple
+/// free-list stack.
+pub(super) struct RootTable {
+    slots: Vec<NodeIndex>,
+    free_slots: Vec<usize>,
+}
+
+/// A reference-counted handle to a BDD that is stored in a `BddPool`.
+///
+/// The handle keeps the root (and hence every node reachable from it) alive. Once the handle
+/// is dropped, the root is unregistered and its nodes can be reclaimed by the next garbage
+/// collection. Cloning the handle registers a new root for the same node.
+///
+/// Note that the handle does not store the node index directly, since the garbage collector
+/// is free to move the nodes around. Use `BddPool::get_root_index` to obtain the current index.
+pub struct BddRoot {
+    slot: usize,
+    table: Rc<RefCell<RootTable>>,
+}
+
+impl RootTable {
+    pub fn new() -> RootTable {
+        RootTable {
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+        }
+    }
+
+    /// The number of roots that are currently registered.
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free_slots.len()
+    }
+
+    /// Register a new root, returning the slot in which it is stored.
+    pub fn register(&mut self, root: NodeIndex) -> usize {
+        debug_assert!(!root.is_undefined());
+        if let Some(slot) = self.free_slots.pop() {
+            self.slots[slot] = root;
+            slot
+        } else {
+            self.slots.push(root);
+            self.slots.len() - 1
+        }
+    }
+
+    /// Unregister the root in the given `slot`, making the slot available for reuse.
+    pub fn release(&mut self, slot: usize) {
+        debug_assert!(!self.slots[slot].is_undefined());
+        self.slots[slot] = NodeIndex::UNDEFINED;
+        self.free_slots.push(slot);
+    }
+
+    /// Read the current node index of the root in the given `slot`.
+    pub fn get(&self, slot: usize) -> NodeIndex {
+        self.slots[slot]
+    }
+
+    /// Iterate over the node indices of all registered roots.
+    pub fn iter(&self) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.slots.iter().cloned().filter(|it| !it.is_undefined())
+    }
+
+    /// Update all registered roots using the given `new_index` mapping.
+    pub fn remap(&mut self, new_index: &[NodeIndex]) {
+        for root in self.slots.iter_mut().filter(|it| !it.is_undefined()) {
+            *root = new_index[root.into_index()];
+        }
+    }
+}
+
+impl BddRoot {
+    pub(super) fn new(table: &Rc<RefCell<RootTable>>, root: NodeIndex) -> BddRoot {
+        let slot = table.borrow_mut().register(root);
+        BddRoot {
+            slot,
+            table: table.clone(),
+        }
+    }
+
+    /// True if this root belongs to the given root table.
+    pub(super) fn is_in(&self, table: &Rc<RefCell<RootTable>>) -> bool {
+        Rc::ptr_eq(&self.table, table)
+    }
+
+    /// The current node index of this root.
+    pub(super) fn get(&self) -> NodeIndex {
+        self.table.borrow().get(self.slot)
+    }
+}
+
+impl Clone for BddRoot {
+    fn clone(&self) -> Self {
+        BddRoot::new(&self.table, self.get())
+    }
+}
+
+impl Drop for BddRoot {
+    fn drop(&mut self) {
+        self.table.borrow_mut().release(self.slot);
+    }
+}
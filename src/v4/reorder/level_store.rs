@@ -0,0 +1,331 @@
+use super::super::core::{Bdd, Node, NodeIndex, Variable};
+use crate::{FromIndex, IntoIndex};
+use fxhash::FxHashMap;
+use std::collections::HashMap;
+
+/// A node storage for a collection of BDDs which share a variable order, where the nodes
+/// are indexed by their decision variable. This allows swapping two adjacent levels of the
+/// variable order in place, only touching the nodes of the two affected levels.
+///
+/// The store works with *variable positions* instead of actual variables: position `p` refers
+/// to the `p`-th smallest variable in the support of the imported BDDs. Positions are assigned
+/// to *levels* (level `0` is the top of the graph), and initially, every position has the same
+/// level. Once exported, level `l` is again represented by the `l`-th smallest variable, such that
+/// the results are valid `Bdd` objects.
+///
+/// Unlike `Bdd`, the node storage does not maintain any particular order of nodes. Instead,
+/// every node counts its references (from other nodes and from roots), and nodes which become
+/// unreachable are immediately deleted and their slots reused. As such, `size` is always
+/// the exact number of decision nodes in the shared graph.
+pub struct LevelStore {
+    variables: Vec<Variable>,
+    level_of: Vec<usize>,
+    at_level: Vec<usize>,
+    nodes: Vec<StoreNode>,
+    free_slots: Vec<NodeIndex>,
+    tables: Vec<FxHashMap<(NodeIndex, NodeIndex), NodeIndex>>,
+    roots: Vec<NodeIndex>,
+    size: usize,
+}
+
+#[derive(Clone)]
+struct StoreNode {
+    position: usize,
+    low: NodeIndex,
+    high: NodeIndex,
+    references: usize,
+}
+
+impl StoreNode {
+    /// Position value used by terminal nodes and free slots.
+    const NONE: usize = usize::MAX;
+}
+
+impl LevelStore {
+    /// Import the given BDDs into a new store. The BDDs must use the same variable order.
+    pub fn new(bdds: &[Bdd]) -> LevelStore {
+        let mut variables: Vec<Variable> = bdds
+            .iter()
+            .flat_map(|bdd| bdd.iter_indices().map(move |i| bdd.get_node(i)))
+            .filter(|node| !node.is_terminal())
+            .map(|node| node.get_variable())
+            .collect();
+        variables.sort();
+        variables.dedup();
+
+        let terminal = StoreNode {
+            position: StoreNode::NONE,
+            low: NodeIndex::UNDEFINED,
+            high: NodeIndex::UNDEFINED,
+            references: 0,
+        };
+        let mut store = LevelStore {
+            level_of: (0..variables.len()).collect(),
+            at_level: (0..variables.len()).collect(),
+            tables: vec![FxHashMap::default(); variables.len()],
+            nodes: vec![terminal.clone(), terminal],
+            free_slots: Vec::new(),
+            roots: Vec::with_capacity(bdds.len()),
+            size: 0,
+            variables,
+        };
+
+        let positions: HashMap<Variable, usize> = store
+            .variables
+            .iter()
+            .enumerate()
+            .map(|(p, v)| (*v, p))
+            .collect();
+        for bdd in bdds {
+            let root = store.import(bdd, &positions);
+            store.roots.push(root);
+        }
+        store
+    }
+
+    /// Copy the nodes of the `bdd` into this store and return the index of its root.
+    /// The root is already referenced.
+    fn import(&mut self, bdd: &Bdd, positions: &HashMap<Variable, usize>) -> NodeIndex {
+        let mut new_index = vec![NodeIndex::UNDEFINED; bdd.get_size().into_index()];
+        new_index[0] = NodeIndex::ZERO;
+        if bdd.get_size() > 1 {
+            new_index[1] = NodeIndex::ONE;
+        }
+        let mut stack = vec![(bdd.get_root_index(), false)];
+        while let Some((index, expanded)) = stack.pop() {
+            if !new_index[index.into_index()].is_undefined() {
+                continue;
+            }
+            let (variable, low, high) = bdd.get_node(index).unpack();
+            if expanded {
+                let low = new_index[low.into_index()];
+                let high = new_index[high.into_index()];
+                let node = self.ensure(positions[&variable], low, high);
+                new_index[index.into_index()] = node;
+            } else {
+                stack.push((index, true));
+                stack.push((high, false));
+                stack.push((low, false));
+            }
+        }
+
+        // During import, nodes are referenced by their parents only once. This is already
+        // accounted for in `ensure`, but the intermediate references must be dropped.
+        let root = new_index[bdd.get_root_index().into_index()];
+        self.reference(root);
+        for index in new_index.into_iter().skip(2) {
+            if !index.is_undefined() {
+                self.dereference(index);
+            }
+        }
+        root
+    }
+
+    /// The number of decision nodes in the store.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The number of levels (i.e. variables) in the store.
+    pub fn level_count(&self) -> usize {
+        self.variables.len()
+    }
+
+    /// The number of nodes which use the variable at the given `position`.
+    pub fn position_size(&self, position: usize) -> usize {
+        self.tables[position].len()
+    }
+
+    /// The level at which the variable `position` currently resides.
+    pub fn level_of(&self, position: usize) -> usize {
+        self.level_of[position]
+    }
+
+    /// The current variable order as a list of original variables (from top to bottom).
+    pub fn order(&self) -> Vec<Variable> {
+        self.at_level.iter().map(|p| self.variables[*p]).collect()
+    }
+
+    /// A mapping from the original variables to the variables that represent them in the
+    /// exported BDDs.
+    pub fn permutation(&self) -> HashMap<Variable, Variable> {
+        self.variables
+            .iter()
+            .enumerate()
+            .map(|(p, v)| (*v, self.variables[self.level_of[p]]))
+            .collect()
+    }
+
+    /// Swap the variables at `level` and `level + 1`.
+    ///
+    /// Nodes of the upper variable which do not depend on the lower variable are not modified.
+    /// The remaining nodes of the upper variable are rewritten in place to nodes of the lower
+    /// variable (so that the references to them remain valid), using new nodes of the upper
+    /// variable as children. Nodes of the lower variable that become unreachable are deleted.
+    pub fn swap(&mut self, level: usize) {
+        let x = self.at_level[level];
+        let y = self.at_level[level + 1];
+
+        let interacting: Vec<((NodeIndex, NodeIndex), NodeIndex)> = self.tables[x]
+            .iter()
+            .filter(|((low, high), _)| self.position(*low) == y || self.position(*high) == y)
+            .map(|(key, node)| (*key, *node))
+            .collect();
+
+        for ((low, high), node) in interacting {
+            self.tables[x].remove(&(low, high));
+            let (f00, f01) = self.cofactors(low, y);
+            let (f10, f11) = self.cofactors(high, y);
+            let new_low = self.ensure(x, f00, f10);
+            let new_high = self.ensure(x, f01, f11);
+            {
+                let node = &mut self.nodes[node.into_index()];
+                node.position = y;
+                node.low = new_low;
+                node.high = new_high;
+            }
+            self.tables[y].insert((new_low, new_high), node);
+            self.dereference(low);
+            self.dereference(high);
+        }
+
+        self.at_level.swap(level, level + 1);
+        self.level_of[x] = level + 1;
+        self.level_of[y] = level;
+    }
+
+    /// Export all BDDs in the store under the current variable order. Level `l` is represented
+    /// by the `l`-th smallest variable of the original support.
+    pub fn export(&self) -> Vec<Bdd> {
+        let nodes: Vec<Node> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| {
+                if i == 1 {
+                    Node::ONE
+                } else if node.position == StoreNode::NONE {
+                    Node::ZERO
+                } else {
+                    let variable = self.variables[self.level_of[node.position]];
+                    Node::pack(variable, node.low, node.high)
+                }
+            })
+            .collect();
+        self.roots
+            .iter()
+            .map(|root| Bdd::from_reachable_nodes(&nodes, *root))
+            .collect()
+    }
+
+    fn position(&self, index: NodeIndex) -> usize {
+        self.nodes[index.into_index()].position
+    }
+
+    /// The `(low, high)` cofactors of the node w.r.t. the variable at `position`.
+    fn cofactors(&self, index: NodeIndex, position: usize) -> (NodeIndex, NodeIndex) {
+        let node = &self.nodes[index.into_index()];
+        if node.position == position {
+            (node.low, node.high)
+        } else {
+            (index, index)
+        }
+    }
+
+    /// Find or create the node `(position, low, high)` and reference it. Returns `low`
+    /// (referenced) if the node would be redundant.
+    fn ensure(&mut self, position: usize, low: NodeIndex, high: NodeIndex) -> NodeIndex {
+        if low == high {
+            self.reference(low);
+            return low;
+        }
+        if let Some(node) = self.tables[position].get(&(low, high)).cloned() {
+            self.reference(node);
+            return node;
+        }
+        self.reference(low);
+        self.reference(high);
+        let node = StoreNode {
+            position,
+            low,
+            high,
+            references: 1,
+        };
+        let index = if let Some(slot) = self.free_slots.pop() {
+            self.nodes[slot.into_index()] = node;
+            slot
+        } else {
+            self.nodes.push(node);
+            NodeIndex::from_index(self.nodes.len() - 1)
+        };
+        self.tables[position].insert((low, high), index);
+        self.size += 1;
+        index
+    }
+
+    fn reference(&mut self, index: NodeIndex) {
+        if !(index.is_zero() || index.is_one()) {
+            self.nodes[index.into_index()].references += 1;
+        }
+    }
+
+    /// Remove one reference to the node, deleting it (and possibly its children) once it
+    /// is no longer referenced.
+    fn dereference(&mut self, index: NodeIndex) {
+        let mut stack = vec![index];
+        while let Some(index) = stack.pop() {
+            if index.is_zero() || index.is_one() {
+                continue;
+            }
+            let node = &mut self.nodes[index.into_index()];
+            node.references -= 1;
+            if node.references == 0 {
+                let (position, low, high) = (node.position, node.low, node.high);
+                node.position = StoreNode::NONE;
+                self.tables[position].remove(&(low, high));
+                self.free_slots.push(index);
+                self.size -= 1;
+                stack.push(low);
+                stack.push(high);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LevelStore;
+    use crate::v4::reorder::permute;
+    use crate::v4::test_util::nodes;
+    use crate::verify::{FunctionTable, Lcg};
+
+    #[test]
+    pub fn level_store_swap_preserves_functions() {
+        let mut rng = Lcg::new(7);
+        let bdds: Vec<_> = [20, 50, 80]
+            .iter()
+            .map(|density| FunctionTable::random(6, *density, &mut rng).to_bdd())
+            .collect();
+        let mut store = LevelStore::new(&bdds);
+        let initial_size = store.size();
+        assert_eq!(6, store.level_count());
+
+        for level in &[0, 3, 1, 4, 2, 0, 4] {
+            store.swap(*level);
+            let permutation = store.permutation();
+            for (bdd, exported) in bdds.iter().zip(store.export()) {
+                let expected = permute(bdd, &permutation).unwrap();
+                assert_eq!(nodes(&expected), nodes(&exported));
+            }
+        }
+
+        // Swapping back restores the original order and size.
+        for level in &[4, 0, 2, 4, 1, 3, 0] {
+            store.swap(*level);
+        }
+        assert_eq!(initial_size, store.size());
+        for (bdd, exported) in bdds.iter().zip(store.export()) {
+            assert_eq!(nodes(bdd), nodes(&exported));
+        }
+    }
+}
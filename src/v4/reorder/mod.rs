@@ -0,0 +1,9 @@
+//! Operations which change the variable ordering of a `Bdd`.
+
+mod level_store;
+mod permutation;
+mod sifting;
+
+pub use level_store::LevelStore;
+pub use permutation::{permute, permute_cancellable, PermutationError};
+pub use sifting::{sift, SiftingConfig, SiftingResult};
@@ -0,0 +1,378 @@
+use super::super::core::{Bdd, CancellationToken, Cancelled, Node, NodeIndex, Variable};
+use crate::{FromIndex, IntoIndex};
+use std::cmp::min;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+/// The reasons why `permute` can fail.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PermutationError {
+    /// The permutation is not injective on the variables of the `Bdd`, or it maps one of them
+    /// to an undefined variable. Contains a description of the problem.
+    InvalidPermutation(String),
+    /// The `cancellation` token was cancelled.
+    Cancelled,
+}
+
+impl Display for PermutationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PermutationError::InvalidPermutation(message) => write!(f, "{}", message),
+            PermutationError::Cancelled => write!(f, "{}", Cancelled),
+        }
+    }
+}
+
+impl From<Cancelled> for PermutationError {
+    fn from(_: Cancelled) -> Self {
+        PermutationError::Cancelled
+    }
+}
+
+/// Rebuild the given `Bdd` such that every variable `x` is replaced with `permutation[x]`.
+///
+/// Variables which do not appear in the `permutation` map are left unchanged. The permutation
+/// does not need to be monotone, but it must be injective on the variables that actually appear
+/// in the `Bdd`, otherwise `PermutationError::InvalidPermutation` is returned. The result is
+/// reduced and has an exact height.
+///
+/// If the permutation preserves the relative order of the variables in the `Bdd`, the nodes
+/// are simply relabelled. Otherwise, the `Bdd` is rebuilt bottom-up using a memoized
+/// if-then-else composition, which can be considerably slower.
+pub fn permute(
+    bdd: &Bdd,
+    permutation: &HashMap<Variable, Variable>,
+) -> Result<Bdd, PermutationError> {
+    permute_in(bdd, permutation, None)
+}
+
+/// The same as `permute`, but stops with `PermutationError::Cancelled` once the `cancellation`
+/// token is cancelled. The token is polled periodically while the `Bdd` is rebuilt.
+pub fn permute_cancellable(
+    bdd: &Bdd,
+    permutation: &HashMap<Variable, Variable>,
+    cancellation: &CancellationToken,
+) -> Result<Bdd, PermutationError> {
+    permute_in(bdd, permutation, Some(cancellation))
+}
+
+/// The number of steps of `rebuild` after which the cancellation token is polled.
+const CANCELLATION_CHECK_INTERVAL: u64 = 1 << 14;
+
+fn permute_in(
+    bdd: &Bdd,
+    permutation: &HashMap<Variable, Variable>,
+    cancellation: Option<&CancellationToken>,
+) -> Result<Bdd, PermutationError> {
+    if bdd.is_constant() {
+        return Ok(bdd.clone());
+    }
+    let rename = |variable: Variable| -> Variable {
+        permutation.get(&variable).cloned().unwrap_or(variable)
+    };
+
+    let mut support: Vec<Variable> = bdd
+        .iter_indices()
+        .map(|index| bdd.get_node(index))
+        .filter(|node| !node.is_terminal())
+        .map(|node| node.get_variable())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    support.sort();
+
+    let mut image = HashSet::with_capacity(support.len());
+    for variable in &support {
+        let renamed = rename(*variable);
+        if renamed.is_undefined() {
+            return Err(PermutationError::InvalidPermutation(format!(
+                "Variable {:?} is mapped to an undefined variable.",
+                variable
+            )));
+        }
+        if !image.insert(renamed) {
+            return Err(PermutationError::InvalidPermutation(format!(
+                "Permutation is not injective: multiple variables are mapped to {:?}.",
+                renamed
+            )));
+        }
+    }
+
+    let is_monotone = support
+        .windows(2)
+        .all(|pair| rename(pair[0]) < rename(pair[1]));
+    if is_monotone {
+        Ok(relabel(bdd, rename))
+    } else {
+        rebuild(bdd, rename, cancellation).map_err(PermutationError::from)
+    }
+}
+
+/// Replace the variables in the nodes of the `Bdd` without changing its structure.
+/// This is only valid if `rename` is monotone on the variables of the `Bdd`.
+fn relabel<F: Fn(Variable) -> Variable>(bdd: &Bdd, rename: F) -> Bdd {
+    let nodes = bdd
+        .iter_indices()
+        .map(|index| {
+            let node = bdd.get_node(index);
+            if node.is_terminal() {
+                node.clone()
+            } else {
+                let (variable, low, high) = node.unpack();
+                Node::pack(rename(variable), low, high)
+            }
+        })
+        .collect();
+    // Structure is unchanged, hence the height is unchanged as well.
+    unsafe { Bdd::from_raw_parts(bdd.get_height(), nodes) }
+}
+
+/// Rebuild the `Bdd` in post-order, such that every node is replaced by `ite(x, high, low)`
+/// where `x` is the renamed decision variable. All intermediate results share one node
+/// storage and one `ite` computed table, hence common sub-results are only built once.
+fn rebuild<F: Fn(Variable) -> Variable>(
+    bdd: &Bdd,
+    rename: F,
+    cancellation: Option<&CancellationToken>,
+) -> Result<Bdd, Cancelled> {
+    let mut compose = Compose::new(bdd.get_size().into_index(), cancellation);
+    let mut results = vec![NodeIndex::UNDEFINED; bdd.get_size().into_index()];
+    results[0] = NodeIndex::ZERO;
+    if results.len() > 1 {
+        results[1] = NodeIndex::ONE;
+    }
+
+    let root = bdd.get_root_index();
+    let mut stack: Vec<(NodeIndex, bool)> = vec![(root, false)];
+    while let Some((index, expanded)) = stack.pop() {
+        if !results[index.into_index()].is_undefined() {
+            continue;
+        }
+        let (variable, low, high) = bdd.get_node(index).unpack();
+        if !expanded {
+            stack.push((index, true));
+            stack.push((high, false));
+            stack.push((low, false));
+            continue;
+        }
+
+        let (low, high) = (results[low.into_index()], results[high.into_index()]);
+        results[index.into_index()] = compose.ite(rename(variable), high, low)?;
+    }
+
+    Ok(Bdd::from_reachable_nodes(&compose.nodes, results[root.into_index()]))
+}
+
+/// A node storage with a unique table and a computed table for `ite(x, high, low)`, where `x`
+/// is a single variable. The variable `x` must not appear in `high` or `low`, which holds
+/// for a renaming that is injective on the `Bdd`, since a variable appears at most once on
+/// every path.
+struct Compose<'a> {
+    nodes: Vec<Node>,
+    unique: HashMap<Node, NodeIndex>,
+    computed: HashMap<(Variable, NodeIndex, NodeIndex), NodeIndex>,
+    cancellation: Option<&'a CancellationToken>,
+    steps: u64,
+}
+
+impl<'a> Compose<'a> {
+    fn new(capacity: usize, cancellation: Option<&'a CancellationToken>) -> Compose<'a> {
+        Compose {
+            nodes: vec![Node::ZERO, Node::ONE],
+            unique: HashMap::with_capacity(capacity),
+            computed: HashMap::with_capacity(capacity),
+            cancellation,
+            steps: 0,
+        }
+    }
+
+    /// Compute `ite(variable, high, low)`. The sub-problems are expanded using an explicit
+    /// stack in post-order (the same as in `rebuild`), since the depth of the recursion is
+    /// only bounded by the height of `high` and `low`. The result of every expanded
+    /// sub-problem is saved in the computed table, where its parent can find it.
+    fn ite(
+        &mut self,
+        variable: Variable,
+        high: NodeIndex,
+        low: NodeIndex,
+    ) -> Result<NodeIndex, Cancelled> {
+        let mut stack: Vec<(NodeIndex, NodeIndex, bool)> = vec![(high, low, false)];
+        while let Some((high, low, expanded)) = stack.pop() {
+            self.steps += 1;
+            if self.steps.is_multiple_of(CANCELLATION_CHECK_INTERVAL) {
+                if let Some(cancellation) = self.cancellation {
+                    cancellation.check()?;
+                }
+            }
+            if !expanded {
+                if self.resolve(variable, high, low).is_none() {
+                    let (_, (high_low, high_high), (low_low, low_high)) = self.cofactors(high, low);
+                    stack.push((high, low, true));
+                    stack.push((high_high, low_high, false));
+                    stack.push((high_low, low_low, false));
+                }
+                continue;
+            }
+
+            let (top, (high_low, high_high), (low_low, low_high)) = self.cofactors(high, low);
+            let result_low = self.resolve(variable, high_low, low_low);
+            let result_high = self.resolve(variable, high_high, low_high);
+            // Both sub-problems were on the stack above this one, hence they are resolved.
+            let result = self.make(top, result_low.unwrap(), result_high.unwrap());
+            self.computed.insert((variable, high, low), result);
+        }
+        Ok(self.resolve(variable, high, low).unwrap())
+    }
+
+    /// The result of `ite(variable, high, low)` if it is trivial or already computed.
+    fn resolve(
+        &mut self,
+        variable: Variable,
+        high: NodeIndex,
+        low: NodeIndex,
+    ) -> Option<NodeIndex> {
+        if high == low {
+            return Some(high);
+        }
+        // Terminals have an undefined variable, which is larger than any other variable.
+        let top = min(
+            self.nodes[high.into_index()].get_variable(),
+            self.nodes[low.into_index()].get_variable(),
+        );
+        if variable < top {
+            return Some(self.make(variable, low, high));
+        }
+        self.computed.get(&(variable, high, low)).cloned()
+    }
+
+    /// The smaller of the two root variables of `high` and `low`, together with the
+    /// `(low, high)` cofactors of `high` and `low` w.r.t. this variable.
+    fn cofactors(
+        &self,
+        high: NodeIndex,
+        low: NodeIndex,
+    ) -> (Variable, (NodeIndex, NodeIndex), (NodeIndex, NodeIndex)) {
+        let (high_var, high_low, high_high) = self.nodes[high.into_index()].unpack();
+        let (low_var, low_low, low_high) = self.nodes[low.into_index()].unpack();
+        let top = min(high_var, low_var);
+        let high_cofactors = if high_var == top {
+            (high_low, high_high)
+        } else {
+            (high, high)
+        };
+        let low_cofactors = if low_var == top {
+            (low_low, low_high)
+        } else {
+            (low, low)
+        };
+        (top, high_cofactors, low_cofactors)
+    }
+
+    fn make(&mut self, variable: Variable, low: NodeIndex, high: NodeIndex) -> NodeIndex {
+        if low == high {
+            return low;
+        }
+        let node = Node::pack(variable, low, high);
+        let nodes = &mut self.nodes;
+        *self.unique.entry(node.clone()).or_insert_with(|| {
+            nodes.push(node);
+            NodeIndex::from_index(nodes.len() - 1)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{permute, permute_cancellable, PermutationError};
+    use crate::v4::core::{Bdd, CancellationToken, Variable};
+    use crate::v4::test_util::nodes;
+    use crate::verify::{FunctionTable, Lcg};
+    use std::collections::HashMap;
+
+    /// Permute the variables of a truth table such that variable `i` becomes `permutation[i]`.
+    fn permute_table(table: &FunctionTable, permutation: &[u32]) -> FunctionTable {
+        let variables = table.variables();
+        FunctionTable::from_fn(variables, |new_row| {
+            let mut old_row = 0;
+            for (old_var, new_var) in permutation.iter().enumerate() {
+                let bit = (new_row >> (variables - 1 - new_var)) & 1;
+                old_row |= bit << (variables as usize - 1 - old_var);
+            }
+            table.get(old_row)
+        })
+    }
+
+    fn to_map(permutation: &[u32]) -> HashMap<Variable, Variable> {
+        permutation
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (Variable::from(i as u32), Variable::from(*p)))
+            .collect()
+    }
+
+    #[test]
+    pub fn permute_matches_truth_table() {
+        let variables = 6;
+        let permutations: [[u32; 6]; 4] = [
+            [0, 1, 2, 3, 4, 5],
+            [5, 4, 3, 2, 1, 0],
+            [2, 0, 5, 1, 3, 4],
+            [1, 2, 3, 4, 5, 0],
+        ];
+        let mut rng = Lcg::new(42);
+        for density in &[10, 50, 90] {
+            let table = FunctionTable::random(variables, *density, &mut rng);
+            let bdd = table.to_bdd();
+            for permutation in &permutations {
+                let expected = permute_table(&table, permutation).to_bdd();
+                let actual = permute(&bdd, &to_map(permutation)).unwrap();
+                assert_eq!(nodes(&expected), nodes(&actual));
+                assert_eq!(expected.get_height(), actual.get_height());
+            }
+        }
+    }
+
+    #[test]
+    pub fn permute_monotone_and_constants() {
+        let bdd = FunctionTable::from_fn(4, |i| i % 3 == 0).to_bdd();
+        // Shift all variables by ten, which preserves their order.
+        let shift: HashMap<Variable, Variable> = (0..4)
+            .map(|i| (Variable::from(i), Variable::from(i + 10)))
+            .collect();
+        let shifted = permute(&bdd, &shift).unwrap();
+        assert_eq!(bdd.get_size(), shifted.get_size());
+        assert_eq!(bdd.get_height(), shifted.get_height());
+        assert_eq!(Variable::from(10), shifted.get_root_node().get_variable());
+
+        assert!(permute(&Bdd::new_zero(), &shift).unwrap().is_zero());
+        assert!(permute(&Bdd::new_one(), &shift).unwrap().is_one());
+    }
+
+    #[test]
+    pub fn permute_rejects_non_injective() {
+        let bdd = FunctionTable::from_fn(3, |i| i % 3 == 0).to_bdd();
+        let mut collapse = HashMap::new();
+        collapse.insert(Variable::from(0), Variable::from(1));
+        let error = permute(&bdd, &collapse).map(|_| ());
+        assert!(matches!(error, Err(PermutationError::InvalidPermutation(_))));
+        // Variables outside of the support are irrelevant.
+        let mut unused = HashMap::new();
+        unused.insert(Variable::from(7), Variable::from(1));
+        unused.insert(Variable::from(1), Variable::from(8));
+        assert!(permute(&bdd, &unused).is_ok());
+    }
+
+    #[test]
+    pub fn permute_cancellation() {
+        let mut rng = Lcg::new(11);
+        let bdd = FunctionTable::random(16, 50, &mut rng).to_bdd();
+        let reverse = to_map(&(0..16).rev().collect::<Vec<u32>>());
+        let token = CancellationToken::new();
+        let expected = permute(&bdd, &reverse).unwrap();
+        let actual = permute_cancellable(&bdd, &reverse, &token).unwrap();
+        assert_eq!(nodes(&expected), nodes(&actual));
+        token.cancel();
+        let cancelled = permute_cancellable(&bdd, &reverse, &token);
+        assert_eq!(Err(PermutationError::Cancelled), cancelled.map(|_| ()));
+    }
+}
@@ -0,0 +1,206 @@
+use super::super::core::{Bdd, Variable};
+use super::level_store::LevelStore;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Limits of the sifting algorithm.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SiftingConfig {
+    /// While a variable is being moved in one direction, the movement stops once the number
+    /// of nodes grows above `max_growth` times the size before the variable was moved.
+    pub max_growth: f64,
+    /// The movement also stops once the number of nodes grows above this absolute limit.
+    pub max_nodes: usize,
+    /// Once this time runs out, no more variables are sifted. The variable that is being moved
+    /// is still placed at the best position found so far, so the limit can be slightly exceeded.
+    pub time_limit: Option<Duration>,
+}
+
+/// The output of the sifting algorithm.
+pub struct SiftingResult {
+    /// The input BDDs rebuilt under the new variable order. This is the same as applying
+    /// `permute` with `permutation` to every input BDD.
+    pub bdds: Vec<Bdd>,
+    /// The new order of the original variables (from the top of the graph to the bottom).
+    pub order: Vec<Variable>,
+    /// For every variable of the input, the variable that represents it in the result. The
+    /// `i`-th variable of `order` is represented by the `i`-th smallest variable of the input.
+    pub permutation: HashMap<Variable, Variable>,
+    /// The number of decision nodes shared by all input BDDs.
+    pub initial_size: usize,
+    /// The number of decision nodes shared by all output BDDs.
+    pub final_size: usize,
+    /// False if sifting was interrupted by the time limit.
+    pub is_complete: bool,
+}
+
+/// By default, a variable is not moved further once the graph grows by 20%, and there
+/// is no time or absolute size limit.
+impl Default for SiftingConfig {
+    fn default() -> Self {
+        SiftingConfig {
+            max_growth: 1.2,
+            max_nodes: usize::MAX,
+            time_limit: None,
+        }
+    }
+}
+
+/// Find a better variable order for the given BDDs using Rudell's sifting algorithm.
+///
+/// The BDDs must share the same variable order. The sifting considers the variables one by
+/// one, starting with the variables that have the most nodes. Each variable is moved using
+/// adjacent level swaps towards the closer end of the order, then to the other end, and finally
+/// back to the position where the shared graph was the smallest. Moving in one direction stops
+/// early once the graph grows beyond the limits given by the `config`.
+///
+/// Since a `Bdd` stores variables in ascending order, the result uses the same set of variables
+/// as the input, but the variables are reassigned as described by `SiftingResult::permutation`.
+pub fn sift(bdds: &[Bdd], config: &SiftingConfig) -> SiftingResult {
+    let start = Instant::now();
+    let is_timed_out = || config.time_limit.map(|limit| start.elapsed() >= limit).unwrap_or(false);
+
+    let mut store = LevelStore::new(bdds);
+    let initial_size = store.size();
+    let level_count = store.level_count();
+
+    let mut positions: Vec<usize> = (0..level_count).collect();
+    positions.sort_by_key(|p| std::cmp::Reverse(store.position_size(*p)));
+
+    let mut is_complete = true;
+    for position in positions {
+        if is_timed_out() {
+            is_complete = false;
+            break;
+        }
+
+        let size_limit = ((store.size() as f64) * config.max_growth) as usize;
+        let size_limit = size_limit.min(config.max_nodes);
+        let mut level = store.level_of(position);
+        let mut best_level = level;
+        let mut best_size = store.size();
+
+        let down_first = level_count - 1 - level < level;
+        for &down in &[down_first, !down_first] {
+            loop {
+                if (down && level + 1 >= level_count) || (!down && level == 0) {
+                    break;
+                }
+                if is_timed_out() {
+                    is_complete = false;
+                    break;
+                }
+                if down {
+                    store.swap(level);
+                    level += 1;
+                } else {
+                    store.swap(level - 1);
+                    level -= 1;
+                }
+                if store.size() < best_size {
+                    best_size = store.size();
+                    best_level = level;
+                }
+                if store.size() > size_limit {
+                    break;
+                }
+            }
+        }
+
+        while level < best_level {
+            store.swap(level);
+            level += 1;
+        }
+        while level > best_level {
+            store.swap(level - 1);
+            level -= 1;
+        }
+    }
+
+    SiftingResult {
+        bdds: store.export(),
+        order: store.order(),
+        permutation: store.permutation(),
+        initial_size,
+        final_size: store.size(),
+        is_complete,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{sift, SiftingConfig};
+    use crate::v4::apply::{apply, And, Or};
+    use crate::v4::core::{Bdd, Variable};
+    use crate::v4::reorder::permute;
+    use crate::v4::test_util::nodes;
+    use crate::verify::{FunctionTable, Lcg};
+    use std::time::Duration;
+
+    /// Build `x_0 & x_n | x_1 & x_{n+1} | ...`, which is exponential in the default order,
+    /// but linear once the pairs of variables are adjacent.
+    fn interleaved_pairs(n: u32) -> Bdd {
+        let mut result = Bdd::new_zero();
+        for i in 0..n {
+            let left = Bdd::new_literal(Variable::from(i), true);
+            let right = Bdd::new_literal(Variable::from(i + n), true);
+            let (pair, _) = apply(&left, &right, And);
+            result = apply(&result, &pair, Or).0;
+        }
+        result
+    }
+
+    #[test]
+    pub fn sifting_reduces_interleaved_pairs() {
+        let bdd = interleaved_pairs(4);
+        let result = sift(std::slice::from_ref(&bdd), &SiftingConfig::default());
+        assert!(result.is_complete);
+        assert_eq!(bdd.get_size() as usize - 2, result.initial_size);
+        assert_eq!(8, result.final_size);
+        assert_eq!(result.final_size + 2, result.bdds[0].get_size() as usize);
+        let expected = permute(&bdd, &result.permutation).unwrap();
+        assert_eq!(nodes(&expected), nodes(&result.bdds[0]));
+        assert_eq!(8, result.order.len());
+    }
+
+    #[test]
+    pub fn sifting_preserves_functions() {
+        let mut rng = Lcg::new(99);
+        let bdds: Vec<Bdd> = [10, 40, 70]
+            .iter()
+            .map(|density| FunctionTable::random(7, *density, &mut rng).to_bdd())
+            .collect();
+        let result = sift(&bdds, &SiftingConfig::default());
+        assert!(result.final_size <= result.initial_size);
+        for (bdd, sifted) in bdds.iter().zip(&result.bdds) {
+            let expected = permute(bdd, &result.permutation).unwrap();
+            assert_eq!(nodes(&expected), nodes(sifted));
+        }
+    }
+
+    #[test]
+    pub fn sifting_respects_limits() {
+        let bdd = interleaved_pairs(4);
+        let config = SiftingConfig {
+            time_limit: Some(Duration::from_secs(0)),
+            ..SiftingConfig::default()
+        };
+        let result = sift(std::slice::from_ref(&bdd), &config);
+        assert!(!result.is_complete);
+        assert_eq!(nodes(&bdd), nodes(&result.bdds[0]));
+
+        // With no allowed growth, the result still cannot be worse than the input.
+        let config = SiftingConfig {
+            max_growth: 1.0,
+            max_nodes: 0,
+            time_limit: None,
+        };
+        let result = sift(&[bdd], &config);
+        assert!(result.is_complete);
+        assert!(result.final_size <= result.initial_size);
+
+        let result = sift(&[Bdd::new_one(), Bdd::new_zero()], &SiftingConfig::default());
+        assert!(result.bdds[0].is_one() && result.bdds[1].is_zero());
+        assert!(result.order.is_empty());
+    }
+}
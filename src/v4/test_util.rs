@@ -0,0 +1,9 @@
+//! Utility functions for comparing BDDs in tests. Test inputs are generated by the
+//! `crate::verify` module.
+
+use super::core::{Bdd, Node};
+
+/// Copy the nodes of the given `Bdd` into a vector, such that they can be compared.
+pub fn nodes(bdd: &Bdd) -> Vec<Node> {
+    bdd.iter_indices().map(|i| bdd.get_node(i).clone()).collect()
+}
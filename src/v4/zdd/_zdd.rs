@@ -0,0 +1,246 @@
+use super::super::core::{Bdd, Node, NodeIndex, Variable};
+use super::node_table::NodeTable;
+use crate::IntoIndex;
+
+/// A `Zdd` is a zero-suppressed decision diagram representing a family of sets of variables.
+///
+/// A `Zdd` uses exactly the same graph layout as a `Bdd` (terminals first, root last, with the
+/// same notion of height). Only the meaning of the graph differs: The terminal `0` is the empty
+/// family, and the terminal `1` is the family which contains only the empty set. A path to the
+/// `1` terminal describes a set which contains exactly the variables of the nodes where
+/// the path follows the high edge.
+///
+/// The graph is reduced using the *zero-suppression* rule: There is no node whose high edge
+/// points to the `0` terminal. Consequently, variables which do not appear on a path are
+/// *not* part of the set described by it (unlike in a `Bdd`, where they can take any value).
+#[derive(Clone)]
+pub struct Zdd(Bdd);
+
+/// Basic operations for examining the contents of a ZDD.
+impl Zdd {
+    /// Create a ZDD representing the empty family of sets.
+    pub fn new_empty() -> Zdd {
+        Zdd(Bdd::new_zero())
+    }
+
+    /// Create a ZDD representing the family which only contains the empty set.
+    pub fn new_unit() -> Zdd {
+        Zdd(Bdd::new_one())
+    }
+
+    /// Create a ZDD representing the family which only contains the given set.
+    pub fn new_set(set: &[Variable]) -> Zdd {
+        let mut set = set.to_vec();
+        set.sort();
+        set.dedup();
+        let mut table = NodeTable::new(true);
+        let mut root = NodeIndex::ONE;
+        for variable in set.into_iter().rev() {
+            root = table.ensure(variable, NodeIndex::ZERO, root);
+        }
+        Zdd(table.export(root))
+    }
+
+    /// Wrap a reduced graph into a ZDD.
+    pub(super) fn from_graph(graph: Bdd) -> Zdd {
+        Zdd(graph)
+    }
+
+    /// Upper bound on the height of the ZDD graph.
+    #[inline]
+    pub fn get_height(&self) -> u32 {
+        self.0.get_height()
+    }
+
+    /// The number of nodes in the ZDD graph.
+    #[inline]
+    pub fn get_size(&self) -> u64 {
+        self.0.get_size()
+    }
+
+    /// The index of the graph root node.
+    #[inline]
+    pub fn get_root_index(&self) -> NodeIndex {
+        self.0.get_root_index()
+    }
+
+    /// Get a reference to a `Node` using the given `index`.
+    #[inline]
+    pub fn get_node(&self, index: NodeIndex) -> &Node {
+        self.0.get_node(index)
+    }
+
+    /// True if the ZDD represents the empty family.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    /// True if the ZDD represents the family which only contains the empty set.
+    #[inline]
+    pub fn is_unit(&self) -> bool {
+        self.0.is_one()
+    }
+
+    /// Copy of all nodes of this ZDD (including unreachable nodes).
+    pub(super) fn nodes(&self) -> Vec<Node> {
+        self.0.iter_indices().map(|i| self.0.get_node(i).clone()).collect()
+    }
+
+    /// True if the given `set` is a member of this family.
+    pub fn contains(&self, set: &[Variable]) -> bool {
+        let mut set = set.to_vec();
+        set.sort();
+        set.dedup();
+        let mut set = set.into_iter().peekable();
+        let mut node = self.get_root_index();
+        while !(node.is_zero() || node.is_one()) {
+            let (variable, low, high) = self.get_node(node).unpack();
+            match set.peek() {
+                // The set contains a variable that does not appear on this path.
+                Some(next) if *next < variable => return false,
+                Some(next) if *next == variable => {
+                    set.next();
+                    node = high;
+                }
+                _ => node = low,
+            }
+        }
+        node.is_one() && set.next().is_none()
+    }
+
+    /// The number of sets in this family, or `None` if the number does not fit into `u128`.
+    pub fn count(&self) -> Option<u128> {
+        let mut counts: Vec<Option<u128>> = vec![None; self.get_size().into_index()];
+        counts[0] = Some(0);
+        if counts.len() > 1 {
+            counts[1] = Some(1);
+        }
+        let mut stack = vec![(self.get_root_index(), false)];
+        while let Some((index, expanded)) = stack.pop() {
+            if counts[index.into_index()].is_some() {
+                continue;
+            }
+            let (_, low, high) = self.get_node(index).unpack();
+            if expanded {
+                let low = counts[low.into_index()].unwrap();
+                let high = counts[high.into_index()].unwrap();
+                counts[index.into_index()] = Some(low.checked_add(high)?);
+            } else {
+                stack.push((index, true));
+                stack.push((high, false));
+                stack.push((low, false));
+            }
+        }
+        counts[self.get_root_index().into_index()]
+    }
+}
+
+/// Unary operations with respect to a single variable.
+impl Zdd {
+    /// The sets which contain `variable`, with `variable` removed from them.
+    pub fn onset(&self, variable: Variable) -> Zdd {
+        self.transform(variable, Transformation::Onset)
+    }
+
+    /// The sets which do not contain `variable`.
+    pub fn offset(&self, variable: Variable) -> Zdd {
+        self.transform(variable, Transformation::Offset)
+    }
+
+    /// Toggle the membership of `variable` in every set of the family.
+    pub fn change(&self, variable: Variable) -> Zdd {
+        self.transform(variable, Transformation::Change)
+    }
+
+    fn transform(&self, variable: Variable, transformation: Transformation) -> Zdd {
+        let mut table = NodeTable::from_nodes(&self.nodes(), true);
+        let mut results = vec![NodeIndex::UNDEFINED; self.get_size().into_index()];
+
+        let mut stack = vec![(self.get_root_index(), false)];
+        while let Some((index, expanded)) = stack.pop() {
+            if !results[index.into_index()].is_undefined() {
+                continue;
+            }
+            let (node_variable, low, high) = self.get_node(index).unpack();
+            let result = if node_variable > variable {
+                // Also applies to terminals. The variable is not present in any set.
+                match transformation {
+                    Transformation::Onset => NodeIndex::ZERO,
+                    Transformation::Offset => index,
+                    Transformation::Change => table.ensure(variable, NodeIndex::ZERO, index),
+                }
+            } else if node_variable == variable {
+                match transformation {
+                    Transformation::Onset => high,
+                    Transformation::Offset => low,
+                    Transformation::Change => table.ensure(variable, high, low),
+                }
+            } else if expanded {
+                let low = results[low.into_index()];
+                let high = results[high.into_index()];
+                table.ensure(node_variable, low, high)
+            } else {
+                stack.push((index, true));
+                stack.push((high, false));
+                stack.push((low, false));
+                continue;
+            };
+            results[index.into_index()] = result;
+        }
+
+        Zdd(table.export(results[self.get_root_index().into_index()]))
+    }
+}
+
+#[derive(Copy, Clone)]
+enum Transformation {
+    Onset,
+    Offset,
+    Change,
+}
+
+#[cfg(test)]
+mod test {
+    use super::Zdd;
+    use crate::v4::core::Variable;
+
+    fn vars(set: &[u32]) -> Vec<Variable> {
+        set.iter().map(|v| Variable::from(*v)).collect()
+    }
+
+    #[test]
+    pub fn zdd_basic_properties() {
+        assert_eq!(Some(0), Zdd::new_empty().count());
+        assert_eq!(Some(1), Zdd::new_unit().count());
+        assert!(Zdd::new_unit().contains(&[]));
+        assert!(!Zdd::new_empty().contains(&[]));
+
+        let set = Zdd::new_set(&vars(&[4, 1, 7]));
+        assert_eq!(5, set.get_size());
+        assert_eq!(4, set.get_height());
+        assert_eq!(Some(1), set.count());
+        assert!(set.contains(&vars(&[1, 4, 7])));
+        assert!(!set.contains(&vars(&[1, 4])));
+        assert!(!set.contains(&vars(&[1, 4, 7, 9])));
+        assert!(!set.contains(&vars(&[0, 1, 4, 7])));
+    }
+
+    #[test]
+    pub fn zdd_unary_operations() {
+        let set = Zdd::new_set(&vars(&[1, 4, 7]));
+
+        let onset = set.onset(Variable::from(4));
+        assert!(onset.contains(&vars(&[1, 7])) && onset.count() == Some(1));
+        assert!(set.onset(Variable::from(5)).is_empty());
+        assert!(set.offset(Variable::from(4)).is_empty());
+        assert!(set.offset(Variable::from(5)).contains(&vars(&[1, 4, 7])));
+
+        let changed = set.change(Variable::from(4));
+        assert!(changed.contains(&vars(&[1, 7])) && changed.count() == Some(1));
+        let changed = set.change(Variable::from(0)).change(Variable::from(9));
+        assert!(changed.contains(&vars(&[0, 1, 4, 7, 9])));
+        assert!(Zdd::new_empty().change(Variable::from(3)).is_empty());
+        assert!(Zdd::new_unit().change(Variable::from(3)).contains(&vars(&[3])));
+    }
+}
@@ -0,0 +1,128 @@
+use super::super::core::{Bdd, Node, NodeIndex, Variable};
+use super::node_table::NodeTable;
+use super::Zdd;
+use crate::IntoIndex;
+use fxhash::FxHashMap;
+
+/// Conversion between `Zdd` and `Bdd` with respect to a variable universe.
+///
+/// A `Bdd` over a universe describes a family of sets by its satisfying assignments: Each
+/// assignment corresponds to the set of variables which are `true` in it. Both conversions
+/// require the `universe` to be sorted, without duplicates, and to contain every variable
+/// that appears in the converted diagram.
+impl Zdd {
+    /// Create a `Zdd` representing the family of sets described by the `bdd`.
+    pub fn from_bdd(bdd: &Bdd, universe: &[Variable]) -> Result<Zdd, String> {
+        let nodes: Vec<Node> = bdd.iter_indices().map(|i| bdd.get_node(i).clone()).collect();
+        let root = convert(&nodes, bdd.get_root_index(), universe, false)?;
+        Ok(Zdd::from_graph(root))
+    }
+
+    /// Create a `Bdd` describing the family of sets represented by this `Zdd`.
+    pub fn to_bdd(&self, universe: &[Variable]) -> Result<Bdd, String> {
+        convert(&self.nodes(), self.get_root_index(), universe, true)
+    }
+}
+
+/// Convert a diagram between the two reduction rules. If `zero_suppressed` is true, the input
+/// is a ZDD and the output is a BDD, otherwise the other way around.
+///
+/// Every node of the output corresponds to a pair of an input node and a position in the
+/// universe, since the output may need to test variables that are skipped in the input.
+fn convert(nodes: &[Node], root: NodeIndex, universe: &[Variable], zero_suppressed: bool) -> Result<Bdd, String> {
+    if universe.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err("Variable universe must be sorted and without duplicates.".to_string());
+    }
+    let mut levels: FxHashMap<Variable, usize> = FxHashMap::default();
+    for (level, variable) in universe.iter().enumerate() {
+        levels.insert(*variable, level);
+    }
+    for node in nodes.iter().filter(|node| !node.is_terminal()) {
+        if !levels.contains_key(&node.get_variable()) {
+            return Err(format!("Variable {:?} is not in the universe.", node.get_variable()));
+        }
+    }
+
+    let mut table = NodeTable::new(!zero_suppressed);
+    let mut results: FxHashMap<(NodeIndex, usize), NodeIndex> = FxHashMap::default();
+    let root_task = (root, 0);
+    let mut stack = vec![(root_task, false)];
+    while let Some((task, expanded)) = stack.pop() {
+        if results.contains_key(&task) {
+            continue;
+        }
+        let (index, level) = task;
+        if level == universe.len() {
+            // Every node must be a terminal at this point.
+            results.insert(task, index);
+            continue;
+        }
+
+        let variable = universe[level];
+        let (node_variable, low, high) = nodes[index.into_index()].unpack();
+        let (low, high) = if node_variable == variable {
+            (low, high)
+        } else if zero_suppressed {
+            (index, NodeIndex::ZERO)
+        } else {
+            (index, index)
+        };
+        let low_task = (low, level + 1);
+        let high_task = (high, level + 1);
+        if expanded {
+            let result = table.ensure(variable, results[&low_task], results[&high_task]);
+            results.insert(task, result);
+        } else {
+            stack.push((task, true));
+            stack.push((high_task, false));
+            stack.push((low_task, false));
+        }
+    }
+
+    Ok(table.export(results[&root_task]))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::v4::core::{Bdd, Variable};
+    use crate::v4::test_util::nodes;
+    use crate::verify::{FunctionTable, Lcg};
+    use crate::v4::zdd::Zdd;
+
+    #[test]
+    pub fn zdd_bdd_conversion() {
+        let universe: Vec<Variable> = (0..6).map(Variable::from).collect();
+        let mut rng = Lcg::new(11);
+        for density in &[0, 5, 50, 95, 100] {
+            let table = FunctionTable::random(6, *density, &mut rng);
+            let bdd = table.to_bdd();
+            let zdd = Zdd::from_bdd(&bdd, &universe).unwrap();
+            let count = (0..64).filter(|row| table.get(*row)).count();
+            assert_eq!(count as u128, zdd.count().unwrap());
+            for row in 0..64u64 {
+                // Variable 0 is the most significant bit of the row.
+                let set: Vec<Variable> = (0..6)
+                    .filter(|v| (row >> (5 - v)) & 1 == 1)
+                    .map(Variable::from)
+                    .collect();
+                assert_eq!(table.get(row), zdd.contains(&set));
+            }
+            assert_eq!(nodes(&bdd), nodes(&zdd.to_bdd(&universe).unwrap()));
+        }
+    }
+
+    #[test]
+    pub fn zdd_conversion_universe() {
+        let set = Zdd::new_set(&[Variable::from(2), Variable::from(5)]);
+        let universe: Vec<Variable> = (0..8).map(Variable::from).collect();
+        let bdd = set.to_bdd(&universe).unwrap();
+        // All eight variables are fixed, so the BDD is one path.
+        assert_eq!(8 + 2, bdd.get_size());
+        assert_eq!(set.get_size(), Zdd::from_bdd(&bdd, &universe).unwrap().get_size());
+
+        assert!(set.to_bdd(&universe[..4]).is_err());
+        assert!(set.to_bdd(&[Variable::from(5), Variable::from(2)]).is_err());
+        let one = Zdd::from_bdd(&Bdd::new_one(), &universe[..3]).unwrap();
+        assert_eq!(Some(8), one.count());
+    }
+}
@@ -0,0 +1,12 @@
+//! Zero-suppressed decision diagrams (ZDDs) for representing families of sets.
+//!
+//! ZDDs reuse the core data structures of the BDD implementation (`Node`, `NodeIndex`,
+//! `Variable` and the graph layout of `Bdd`), as well as the caches used by `apply`.
+//! Only the reduction rule and the interpretation of the graph are different.
+
+mod _zdd;
+mod conversion;
+mod node_table;
+mod set_operations;
+
+pub use _zdd::Zdd;
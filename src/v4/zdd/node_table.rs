@@ -0,0 +1,62 @@
+use super::super::core::{Bdd, Node, NodeIndex, Variable};
+use crate::FromIndex;
+use fxhash::FxHashMap;
+
+/// A simple unique table for building diagrams node by node, either using the BDD reduction
+/// rule (nodes with equal children are removed), or the ZDD reduction rule (nodes with the high
+/// child pointing to the zero terminal are removed).
+pub(super) struct NodeTable {
+    nodes: Vec<Node>,
+    table: FxHashMap<Node, NodeIndex>,
+    zero_suppressed: bool,
+}
+
+impl NodeTable {
+    /// Create a table which only contains the two terminal nodes.
+    pub fn new(zero_suppressed: bool) -> NodeTable {
+        NodeTable {
+            nodes: vec![Node::ZERO, Node::ONE],
+            table: FxHashMap::default(),
+            zero_suppressed,
+        }
+    }
+
+    /// Create a table which already contains the given nodes at their current positions.
+    /// The nodes must be reduced with respect to the selected reduction rule.
+    pub fn from_nodes(nodes: &[Node], zero_suppressed: bool) -> NodeTable {
+        let mut result = NodeTable::new(zero_suppressed);
+        result.nodes = nodes.to_vec();
+        if result.nodes.len() < 2 {
+            result.nodes.push(Node::ONE);
+        }
+        for (i, node) in result.nodes.iter().enumerate().skip(2) {
+            result.table.insert(node.clone(), NodeIndex::from_index(i));
+        }
+        result
+    }
+
+    /// Get the index of the reduced node `(variable, low, high)`.
+    pub fn ensure(&mut self, variable: Variable, low: NodeIndex, high: NodeIndex) -> NodeIndex {
+        let is_redundant = if self.zero_suppressed {
+            high.is_zero()
+        } else {
+            low == high
+        };
+        if is_redundant {
+            return low;
+        }
+        let nodes = &mut self.nodes;
+        *self
+            .table
+            .entry(Node::pack(variable, low, high))
+            .or_insert_with_key(|node| {
+                nodes.push(node.clone());
+                NodeIndex::from_index(nodes.len() - 1)
+            })
+    }
+
+    /// Create a diagram from the nodes of this table reachable from `root`.
+    pub fn export(&self, root: NodeIndex) -> Bdd {
+        Bdd::from_reachable_nodes(&self.nodes, root)
+    }
+}
@@ -0,0 +1,270 @@
+use super::super::apply::node_cache::NodeCache;
+use super::super::apply::task_cache::{TaskCache, TaskCacheSlot};
+use super::super::apply::ApplyConfig;
+use super::super::core::{CancellationToken, Cancelled, Node, NodeIndex, Variable};
+use super::Zdd;
+use std::cmp::{max, min};
+
+/// Binary operations on families of sets.
+impl Zdd {
+    /// The sets which are in `self` or in `other`.
+    pub fn union(&self, other: &Zdd) -> Zdd {
+        uncancelled(set_operation(self, other, SetOperator::Union, None))
+    }
+
+    /// The sets which are both in `self` and in `other`.
+    pub fn intersection(&self, other: &Zdd) -> Zdd {
+        uncancelled(set_operation(self, other, SetOperator::Intersection, None))
+    }
+
+    /// The sets which are in `self`, but not in `other`.
+    pub fn difference(&self, other: &Zdd) -> Zdd {
+        uncancelled(set_operation(self, other, SetOperator::Difference, None))
+    }
+
+    /// The same as `Zdd::union`, but stops with an error once the `cancellation` token
+    /// is cancelled.
+    pub fn union_cancellable(
+        &self,
+        other: &Zdd,
+        cancellation: &CancellationToken,
+    ) -> Result<Zdd, Cancelled> {
+        set_operation(self, other, SetOperator::Union, Some(cancellation))
+    }
+
+    /// The same as `Zdd::intersection`, but stops with an error once the `cancellation` token
+    /// is cancelled.
+    pub fn intersection_cancellable(
+        &self,
+        other: &Zdd,
+        cancellation: &CancellationToken,
+    ) -> Result<Zdd, Cancelled> {
+        set_operation(self, other, SetOperator::Intersection, Some(cancellation))
+    }
+
+    /// The same as `Zdd::difference`, but stops with an error once the `cancellation` token
+    /// is cancelled.
+    pub fn difference_cancellable(
+        &self,
+        other: &Zdd,
+        cancellation: &CancellationToken,
+    ) -> Result<Zdd, Cancelled> {
+        set_operation(self, other, SetOperator::Difference, Some(cancellation))
+    }
+}
+
+fn uncancelled(result: Result<Zdd, Cancelled>) -> Zdd {
+    match result {
+        Ok(result) => result,
+        Err(Cancelled) => unreachable!("Set operation without a token cannot be cancelled."),
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum SetOperator {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl SetOperator {
+    /// Return the result of the operation if it is a terminal node that can be determined
+    /// without expanding the task. Otherwise returns `NodeIndex::UNDEFINED`.
+    ///
+    /// Note that unlike in the BDD `apply`, the `1` terminal does not absorb anything,
+    /// because it represents the family with only the empty set.
+    #[inline]
+    fn resolve(&self, left: NodeIndex, right: NodeIndex) -> NodeIndex {
+        let is_terminal = |x: NodeIndex| x.is_zero() || x.is_one();
+        let result = match self {
+            SetOperator::Union if is_terminal(left) && is_terminal(right) => {
+                left.is_one() || right.is_one()
+            }
+            SetOperator::Intersection if left.is_zero() || right.is_zero() => false,
+            SetOperator::Intersection if is_terminal(left) && is_terminal(right) => true,
+            SetOperator::Difference if left.is_zero() || (left.is_one() && right.is_one()) => false,
+            SetOperator::Difference if left.is_one() && right.is_zero() => true,
+            _ => return NodeIndex::UNDEFINED,
+        };
+        if result {
+            NodeIndex::ONE
+        } else {
+            NodeIndex::ZERO
+        }
+    }
+}
+
+/// A single task of the set operation. Same as in the BDD `apply`, `offset` is the distance
+/// to the parent task on the stack, and the two results are stored in reverse order.
+struct SetTask {
+    offset: usize,
+    task: (NodeIndex, NodeIndex),
+    variable: Variable,
+    results: [NodeIndex; 2],
+    task_cache_slot: TaskCacheSlot,
+}
+
+impl SetTask {
+    fn new(offset: usize, task: (NodeIndex, NodeIndex)) -> SetTask {
+        SetTask {
+            offset,
+            task,
+            variable: Variable::UNDEFINED,
+            results: [NodeIndex::UNDEFINED, NodeIndex::UNDEFINED],
+            task_cache_slot: TaskCacheSlot::UNDEFINED,
+        }
+    }
+}
+
+/// The `(low, high)` cofactors of the node w.r.t. the given `variable`. If the node does not
+/// test the variable, the variable is not in any of its sets, so the high cofactor is empty.
+#[inline]
+fn cofactors(zdd: &Zdd, index: NodeIndex, variable: Variable) -> (NodeIndex, NodeIndex) {
+    let (node_variable, low, high) = zdd.get_node(index).unpack();
+    if node_variable == variable {
+        (low, high)
+    } else {
+        (index, NodeIndex::ZERO)
+    }
+}
+
+/// A variant of the BDD `apply` algorithm with the ZDD cofactors and reduction rule.
+///
+/// The `cancellation` token is polled every time the caches are checked for growth.
+fn set_operation(
+    left_zdd: &Zdd,
+    right_zdd: &Zdd,
+    operator: SetOperator,
+    cancellation: Option<&CancellationToken>,
+) -> Result<Zdd, Cancelled> {
+    let mut task_cache = TaskCache::new(left_zdd.get_size());
+    let mut node_cache = NodeCache::new(max(left_zdd.get_size() / 2, 2));
+
+    let mut stack = vec![SetTask::new(0, (left_zdd.get_root_index(), right_zdd.get_root_index()))];
+    let config = ApplyConfig::default();
+    let root_result;
+    'main: loop {
+        if let Some(cancellation) = cancellation {
+            cancellation.check()?;
+        }
+        // Same as in the BDD `apply`, the caches are only checked once per block of iterations.
+        let base_iterations = max(
+            left_zdd.get_size() >> config.check_interval_shift,
+            config.min_check_interval,
+        );
+        let node_iterations = node_cache.ensure_capacity(base_iterations);
+        let task_iterations = max(task_cache.grow_if_necessary(), base_iterations);
+        for _ in 0..min(node_iterations, task_iterations) {
+            let top = stack.last_mut().unwrap();
+            let mut result;
+            if top.variable.is_undefined() {
+                let (left, right) = top.task;
+                result = operator.resolve(left, right);
+                if result.is_undefined() {
+                    let (cached, slot) = task_cache.read(top.task);
+                    if !cached.is_undefined() {
+                        result = cached;
+                    } else {
+                        top.task_cache_slot = slot;
+                        let variable = min(
+                            left_zdd.get_node(left).get_variable(),
+                            right_zdd.get_node(right).get_variable(),
+                        );
+                        top.variable = variable;
+                        let (l_low, l_high) = cofactors(left_zdd, left, variable);
+                        let (r_low, r_high) = cofactors(right_zdd, right, variable);
+                        stack.push(SetTask::new(1, (l_high, r_high)));
+                        stack.push(SetTask::new(2, (l_low, r_low)));
+                    }
+                }
+            } else {
+                let (result_low, result_high) = (top.results[1], top.results[0]);
+                if result_high.is_zero() {
+                    result = result_low;
+                } else {
+                    let node = Node::pack(top.variable, result_low, result_high);
+                    let mut cached = node_cache.ensure(&node);
+                    while let Err(slot) = cached {
+                        cached = node_cache.ensure_at(&node, slot);
+                    }
+                    result = cached.unwrap();
+                }
+                task_cache.write(top.task_cache_slot, top.task, result);
+            }
+
+            if !result.is_undefined() {
+                let offset = stack.pop().unwrap().offset;
+                if stack.is_empty() {
+                    root_result = result;
+                    break 'main;
+                }
+                let parent = stack.len() - offset;
+                stack[parent].results[offset - 1] = result;
+            }
+        }
+    }
+
+    Ok(Zdd::from_graph(node_cache.export(root_result)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::Zdd;
+    use crate::v4::core::{CancellationToken, Variable};
+
+    fn family(sets: &[&[u32]]) -> Zdd {
+        sets.iter().fold(Zdd::new_empty(), |result, set| {
+            let set: Vec<Variable> = set.iter().map(|v| Variable::from(*v)).collect();
+            result.union(&Zdd::new_set(&set))
+        })
+    }
+
+    fn members(zdd: &Zdd, sets: &[&[u32]]) -> Vec<bool> {
+        sets.iter()
+            .map(|set| {
+                let set: Vec<Variable> = set.iter().map(|v| Variable::from(*v)).collect();
+                zdd.contains(&set)
+            })
+            .collect()
+    }
+
+    #[test]
+    pub fn zdd_set_operations() {
+        let all: [&[u32]; 6] = [&[], &[1], &[2], &[1, 2], &[1, 3], &[2, 3, 4]];
+        let a = family(&[&[], &[1], &[1, 2], &[2, 3, 4]]);
+        let b = family(&[&[1], &[2], &[1, 3], &[2, 3, 4]]);
+        assert_eq!(Some(4), a.count());
+
+        let union = a.union(&b);
+        assert_eq!(Some(6), union.count());
+        assert_eq!(vec![true; 6], members(&union, &all));
+
+        let intersection = a.intersection(&b);
+        assert_eq!(Some(2), intersection.count());
+        assert_eq!(vec![false, true, false, false, false, true], members(&intersection, &all));
+
+        let difference = a.difference(&b);
+        assert_eq!(Some(2), difference.count());
+        assert_eq!(vec![true, false, false, true, false, false], members(&difference, &all));
+
+        // The result is canonical, so the same family yields the same graph size.
+        let rebuilt = difference.union(&intersection);
+        assert_eq!(a.get_size(), rebuilt.get_size());
+        assert_eq!(Some(4), rebuilt.count());
+        assert!(a.difference(&a).is_empty());
+        assert!(a.intersection(&Zdd::new_unit()).is_unit());
+        assert!(b.intersection(&Zdd::new_unit()).is_empty());
+    }
+
+    #[test]
+    pub fn zdd_set_operations_cancellation() {
+        let a = family(&[&[], &[1], &[1, 2], &[2, 3, 4]]);
+        let b = family(&[&[1], &[2], &[1, 3], &[2, 3, 4]]);
+        let token = CancellationToken::new();
+        assert_eq!(a.union(&b).count(), a.union_cancellable(&b, &token).unwrap().count());
+        token.cancel();
+        assert!(a.union_cancellable(&b, &token).is_err());
+        assert!(a.intersection_cancellable(&b, &token).is_err());
+        assert!(a.difference_cancellable(&b, &token).is_err());
+    }
+}
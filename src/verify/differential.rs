@@ -0,0 +1,281 @@
+use super::random::{change_layout, random_bdd, random_formula, random_table, BddShape, Lcg, NodeLayout};
+use super::FunctionTable;
+use crate::convert::ConversionError;
+use crate::engine::{BddEngine, LibBddEngine, MachineEngine, V2Engine, V3OooEngine};
+use crate::v4::apply::{
+    apply, apply_with_config, find_witness, parallel_apply, ApplyConfig, ApplyContext, ApplyEngine, TruthTable,
+};
+use crate::v4::complement::{self, ComplementBdd};
+use crate::v4::core::Bdd;
+use crate::IntoIndex;
+use biodivine_lib_bdd::BddValuation;
+use std::convert::TryFrom;
+
+/// Compare all `apply` implementations with the `FunctionTable` reference on `pairs` random
+/// pairs of BDDs with up to `max_variables` variables, generated from the given `seed`.
+///
+/// The `v4` implementations are checked for all 16 binary operators, including the size
+/// of the result (`v4` diagrams are canonical, so they must have the same size as the
+/// reference BDD). The remaining engines (`v3`, `v2`, `machine` and `biodivine_lib_bdd`)
+/// are checked for the operators supported by `BddEngine`. The operands use all node layouts,
+/// not just the pre-order which is assumed (but not required) by `v4`.
+///
+/// Returns the number of performed checks, or a description of the first mismatch.
+pub fn differential_test(pairs: usize, max_variables: u32, seed: u64) -> Result<usize, String> {
+    assert!((1..=FunctionTable::MAX_VARIABLES).contains(&max_variables));
+    let mut rng = Lcg::new(seed);
+    let mut contexts = Contexts::new();
+    let mut checks = 0;
+    for pair in 0..pairs {
+        let variables = 1 + rng.next_below(max_variables);
+        let left = random_operand(variables, &mut rng);
+        let right = random_operand(variables, &mut rng);
+        checks += check_pair(&mut contexts, left, right).map_err(|error| {
+            format!("Pair {} of seed {} ({} variables): {}", pair, seed, variables, error)
+        })?;
+    }
+    Ok(checks)
+}
+
+/// **(internal)** Persistent `ApplyContext` objects, which are reused for all pairs such that
+/// the reuse of their caches is also tested.
+struct Contexts {
+    in_order: ApplyContext,
+    out_of_order: ApplyContext,
+}
+
+impl Contexts {
+    fn new() -> Contexts {
+        Contexts {
+            in_order: ApplyContext::new(),
+            out_of_order: ApplyContext::with_config(ApplyConfig {
+                engine: ApplyEngine::OutOfOrder,
+                ..ApplyConfig::default()
+            }),
+        }
+    }
+}
+
+/// **(internal)** A random function given by a dense random table, a random formula or
+/// `random_bdd`, which produce very different BDD shapes. The nodes of the returned BDD
+/// are stored in a random `NodeLayout`.
+fn random_operand(variables: u32, rng: &mut Lcg) -> (FunctionTable, Bdd) {
+    let table = match rng.next_below(3) {
+        0 => {
+            let density = u64::from(rng.next_below(101));
+            random_table(variables, density, rng)
+        }
+        1 => {
+            let operations = rng.next_below(4 * variables) as usize;
+            random_formula(variables, operations, rng)
+        }
+        _ => {
+            let shape = BddShape {
+                variables,
+                nodes: u64::from(rng.next_below(1 << variables)),
+                density: u64::from(rng.next_below(101)),
+                sharing: u64::from(rng.next_below(101)),
+                layout: NodeLayout::PreOrder,
+            };
+            FunctionTable::from_bdd(&random_bdd(&shape, rng), variables)
+        }
+    };
+    let layouts = [
+        NodeLayout::PreOrder,
+        NodeLayout::PostOrder,
+        NodeLayout::ReversedPreOrder,
+        NodeLayout::Shuffled,
+    ];
+    let layout = layouts[rng.next_below(4).into_index()];
+    let bdd = change_layout(&table.to_bdd(), layout, rng);
+    (table, bdd)
+}
+
+/// **(internal)** Run all checks for a single pair of operands.
+fn check_pair(
+    contexts: &mut Contexts,
+    (left, left_bdd): (FunctionTable, Bdd),
+    (right, right_bdd): (FunctionTable, Bdd),
+) -> Result<usize, String> {
+    let (left, right) = (&left, &right);
+    let mut checks = 0;
+    for table in 0..16 {
+        let operator = TruthTable::new(table);
+        let expected = left.apply(right, |l, r| operator.eval(l, r));
+        checks += check_v4(contexts, &left_bdd, &right_bdd, operator, &expected)
+            .map_err(|error| format!("{:?}: {}", operator, error))?;
+    }
+    let variables = left.variables();
+    let operands = (left, &left_bdd, right, &right_bdd);
+    checks += check_engine::<V3OooEngine, _>(operands, |bdd| converted(bdd, variables))?;
+    checks += check_engine::<V2Engine, _>(operands, |bdd| converted(bdd, variables))?;
+    checks += check_engine::<MachineEngine, _>(operands, |bdd| converted(bdd, variables))?;
+    checks += check_engine::<LibBddEngine, _>(operands, |bdd| {
+        let values = FunctionTable::from_fn(variables, |index| {
+            bdd.eval_in(&BddValuation::new(left.valuation(index)))
+        });
+        Ok(values)
+    })?;
+    Ok(checks)
+}
+
+/// **(internal)** Check every `v4` implementation of `apply` for the given `operator`.
+fn check_v4(
+    contexts: &mut Contexts,
+    left: &Bdd,
+    right: &Bdd,
+    operator: TruthTable,
+    expected: &FunctionTable,
+) -> Result<usize, String> {
+    let out_of_order = ApplyConfig {
+        engine: ApplyEngine::OutOfOrder,
+        ..ApplyConfig::default()
+    };
+    let prefetch = ApplyConfig {
+        prefetch: true,
+        ..ApplyConfig::default()
+    };
+    // Caches that have to grow many times, checked as often as possible.
+    let tiny_caches = ApplyConfig {
+        task_cache_capacity: Some(1),
+        node_cache_capacity: Some(1),
+        task_cache_load_factor: 1,
+        check_interval_shift: 63,
+        min_check_interval: 1,
+        ..ApplyConfig::default()
+    };
+    let with_config = |config: &ApplyConfig| {
+        apply_with_config(left, right, operator, config)
+            .map(|(result, _)| result)
+            .map_err(|error| format!("Unexpected error: {:?}", error))
+    };
+    let complement = complement::apply(&ComplementBdd::from(left), &ComplementBdd::from(right), operator).0;
+
+    let results = vec![
+        ("apply", apply(left, right, operator).0),
+        ("apply (out-of-order)", with_config(&out_of_order)?),
+        ("apply (prefetch)", with_config(&prefetch)?),
+        ("apply (tiny caches)", with_config(&tiny_caches)?),
+        ("ApplyContext (in-order)", contexts.in_order.apply(left, right, operator).0),
+        ("ApplyContext (out-of-order)", contexts.out_of_order.apply(left, right, operator).0),
+        ("parallel_apply", parallel_apply(left, right, operator, 2).0),
+        ("complement::apply", Bdd::from(&complement)),
+    ];
+    let expected_size = expected.to_bdd().get_size();
+    let variables = expected.variables();
+    for (name, result) in &results {
+        check_table(name, &FunctionTable::from_bdd(result, variables), expected)?;
+        if result.get_size() != expected_size {
+            return Err(format!(
+                "{}: result has {} nodes, but the reduced BDD has {}.",
+                name,
+                result.get_size(),
+                expected_size
+            ));
+        }
+    }
+
+    let result = &results[0].1;
+    check_table("sort_preorder", &FunctionTable::from_bdd(&result.sort_preorder(), variables), expected)?;
+    check_table("sort_postorder", &FunctionTable::from_bdd(&result.sort_postorder(), variables), expected)?;
+
+    match find_witness(left, right, operator) {
+        None if !expected.is_false() => return Err("find_witness: no witness found.".to_string()),
+        Some(witness) => {
+            let mut valuation = vec![false; variables as usize];
+            for (variable, value) in witness {
+                valuation[u32::from(variable) as usize] = value;
+            }
+            if !expected.get(FunctionTable::index_of(&valuation)) {
+                return Err(format!("find_witness: {:?} is not a witness.", valuation));
+            }
+        }
+        None => {}
+    }
+
+    Ok(results.len() + 3)
+}
+
+/// **(internal)** A named binary operation of a `BddEngine` with its reference semantics.
+type EngineOperation<E> = (
+    &'static str,
+    fn(bool, bool) -> bool,
+    fn(&mut E, &<E as BddEngine>::Bdd, &<E as BddEngine>::Bdd) -> <E as BddEngine>::Bdd,
+);
+
+/// **(internal)** Check the binary operations of the engine `E`. The engine results are
+/// converted back to tables using `to_table`.
+fn check_engine<E, F>(
+    (left, left_bdd, right, right_bdd): (&FunctionTable, &Bdd, &FunctionTable, &Bdd),
+    to_table: F,
+) -> Result<usize, String>
+where
+    E: BddEngine,
+    F: Fn(&E::Bdd) -> Result<FunctionTable, String>,
+{
+    let operations: [EngineOperation<E>; 6] = [
+        ("and", |l, r| l && r, |e, l, r| e.and(l, r)),
+        ("or", |l, r| l || r, |e, l, r| e.or(l, r)),
+        ("xor", |l, r| l ^ r, |e, l, r| e.xor(l, r)),
+        ("iff", |l, r| l == r, |e, l, r| e.iff(l, r)),
+        ("imp", |l, r| !l || r, |e, l, r| e.imp(l, r)),
+        ("and_not", |l, r| l && !r, |e, l, r| e.and_not(l, r)),
+    ];
+    let mut engine = E::new(left.variables() as u16);
+    let left_bdd = engine.import(left_bdd);
+    let right_bdd = engine.import(right_bdd);
+    for (name, function, operation) in &operations {
+        let result = operation(&mut engine, &left_bdd, &right_bdd);
+        let name = format!("{} {}", E::NAME, name);
+        let result = to_table(&result).map_err(|error| format!("{}: {}", name, error))?;
+        check_table(&name, &result, &left.apply(right, function))?;
+    }
+    Ok(operations.len())
+}
+
+/// **(internal)** Convert a BDD of another representation to a table (through `v4`).
+fn converted<B>(bdd: &B, variables: u32) -> Result<FunctionTable, String>
+where
+    for<'a> Bdd: TryFrom<&'a B, Error = ConversionError>,
+{
+    Bdd::try_from(bdd)
+        .map(|bdd| FunctionTable::from_bdd(&bdd, variables))
+        .map_err(|error| format!("Conversion failed: {:?}", error))
+}
+
+/// **(internal)** Compare a `result` with the `expected` table.
+fn check_table(name: &str, result: &FunctionTable, expected: &FunctionTable) -> Result<(), String> {
+    if result == expected {
+        return Ok(());
+    }
+    let index = (0..(1u64 << expected.variables()))
+        .find(|index| result.get(*index) != expected.get(*index))
+        .unwrap();
+    Err(format!(
+        "{}: the result is {} for {:?}, expected {}.",
+        name,
+        result.get(index),
+        expected.valuation(index),
+        expected.get(index)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::differential_test;
+
+    #[test]
+    fn small_differential_test() {
+        match differential_test(1000, 8, 42) {
+            Ok(checks) => assert!(checks > 1000),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    #[test]
+    fn large_differential_test() {
+        if let Err(error) = differential_test(4, 14, 7) {
+            panic!("{}", error);
+        }
+    }
+}
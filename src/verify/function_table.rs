@@ -0,0 +1,184 @@
+use super::random::Lcg;
+use crate::v4::core::{Bdd, Node, NodeIndex, Variable};
+use crate::FromIndex;
+use fxhash::FxHashMap;
+
+/// A reference representation of a Boolean function as an explicit truth table.
+///
+/// The table is indexed by valuations, where variable `0` corresponds to the most significant
+/// bit of the index (i.e. variable `0` is closest to the root). Since the table grows
+/// exponentially, it is only usable up to `MAX_VARIABLES` variables.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FunctionTable {
+    variables: u32,
+    /// The values of the function, 64 valuations per word. Unused bits are always zero.
+    words: Vec<u64>,
+}
+
+impl FunctionTable {
+    /// The largest supported number of variables.
+    pub const MAX_VARIABLES: u32 = 20;
+
+    /// A table computed by evaluating `function` for every valuation index.
+    pub fn from_fn<F: FnMut(u64) -> bool>(variables: u32, mut function: F) -> FunctionTable {
+        assert!(variables <= Self::MAX_VARIABLES);
+        let size = 1u64 << variables;
+        let mut words = vec![0u64; size.div_ceil(64) as usize];
+        for index in 0..size {
+            if function(index) {
+                words[(index / 64) as usize] |= 1 << (index % 64);
+            }
+        }
+        FunctionTable { variables, words }
+    }
+
+    /// A constant function.
+    pub fn constant(variables: u32, value: bool) -> FunctionTable {
+        FunctionTable::from_fn(variables, |_| value)
+    }
+
+    /// The function `variable = value`.
+    pub fn literal(variables: u32, variable: u32, value: bool) -> FunctionTable {
+        assert!(variable < variables);
+        let bit = 1u64 << (variables - 1 - variable);
+        FunctionTable::from_fn(variables, |index| (index & bit != 0) == value)
+    }
+
+    /// A random function where roughly `density` percent of valuations are `true`.
+    pub fn random(variables: u32, density: u64, rng: &mut Lcg) -> FunctionTable {
+        FunctionTable::from_fn(variables, |_| rng.next_percent(density))
+    }
+
+    /// The function represented by the given `Bdd`. All its variables must be smaller
+    /// than `variables`.
+    pub fn from_bdd(bdd: &Bdd, variables: u32) -> FunctionTable {
+        FunctionTable::from_fn(variables, |index| {
+            let mut node = bdd.get_root_index();
+            while !(node.is_zero() || node.is_one()) {
+                let (variable, low, high) = bdd.get_node(node).unpack();
+                let variable = u32::from(variable);
+                assert!(variable < variables, "Variable {} out of range.", variable);
+                node = if index & (1 << (variables - 1 - variable)) != 0 { high } else { low };
+            }
+            node.is_one()
+        })
+    }
+
+    /// The number of variables of this function.
+    pub fn variables(&self) -> u32 {
+        self.variables
+    }
+
+    /// The value of the function for the given valuation index.
+    pub fn get(&self, index: u64) -> bool {
+        (self.words[(index / 64) as usize] >> (index % 64)) & 1 == 1
+    }
+
+    /// True if the function has no satisfying valuation.
+    pub fn is_false(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+
+    /// The negation of this function.
+    pub fn not(&self) -> FunctionTable {
+        self.apply(self, |l, _| !l)
+    }
+
+    /// Apply the binary Boolean `function` to every valuation of the two tables.
+    pub fn apply<F: Fn(bool, bool) -> bool>(&self, other: &FunctionTable, function: F) -> FunctionTable {
+        assert_eq!(self.variables, other.variables);
+        // Select the valuations where the result is `true` using bitwise operations.
+        let cases = [
+            (function(false, false), !0u64, !0u64),
+            (function(false, true), !0u64, 0),
+            (function(true, false), 0, !0u64),
+            (function(true, true), 0, 0),
+        ];
+        let size = 1u64 << self.variables;
+        let unused_mask = if size < 64 { (1u64 << size) - 1 } else { !0u64 };
+        let words = self
+            .words
+            .iter()
+            .zip(&other.words)
+            .map(|(l, r)| {
+                let mut word = 0;
+                for (value, l_flip, r_flip) in &cases {
+                    if *value {
+                        word |= (l ^ l_flip) & (r ^ r_flip);
+                    }
+                }
+                word & unused_mask
+            })
+            .collect();
+        FunctionTable {
+            variables: self.variables,
+            words,
+        }
+    }
+
+    /// Build the reduced `Bdd` of this function using Shannon expansion. The nodes are
+    /// sorted in DFS pre-order.
+    pub fn to_bdd(&self) -> Bdd {
+        let mut nodes = vec![Node::ZERO, Node::ONE];
+        let mut unique = FxHashMap::default();
+        let root = self.build(0, 0, &mut nodes, &mut unique);
+        Bdd::from_reachable_nodes(&nodes, root)
+    }
+
+    /// **(internal)** Build the sub-function of the given `variable` level which starts
+    /// at valuation index `offset`.
+    fn build(
+        &self,
+        variable: u32,
+        offset: u64,
+        nodes: &mut Vec<Node>,
+        unique: &mut FxHashMap<Node, NodeIndex>,
+    ) -> NodeIndex {
+        if variable == self.variables {
+            return if self.get(offset) { NodeIndex::ONE } else { NodeIndex::ZERO };
+        }
+        let half = 1u64 << (self.variables - 1 - variable);
+        let low = self.build(variable + 1, offset, nodes, unique);
+        let high = self.build(variable + 1, offset + half, nodes, unique);
+        if low == high {
+            return low;
+        }
+        let node = Node::pack(Variable::from(variable), low, high);
+        *unique.entry(node.clone()).or_insert_with(|| {
+            nodes.push(node);
+            NodeIndex::from_index(nodes.len() - 1)
+        })
+    }
+
+    /// A valuation (one value per variable) of the given index.
+    pub fn valuation(&self, index: u64) -> Vec<bool> {
+        (0..self.variables)
+            .map(|variable| index & (1 << (self.variables - 1 - variable)) != 0)
+            .collect()
+    }
+
+    /// The index of the given valuation.
+    pub fn index_of(valuation: &[bool]) -> u64 {
+        valuation
+            .iter()
+            .fold(0, |index, value| (index << 1) | u64::from(*value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FunctionTable;
+    use crate::verify::{random_formula, Lcg};
+
+    #[test]
+    fn function_table_round_trip() {
+        let mut rng = Lcg::new(3);
+        for variables in 0..8 {
+            let table = random_formula(variables, 10, &mut rng);
+            assert_eq!(table, FunctionTable::from_bdd(&table.to_bdd(), variables));
+            let negated = table.not();
+            assert_eq!(table, negated.not());
+            assert_ne!(table, negated);
+        }
+    }
+}
@@ -0,0 +1,17 @@
+//! A self-contained oracle for checking the correctness of the BDD implementations.
+//!
+//! The reference semantics is given by `FunctionTable`, an explicit truth table which is only
+//! usable for small functions, but is simple enough to be obviously correct. On top of that,
+//! `differential_test` compares every `apply` implementation of this crate (and the
+//! `biodivine_lib_bdd` engine) with the reference on many pseudo-random pairs of BDDs.
+//!
+//! Everything is deterministic: the same seed always produces the same inputs, so a failure
+//! can be reproduced using the seed reported in the error message.
+
+mod differential;
+mod function_table;
+mod random;
+
+pub use self::differential::differential_test;
+pub use self::function_table::FunctionTable;
+pub use self::random::{change_layout, random_bdd, random_formula, random_table, BddShape, Lcg, NodeLayout};
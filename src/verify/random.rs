@@ -0,0 +1,331 @@
+use super::FunctionTable;
+use crate::v4::apply::TruthTable;
+use crate::v4::core::{Bdd, Node, NodeIndex, Variable};
+use crate::{FromIndex, IntoIndex};
+use fxhash::FxHashMap;
+
+/// A deterministic pseudo-random number generator (a 64-bit LCG with the MMIX constants).
+///
+/// It is not suitable for anything beyond generating test inputs, but it is reproducible
+/// everywhere and needs no dependencies.
+#[derive(Clone, Debug)]
+pub struct Lcg {
+    state: u64,
+}
+
+impl Lcg {
+    /// Create a generator from the given `seed`.
+    pub fn new(seed: u64) -> Lcg {
+        Lcg { state: seed }
+    }
+
+    /// The next 32 random bits (the upper half of the LCG state, the lower bits are weak).
+    pub fn next_u32(&mut self) -> u32 {
+        self.state = self
+            .state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.state >> 32) as u32
+    }
+
+    /// A random number in `0..bound`. The bound must be positive.
+    pub fn next_below(&mut self, bound: u32) -> u32 {
+        assert!(bound > 0);
+        ((u64::from(self.next_u32()) * u64::from(bound)) >> 32) as u32
+    }
+
+    /// True with a probability of `percent / 100`.
+    pub fn next_percent(&mut self, percent: u64) -> bool {
+        u64::from(self.next_below(100)) < percent
+    }
+}
+
+/// A random function with the given number of `variables` where roughly `density` percent
+/// of valuations are `true`. The BDDs of such functions are close to the worst-case size.
+pub fn random_table(variables: u32, density: u64, rng: &mut Lcg) -> FunctionTable {
+    FunctionTable::random(variables, density, rng)
+}
+
+/// A random function built by `operations` random binary operators. Every operator combines
+/// two random sub-formulas created so far (initially only literals). Compared to `random_table`,
+/// such functions have smaller BDDs with more sharing, which are closer to the BDDs seen
+/// in practice.
+pub fn random_formula(variables: u32, operations: usize, rng: &mut Lcg) -> FunctionTable {
+    if variables == 0 {
+        return FunctionTable::constant(0, rng.next_percent(50));
+    }
+    let mut formulas = vec![random_literal(variables, rng), random_literal(variables, rng)];
+    for _ in 0..operations {
+        let left = &formulas[rng.next_below(formulas.len() as u32) as usize];
+        let right = &formulas[rng.next_below(formulas.len() as u32) as usize];
+        let operator = TruthTable::new(rng.next_below(16) as u8);
+        let result = left.apply(right, |l, r| operator.eval(l, r));
+        formulas.push(result);
+        formulas.push(random_literal(variables, rng));
+    }
+    // The last literal is not used by any operator.
+    formulas.pop();
+    formulas.pop().unwrap()
+}
+
+/// The order in which the nodes of a `random_bdd` are stored.
+///
+/// The `v4` algorithms are fastest on BDDs sorted in DFS pre-order (this is also the order
+/// of their results), but they must work correctly for any order. The other layouts can be
+/// used to measure or test the behaviour on inputs which violate this assumption.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum NodeLayout {
+    /// The DFS pre-order produced by `Bdd::sort_preorder`.
+    PreOrder,
+    /// The DFS post-order produced by `Bdd::sort_postorder`.
+    PostOrder,
+    /// The reverse of the pre-order (except for the root, which is always the last node),
+    /// i.e. the children of a node are typically stored after it instead of before it.
+    ReversedPreOrder,
+    /// A uniformly random permutation of the decision nodes.
+    Shuffled,
+}
+
+/// The parameters of `random_bdd`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BddShape {
+    /// The number of variables the BDD can use.
+    pub variables: u32,
+    /// The approximate number of decision nodes. The actual number is smaller when there
+    /// are not enough variables for so many nodes, or when some of the generated nodes turn
+    /// out to be redundant (equivalent to another node or with equal children).
+    pub nodes: u64,
+    /// The percentage of terminal edges which lead to the `1` terminal.
+    pub density: u64,
+    /// The probability (in percent) that an edge which is not used by a new node is redirected
+    /// to an existing node of the next variable. This is repeated for every variable and the
+    /// edges that are never redirected lead to the terminals. With no sharing, the unreduced
+    /// diagram is a tree.
+    pub sharing: u64,
+    /// The order of the nodes in the resulting `Bdd`.
+    pub layout: NodeLayout,
+}
+
+impl Default for BddShape {
+    fn default() -> Self {
+        BddShape {
+            variables: 16,
+            nodes: 1000,
+            density: 50,
+            sharing: 20,
+            layout: NodeLayout::PreOrder,
+        }
+    }
+}
+
+/// Generate a random reduced `Bdd` with the given `shape`.
+///
+/// The diagram is generated top-down: the nodes are distributed among the variables such that
+/// the `k`-th variable has at most `2^k` nodes. Every new node becomes the target of one of the
+/// unused edges of the nodes above it, so all nodes are reachable. The remaining edges can
+/// be shared (see `BddShape::sharing`), and the edges that are still unused at the end lead
+/// to the terminals. Finally, the diagram is reduced. Unlike `random_table`, this is not
+/// limited to a small number of variables.
+pub fn random_bdd(shape: &BddShape, rng: &mut Lcg) -> Bdd {
+    if shape.variables == 0 || shape.nodes == 0 {
+        return if rng.next_percent(shape.density) { Bdd::new_one() } else { Bdd::new_zero() };
+    }
+
+    // The number of nodes of every variable.
+    let mut counts = vec![0u64; shape.variables.into_index()];
+    counts[0] = 1;
+    let level_capacity = |variable: usize| 1u64 << variable.min(63);
+    for _ in 1..shape.nodes {
+        let start = rng.next_below(shape.variables).into_index();
+        let free = (0..counts.len())
+            .map(|offset| (start + offset) % counts.len())
+            .find(|variable| counts[*variable] < level_capacity(*variable));
+        match free {
+            Some(variable) => counts[variable] += 1,
+            None => break,
+        }
+    }
+
+    // The unreduced diagram: `(variable, [low, high])` for every node, where the node `i`
+    // has the id `i + 2` and the ids `0` and `1` are the terminals.
+    let mut nodes: Vec<(u32, [usize; 2])> = Vec::new();
+    // Edges (`(node, child)` positions) which do not have a target yet.
+    let mut unused: Vec<(usize, usize)> = Vec::new();
+    // Nodes which could not be created at one level are created at the level below.
+    let mut pending = 0;
+    for variable in 0..shape.variables {
+        pending += counts[variable.into_index()];
+        let level_start = nodes.len();
+        while pending > 0 && (nodes.is_empty() || !unused.is_empty()) {
+            if !nodes.is_empty() {
+                let (node, child) = unused.swap_remove(rng.next_below(unused.len() as u32).into_index());
+                nodes[node].1[child] = nodes.len() + 2;
+            }
+            nodes.push((variable, [usize::MAX; 2]));
+            pending -= 1;
+        }
+        let level_size = nodes.len() - level_start;
+        if level_size == 0 {
+            continue;
+        }
+        // Share the remaining edges of the nodes above this level.
+        let mut i = 0;
+        while i < unused.len() {
+            let (node, child) = unused[i];
+            let target = level_start + 2 + rng.next_below(level_size as u32).into_index();
+            let sibling = nodes[node].1[1 - child];
+            if sibling != target && rng.next_percent(shape.sharing) {
+                nodes[node].1[child] = target;
+                unused.swap_remove(i);
+            } else {
+                i += 1;
+            }
+        }
+        // The edges of the new nodes can only be used by the variables below.
+        unused.extend((level_start..nodes.len()).flat_map(|node| vec![(node, 0), (node, 1)]));
+    }
+
+    // The remaining edges lead to the terminals. If both edges of a node are unused,
+    // they lead to different terminals.
+    for (node, child) in unused {
+        let sibling = nodes[node].1[1 - child];
+        nodes[node].1[child] = if sibling < 2 {
+            1 - sibling
+        } else {
+            usize::from(rng.next_percent(shape.density))
+        };
+    }
+
+    // Reduce the diagram. The children are always created after their parents, so we can
+    // process the nodes in reverse.
+    let mut reduced = vec![Node::ZERO, Node::ONE];
+    let mut unique: FxHashMap<Node, NodeIndex> = FxHashMap::default();
+    let mut new_index = vec![NodeIndex::UNDEFINED; nodes.len() + 2];
+    new_index[0] = NodeIndex::ZERO;
+    new_index[1] = NodeIndex::ONE;
+    for (i, (variable, [low, high])) in nodes.iter().enumerate().rev() {
+        let (low, high) = (new_index[*low], new_index[*high]);
+        new_index[i + 2] = if low == high {
+            low
+        } else {
+            let node = Node::pack(Variable::from(*variable), low, high);
+            *unique.entry(node.clone()).or_insert_with(|| {
+                reduced.push(node);
+                NodeIndex::from_index(reduced.len() - 1)
+            })
+        };
+    }
+    let bdd = Bdd::from_reachable_nodes(&reduced, new_index[2]);
+    change_layout(&bdd, shape.layout, rng)
+}
+
+/// A copy of the `bdd` with its nodes stored in the given `layout`. The random generator
+/// is only used for the `Shuffled` layout.
+pub fn change_layout(bdd: &Bdd, layout: NodeLayout, rng: &mut Lcg) -> Bdd {
+    if bdd.is_constant() {
+        return bdd.clone();
+    }
+    let root = bdd.get_size().into_index() - 1;
+    match layout {
+        NodeLayout::PreOrder => bdd.sort_preorder(),
+        NodeLayout::PostOrder => bdd.sort_postorder(),
+        NodeLayout::ReversedPreOrder => {
+            // The pre-order is stored in descending order, so we just reverse the decision nodes.
+            let shuffle: Vec<NodeIndex> = (0..=root)
+                .map(|index| if index < 2 || index == root { index } else { root + 1 - index })
+                .map(NodeIndex::from_index)
+                .collect();
+            unsafe { bdd.sort_preorder().shuffle_unchecked(&shuffle) }
+        }
+        NodeLayout::Shuffled => {
+            let mut shuffle: Vec<NodeIndex> = (0..=root).map(NodeIndex::from_index).collect();
+            // Fisher-Yates on the decision nodes, except for the root.
+            for i in (3..root).rev() {
+                let j = 2 + rng.next_below((i - 1) as u32).into_index();
+                shuffle.swap(i, j);
+            }
+            unsafe { bdd.shuffle_unchecked(&shuffle) }
+        }
+    }
+}
+
+/// **(internal)** A random positive or negative literal.
+fn random_literal(variables: u32, rng: &mut Lcg) -> FunctionTable {
+    let variable = rng.next_below(variables);
+    FunctionTable::literal(variables, variable, rng.next_percent(50))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{change_layout, random_bdd, BddShape, Lcg, NodeLayout};
+    use crate::v4::core::Bdd;
+    use crate::v4::test_util::nodes;
+    use crate::verify::FunctionTable;
+
+    const LAYOUTS: [NodeLayout; 4] = [
+        NodeLayout::PreOrder,
+        NodeLayout::PostOrder,
+        NodeLayout::ReversedPreOrder,
+        NodeLayout::Shuffled,
+    ];
+
+    #[test]
+    fn random_bdd_is_reduced() {
+        let mut rng = Lcg::new(5);
+        for variables in 0..12 {
+            for sharing in &[0, 30, 90] {
+                for layout in &LAYOUTS {
+                    let shape = BddShape {
+                        variables,
+                        nodes: 1 << (variables / 2 + 2),
+                        density: 30,
+                        sharing: *sharing,
+                        layout: *layout,
+                    };
+                    let bdd = random_bdd(&shape, &mut rng);
+                    assert!(Bdd::check_consistency_errors(&nodes(&bdd)).is_none());
+                    let table = FunctionTable::from_bdd(&bdd, variables);
+                    assert_eq!(table.to_bdd().get_size(), bdd.get_size());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn random_bdd_shape() {
+        let shape = BddShape {
+            variables: 40,
+            nodes: 10_000,
+            ..BddShape::default()
+        };
+        let bdd = random_bdd(&shape, &mut Lcg::new(1));
+        assert!(Bdd::check_consistency_errors(&nodes(&bdd)).is_none());
+        assert!(bdd.get_size() <= shape.nodes + 2);
+        assert!(bdd.get_size() >= shape.nodes / 2);
+        // The same seed gives the same BDD.
+        assert_eq!(nodes(&bdd), nodes(&random_bdd(&shape, &mut Lcg::new(1))));
+        assert_ne!(nodes(&bdd), nodes(&random_bdd(&shape, &mut Lcg::new(2))));
+    }
+
+    #[test]
+    fn layouts_preserve_function() {
+        let mut rng = Lcg::new(9);
+        let shape = BddShape {
+            variables: 10,
+            nodes: 200,
+            ..BddShape::default()
+        };
+        let bdd = random_bdd(&shape, &mut rng);
+        let table = FunctionTable::from_bdd(&bdd, 10);
+        for layout in &LAYOUTS {
+            let changed = change_layout(&bdd, *layout, &mut rng);
+            assert!(Bdd::check_consistency_errors(&nodes(&changed)).is_none());
+            assert_eq!(bdd.get_size(), changed.get_size());
+            assert_eq!(table, FunctionTable::from_bdd(&changed, 10));
+            if *layout != NodeLayout::PreOrder {
+                assert_ne!(nodes(&bdd), nodes(&changed));
+            }
+        }
+        assert_eq!(nodes(&bdd), nodes(&bdd.sort_preorder()));
+    }
+}